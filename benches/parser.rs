@@ -0,0 +1,115 @@
+// Benchmarks of the parser's hot paths: text extraction and whole statement
+// parses over real statements, row reconstruction, and amount and date
+// parsing. Run with `cargo bench`.
+use criterion::{criterion_group, criterion_main, Criterion};
+use hdfc_cc_parser_rs::{layout, parse, pdf_tools, statement, ParseOptions};
+use pdf::file::File as PdfFile;
+use std::env;
+use std::fs;
+use std::hint::black_box;
+use std::sync::mpsc;
+use std::thread;
+
+// Statements to benchmark extraction over, from $BENCH_STATEMENTS (default
+// benches/statements). Real statements carry personal data and aren't
+// committed; decrypted copies (see `decrypt`) avoid timing the decryption.
+fn statements() -> Vec<(String, Vec<u8>)> {
+    let dir = env::var("BENCH_STATEMENTS").unwrap_or_else(|_| "benches/statements".to_owned());
+    let mut statements: Vec<(String, Vec<u8>)> = match fs::read_dir(&dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "pdf"))
+            .filter_map(|path| {
+                let name = path.file_name()?.to_string_lossy().to_string();
+                Some((name, fs::read(&path).ok()?))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    if statements.is_empty() {
+        eprintln!(
+            "no statements in {}, skipping the extraction benchmarks",
+            dir
+        );
+    }
+    statements.sort();
+    statements
+}
+
+fn extraction(c: &mut Criterion) {
+    let name = env::var("BENCH_NAME").unwrap_or_default();
+    // rows are thrown away as they come, so parse never waits on the channel.
+    let (tx, rx) = mpsc::sync_channel(1000);
+    thread::spawn(move || rx.into_iter().for_each(drop));
+
+    for (file, data) in statements() {
+        c.bench_function(&format!("extract_positioned_text {}", file), |b| {
+            b.iter(|| {
+                let pdf = PdfFile::from_data_password(data.clone(), b"").unwrap();
+                for page in pdf.pages() {
+                    black_box(pdf_tools::extract_positioned_text(&page.unwrap(), &pdf).unwrap());
+                }
+            })
+        });
+        c.bench_function(&format!("parse {}", file), |b| {
+            b.iter(|| {
+                parse(
+                    file.clone(),
+                    data.clone(),
+                    name.clone(),
+                    &[String::new()],
+                    ParseOptions::default(),
+                    &tx,
+                )
+                .unwrap()
+            })
+        });
+    }
+}
+
+// A legacy layout statement of `n` rows, as the texts drawn on its pages.
+fn legacy_texts(n: usize) -> Vec<String> {
+    let mut texts = vec![
+        "Transaction Description".to_owned(),
+        "Amount (in Rs.)".to_owned(),
+    ];
+    for i in 0..n {
+        texts.push(format!("{:02}/{:02}", i % 28 + 1, i % 12 + 1));
+        texts.push(format!("SWIGGY LIMITED {} BANGALORE", i));
+        if i % 10 == 0 {
+            texts.push(format!("{},{:03}.00 Cr", i % 9 + 1, i % 1000));
+        } else {
+            texts.push(format!("{}.{:02}", i * 37 % 5000, i % 100));
+        }
+    }
+    texts.push("Reward Points Summary".to_owned());
+    texts
+}
+
+fn rows(c: &mut Criterion) {
+    let texts = legacy_texts(500);
+    let end = chrono::NaiveDate::from_ymd_opt(2024, 12, 31);
+    c.bench_function("rows hdfc-legacy 500", |b| {
+        b.iter(|| layout::HDFC_LEGACY.rows(black_box(&texts), end))
+    });
+}
+
+fn amounts_and_dates(c: &mut Criterion) {
+    c.bench_function("parse_amount", |b| {
+        b.iter(|| {
+            statement::parse_amount(black_box("1,23,456.78 Cr"));
+            statement::parse_amount(black_box("2,499.00"));
+        })
+    });
+    c.bench_function("parse_date with time", |b| {
+        b.iter(|| layout::HDFC.parse_date(black_box("12/03/2024 18:22:05"), None))
+    });
+    let end = chrono::NaiveDate::from_ymd_opt(2024, 1, 12);
+    c.bench_function("parse_date without year", |b| {
+        b.iter(|| layout::HDFC_LEGACY.parse_date(black_box("28/12"), end))
+    });
+}
+
+criterion_group!(benches, extraction, rows, amounts_and_dates);
+criterion_main!(benches);
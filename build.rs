@@ -0,0 +1,9 @@
+fn main() {
+    // the serve-grpc service, from proto/parser.proto, with a bundled protoc.
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/parser.proto");
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().unwrap());
+        tonic_build::compile_protos("proto/parser.proto").unwrap();
+    }
+}
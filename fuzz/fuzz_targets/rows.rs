@@ -0,0 +1,88 @@
+// Arbitrary text operators through the row state machine of every
+// `Rows::Columns` layout: it must not panic, and rows must only carry texts
+// it was fed. Run with `cargo fuzz run rows`.
+#![no_main]
+
+use arbitrary::Arbitrary;
+use hdfc_cc_parser_rs::layout::{Rows, LAYOUTS};
+use hdfc_cc_parser_rs::pdf_tools::TextOp;
+use hdfc_cc_parser_rs::ParserState;
+use libfuzzer_sys::fuzz_target;
+
+const NAME: &str = "JOHN DOE";
+
+// Texts statements draw, so inputs reach past the section and header
+// without the fuzzer having to guess them.
+const WORDS: [&str; 12] = [
+    "Domestic Transactions",
+    "International Transactions",
+    "Amount (in Rs.)",
+    NAME,
+    "",
+    "Cr",
+    "12/01/2024 18:22:05",
+    "28/12",
+    "1,23,456.78",
+    "- 25",
+    "SWIGGY LIMITED BANGALORE",
+    "Date",
+];
+
+#[derive(Debug, Arbitrary)]
+enum Token {
+    Begin,
+    End,
+    Word(u8),
+    Text(String),
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    layout: u8,
+    period_end: Option<(i32, u32, u32)>,
+    tokens: Vec<Token>,
+}
+
+fuzz_target!(|input: Input| {
+    let layouts: Vec<_> = LAYOUTS
+        .iter()
+        .filter(|layout| matches!(layout.rows, Rows::Columns))
+        .collect();
+    let layout = layouts[input.layout as usize % layouts.len()];
+    let period_end = input
+        .period_end
+        .and_then(|(y, m, d)| chrono::NaiveDate::from_ymd_opt(y, m, d));
+
+    let ops: Vec<TextOp> = input
+        .tokens
+        .into_iter()
+        .map(|token| match token {
+            Token::Begin => TextOp::BeginText,
+            Token::End => TextOp::EndText,
+            Token::Word(i) => TextOp::Text(WORDS[i as usize % WORDS.len()].to_owned()),
+            Token::Text(s) => TextOp::Text(s),
+        })
+        .collect();
+
+    let mut state = ParserState::new(NAME, layout, period_end);
+    let mut rows = 0;
+    for op in &ops {
+        if let Some(row) = state.step(op) {
+            rows += 1;
+            // a description is one text, never an accumulation of them.
+            assert!(
+                row.tx.is_empty()
+                    || ops
+                        .iter()
+                        .any(|op| matches!(op, TextOp::Text(s) if s.trim() == row.tx)),
+                "{:?}",
+                row.tx
+            );
+        }
+    }
+    let ends = ops
+        .iter()
+        .filter(|op| matches!(op, TextOp::EndText))
+        .count();
+    assert!(rows <= ends);
+});
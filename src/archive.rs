@@ -0,0 +1,71 @@
+use anyhow::{anyhow, Context, Error};
+use mail_parser::{MessageParser, MimeHeaders};
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+pub fn is_zip(path: &str) -> bool {
+    path.to_lowercase().ends_with(".zip")
+}
+
+pub fn is_eml(path: &str) -> bool {
+    path.to_lowercase().ends_with(".eml")
+}
+
+// Extract the PDFs in a ZIP archive, in name order, as (archive.zip/name, data).
+pub fn pdfs(path: &str, data: Vec<u8>) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let mut archive =
+        ZipArchive::new(Cursor::new(data)).context(format!("failed to open zip {}", path))?;
+
+    let mut pdfs = Vec::new();
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .context(format!("failed to read zip {}", path))?;
+        if !entry.is_file() || !entry.name().to_lowercase().ends_with(".pdf") {
+            continue;
+        }
+
+        let name = format!("{}/{}", path, entry.name());
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry
+            .read_to_end(&mut data)
+            .context(format!("failed to extract {}", name))?;
+        pdfs.push((name, data));
+    }
+
+    pdfs.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(pdfs)
+}
+
+// Extract the PDF attachments of an email, as (mail.eml/attachment name, data).
+pub fn eml_pdfs(path: &str, data: Vec<u8>) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let message = MessageParser::default()
+        .parse(&data)
+        .ok_or_else(|| anyhow!("failed to parse email {}", path))?;
+
+    let pdfs = message
+        .attachments()
+        .filter(|part| {
+            let by_name = part
+                .attachment_name()
+                .is_some_and(|name| name.to_lowercase().ends_with(".pdf"));
+            let by_type = part.content_type().is_some_and(|ct| {
+                ct.c_type.eq_ignore_ascii_case("application")
+                    && ct
+                        .subtype()
+                        .is_some_and(|st| st.eq_ignore_ascii_case("pdf"))
+            });
+            by_name || by_type
+        })
+        .enumerate()
+        .map(|(i, part)| {
+            let name = match part.attachment_name() {
+                Some(name) => format!("{}/{}", path, name),
+                None => format!("{}/attachment-{}.pdf", path, i + 1),
+            };
+            (name, part.contents().to_vec())
+        })
+        .collect();
+
+    Ok(pdfs)
+}
@@ -0,0 +1,318 @@
+use crate::layout::{self, Custom, Layout};
+use crate::statement::{amount_after, date_after, parse_points, table_values, Statement};
+use chrono::NaiveDate;
+use regex::Regex;
+
+// Banks selectable with --bank.
+pub const BANKS: [&str; 5] = ["hdfc", "hdfc-account", "icici", "sbi", "amex"];
+
+// Labels of an HDFC account statement's summary, in the order its values
+// follow.
+pub const ACCOUNT_SUMMARY: [&str; 6] = [
+    "Opening Balance",
+    "Dr Count",
+    "Cr Count",
+    "Debits",
+    "Credits",
+    "Closing Bal",
+];
+
+// Labels of Amex's "Membership Rewards" summary, in the order its values follow.
+const MEMBERSHIP_REWARDS: [&str; 4] = ["Opening Balance", "Earned", "Redeemed", "Closing Balance"];
+
+// One bank's statements: the layouts they come in and how to read the
+// statement details, which every bank words differently. Rows are read by
+// the layout, so the Transaction model and outputs are the same across banks.
+pub trait StatementParser: Sync {
+    fn name(&self) -> &'static str;
+
+    // Layouts, most specific first. The last one is used when none of them
+    // is detected.
+    fn layouts(&self) -> &'static [&'static Layout];
+
+    fn read_details(&self, statement: &mut Statement, texts: &[String]);
+
+    // How the bank derives statement passwords, shown when opening fails.
+    fn password_hint(&self) -> &'static str;
+}
+
+// The bank and layout a statement is detected as, and the layout's score
+// (see `Layout::score`), None when it's the fallback.
+#[derive(Clone, Copy)]
+pub struct Detection {
+    pub parser: &'static dyn StatementParser,
+    pub layout: &'static Layout,
+    pub score: Option<usize>,
+}
+
+pub struct Hdfc;
+
+impl StatementParser for Hdfc {
+    fn name(&self) -> &'static str {
+        "hdfc"
+    }
+
+    fn layouts(&self) -> &'static [&'static Layout] {
+        &layout::LAYOUTS
+    }
+
+    fn read_details(&self, statement: &mut Statement, texts: &[String]) {
+        statement.read_details(texts);
+    }
+
+    fn password_hint(&self) -> &'static str {
+        "the first 4 letters of the name on the card (uppercase) followed by the date of birth as DDMM"
+    }
+}
+
+pub struct Icici;
+
+impl StatementParser for Icici {
+    fn name(&self) -> &'static str {
+        "icici"
+    }
+
+    fn layouts(&self) -> &'static [&'static Layout] {
+        &[&layout::ICICI]
+    }
+
+    fn read_details(&self, statement: &mut Statement, texts: &[String]) {
+        let joined = texts.join("\n");
+
+        // the masked card number is printed without a label.
+        let card = Regex::new(r"\b(\d{4}X{4,8}\d{4})\b").unwrap();
+        statement.card_number = card.captures(&joined).map(|c| c[1].to_owned());
+        statement.statement_date =
+            date_after(&joined, "Statement Date").or_else(|| date_after(&joined, "STATEMENT DATE"));
+        statement.due_date = date_after(&joined, "Payment Due Date")
+            .or_else(|| date_after(&joined, "PAYMENT DUE DATE"));
+
+        statement.opening_balance = amount_after(texts, "Previous Balance");
+        statement.payments = amount_after(texts, "Payments/Credits");
+        statement.purchases = amount_after(texts, "Purchases/Charges");
+        statement.finance_charges = amount_after(texts, "Interest");
+        statement.total_dues = amount_after(texts, "Total Amount due");
+        statement.minimum_due = amount_after(texts, "Minimum Amount due");
+
+        statement.warn_missing();
+    }
+
+    fn password_hint(&self) -> &'static str {
+        "the first 4 letters of the name on the card (lowercase) followed by the date of birth as DDMM"
+    }
+}
+
+pub struct Sbi;
+
+impl StatementParser for Sbi {
+    fn name(&self) -> &'static str {
+        "sbi"
+    }
+
+    fn layouts(&self) -> &'static [&'static Layout] {
+        &[&layout::SBI]
+    }
+
+    fn read_details(&self, statement: &mut Statement, texts: &[String]) {
+        let joined = texts.join("\n");
+
+        let card = Regex::new(r"\b((?:X{4} ?){3}X{0,2}\d{2,4})\b").unwrap();
+        statement.card_number = card.captures(&joined).map(|c| c[1].to_owned());
+        statement.statement_date = date_after(&joined, "Statement Date");
+        statement.due_date = date_after(&joined, "Payment Due Date");
+
+        statement.opening_balance = amount_after(texts, "Previous Balance");
+        statement.payments = amount_after(texts, "Payments, Reversals & other Credits");
+        statement.purchases = amount_after(texts, "Purchases & Other Debits");
+        statement.finance_charges = amount_after(texts, "Fee, Taxes & Interest Charges");
+        statement.total_dues = amount_after(texts, "Total Amount Due");
+        statement.minimum_due = amount_after(texts, "Minimum Amount Due");
+
+        statement.warn_missing();
+    }
+
+    fn password_hint(&self) -> &'static str {
+        "the date of birth as DDMMYYYY followed by the last 4 digits of the card number"
+    }
+}
+
+pub struct Amex;
+
+impl StatementParser for Amex {
+    fn name(&self) -> &'static str {
+        "amex"
+    }
+
+    fn layouts(&self) -> &'static [&'static Layout] {
+        &[&layout::AMEX]
+    }
+
+    fn read_details(&self, statement: &mut Statement, texts: &[String]) {
+        let joined = texts.join("\n");
+
+        let card = Regex::new(r"\b(XXXX[- ]?X{6}[- ]?\d{5}|X{4,11}\d{4,5})\b").unwrap();
+        statement.card_number = card.captures(&joined).map(|c| c[1].to_owned());
+        statement.statement_date = date_after(&joined, "Statement Date");
+        statement.due_date = date_after(&joined, "Due Date");
+
+        statement.opening_balance = amount_after(texts, "Opening Balance");
+        statement.payments = amount_after(texts, "New Credits");
+        statement.purchases = amount_after(texts, "New Debits");
+        statement.total_dues = amount_after(texts, "Closing Balance");
+        statement.minimum_due = amount_after(texts, "Minimum Payment");
+
+        // the rewards summary comes after the account summary, and reuses
+        // its "Opening Balance"/"Closing Balance" labels.
+        if let Some(start) = texts
+            .iter()
+            .position(|t| t.starts_with("Membership Rewards"))
+        {
+            if let Some(values) = table_values(&texts[start..], &MEMBERSHIP_REWARDS, parse_points) {
+                statement.points_opening = Some(values[0]);
+                statement.points_earned = Some(values[1]);
+                statement.points_redeemed = Some(values[2]);
+                statement.points_closing = Some(values[3]);
+            }
+        }
+
+        statement.warn_missing();
+    }
+
+    fn password_hint(&self) -> &'static str {
+        "usually not needed, Amex India statements aren't password protected"
+    }
+}
+
+// HDFC savings and current account statements.
+pub struct HdfcAccount;
+
+impl StatementParser for HdfcAccount {
+    fn name(&self) -> &'static str {
+        "hdfc-account"
+    }
+
+    fn layouts(&self) -> &'static [&'static Layout] {
+        &[&layout::HDFC_ACCOUNT]
+    }
+
+    fn read_details(&self, statement: &mut Statement, texts: &[String]) {
+        let joined = texts.join("\n");
+
+        let account = Regex::new(r"Account No\s*:?\s*(\d{8,20})").unwrap();
+        statement.account_number = account.captures(&joined).map(|c| c[1].to_owned());
+        let period =
+            Regex::new(r"From\s*:?\s*(\d{2}/\d{2}/\d{4})\s*To\s*:?\s*(\d{2}/\d{2}/\d{4})").unwrap();
+        statement.period = period.captures(&joined).and_then(|c| {
+            Some((
+                NaiveDate::parse_from_str(&c[1], "%d/%m/%Y").ok()?,
+                NaiveDate::parse_from_str(&c[2], "%d/%m/%Y").ok()?,
+            ))
+        });
+        statement.statement_date = statement.period.map(|(_, to)| to);
+
+        let number = |s: &str| s.trim().replace(',', "").parse::<f32>().ok();
+        if let Some(values) = table_values(texts, &ACCOUNT_SUMMARY, number) {
+            statement.opening_balance = Some(values[0]);
+            statement.purchases = Some(values[3]);
+            statement.payments = Some(values[4]);
+            statement.total_dues = Some(values[5]);
+        }
+
+        for (field, missing) in [
+            ("account number", statement.account_number.is_none()),
+            ("statement period", statement.period.is_none()),
+            ("statement summary", statement.total_dues.is_none()),
+        ] {
+            if missing {
+                statement.warnings.push(format!("{} not found", field));
+            }
+        }
+    }
+
+    fn password_hint(&self) -> &'static str {
+        "the customer ID"
+    }
+}
+
+// Other banks come first: HDFC's card layouts are told apart by words like
+// "CashBack" that other banks' statements may print as well.
+const PARSERS: [&dyn StatementParser; 5] = [&HdfcAccount, &Icici, &Sbi, &Amex, &Hdfc];
+
+// The parser for a --bank name.
+pub fn parser(bank: &str) -> Option<&'static dyn StatementParser> {
+    PARSERS.into_iter().find(|parser| parser.name() == bank)
+}
+
+// A built-in layout by name, and the parser whose layout it is.
+pub fn layout(name: &str) -> Option<(&'static dyn StatementParser, &'static Layout)> {
+    PARSERS.into_iter().find_map(|parser| {
+        parser
+            .layouts()
+            .iter()
+            .find(|layout| layout.name == name)
+            .map(|layout| (parser, *layout))
+    })
+}
+
+// The bank and layout of a statement: every candidate layout is scored
+// against its texts, the first `first_page` of which are its first page, and
+// the best one wins, ties going to the --layout layouts (`custom`), then to
+// the more specific. With `bank` (--bank) only that bank's layouts are
+// candidates, otherwise every bank's. A --layout layout without markers, or
+// else the bank's default (HDFC's without --bank), is the fallback.
+pub fn detect(
+    texts: &[String],
+    first_page: usize,
+    bank: Option<&str>,
+    custom: &[Custom],
+) -> Detection {
+    let custom: Vec<(&'static dyn StatementParser, &'static Layout)> = custom
+        .iter()
+        .filter(|custom| bank.is_none_or(|bank| custom.bank == bank))
+        .filter_map(|custom| Some((parser(custom.bank)?, custom.layout)))
+        .collect();
+    let candidates: Vec<&'static dyn StatementParser> = match bank.and_then(parser) {
+        Some(parser) => vec![parser],
+        None => PARSERS.to_vec(),
+    };
+    let layouts = custom
+        .iter()
+        .copied()
+        .chain(candidates.iter().flat_map(|parser| {
+            parser
+                .layouts()
+                .iter()
+                .map(move |layout| (*parser, *layout))
+        }));
+
+    let mut best: Option<Detection> = None;
+    for (parser, layout) in layouts {
+        let Some(score) = layout.score(texts, first_page) else {
+            continue;
+        };
+        if best.is_none_or(|best| best.score < Some(score)) {
+            best = Some(Detection {
+                parser,
+                layout,
+                score: Some(score),
+            });
+        }
+    }
+    if let Some(best) = best {
+        return best;
+    }
+
+    let (parser, layout) = custom
+        .iter()
+        .copied()
+        .find(|(_, layout)| layout.markers.is_empty())
+        .unwrap_or_else(|| {
+            let parser = bank.and_then(parser).unwrap_or(&Hdfc);
+            (parser, parser.layouts().last().unwrap())
+        });
+    Detection {
+        parser,
+        layout,
+        score: None,
+    }
+}
@@ -0,0 +1,149 @@
+// `--budgets`: monthly budgets per category with rollover, what's left of a
+// month's budget carried into the next and overspend taken out of it, and the
+// running balance of each category over the months the statements cover. A
+// TOML file of category (from --categories) = monthly budget:
+//
+//   Food = 8000
+//   Travel = 15000
+use crate::categories::Categories;
+use crate::forecast;
+use crate::locale::Locale;
+use crate::summary::UNCATEGORIZED;
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use chrono::{Datelike, Months, NaiveDate};
+use std::collections::BTreeMap;
+use std::fs;
+
+pub fn load(path: &str) -> Result<BTreeMap<String, f32>, Error> {
+    let data = fs::read_to_string(path).context(format!("failed to read {}", path))?;
+    let budgets: BTreeMap<String, f32> =
+        toml::from_str(&data).context(format!("failed to parse {}", path))?;
+    if let Some((category, _)) = budgets.iter().find(|(_, budget)| **budget < 0.0) {
+        return Err(anyhow!("{}: negative budget for {}", path, category));
+    }
+    if budgets.is_empty() {
+        return Err(anyhow!("{}: no budgets", path));
+    }
+    Ok(budgets)
+}
+
+// A category's month: its budget, spend, and the balance after it, carried
+// into the next month.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Month {
+    pub month: NaiveDate,
+    pub budget: f32,
+    pub spent: f32,
+    pub balance: f32,
+}
+
+// Each of `months` with its balance, the previous one's plus the budget less
+// the month's spend: unused budget carries over, overspend comes out of it.
+pub fn rollover(budget: f32, spend: &BTreeMap<NaiveDate, f32>, months: &[NaiveDate]) -> Vec<Month> {
+    let mut balance = 0.0;
+    months
+        .iter()
+        .map(|month| {
+            let spent = spend.get(month).copied().unwrap_or_default();
+            balance += budget - spent;
+            Month {
+                month: *month,
+                budget,
+                spent,
+                balance,
+            }
+        })
+        .collect()
+}
+
+// Print each budgeted category's months and the balance it ends on, then
+// the spend outside the budgets.
+pub fn run(
+    rows: impl Iterator<Item = Transaction>,
+    categories: Option<&Categories>,
+    budgets: &BTreeMap<String, f32>,
+    locale: Locale,
+) -> Result<(), Error> {
+    // category -> month -> spend
+    let mut spend_by: BTreeMap<String, BTreeMap<NaiveDate, f32>> = BTreeMap::new();
+    let mut range: Option<(NaiveDate, NaiveDate)> = None;
+    for transaction in rows {
+        let month = transaction.date.date().with_day(1).unwrap();
+        range = Some(match range {
+            Some((first, last)) => (first.min(month), last.max(month)),
+            None => (month, month),
+        });
+        let spend = forecast::spend(&transaction);
+        if spend == 0.0 {
+            continue;
+        }
+        let category = categories
+            .and_then(|categories| categories.categorize(&transaction))
+            .unwrap_or(UNCATEGORIZED);
+        *spend_by
+            .entry(category.to_owned())
+            .or_default()
+            .entry(month)
+            .or_default() += spend;
+    }
+
+    let (first, last) = range.ok_or_else(|| anyhow!("no transactions to budget"))?;
+    // every month in between, spent in or not, adds its budget.
+    let months: Vec<NaiveDate> = (0..)
+        .map_while(|i| first.checked_add_months(Months::new(i)))
+        .take_while(|month| *month <= last)
+        .collect();
+
+    println!(
+        "Budgets from {} to {}, unused budget carried forward",
+        first.format("%Y-%m"),
+        last.format("%Y-%m")
+    );
+    let empty = BTreeMap::new();
+    let mut balances = Vec::new();
+    for (category, budget) in budgets {
+        let months = rollover(*budget, spend_by.get(category).unwrap_or(&empty), &months);
+        println!();
+        println!("{}", category);
+        println!(
+            "  {:<8} {:>14} {:>14} {:>14}",
+            "Month", "Budget", "Spent", "Balance"
+        );
+        for month in &months {
+            println!(
+                "  {:<8} {:>14} {:>14} {:>14}",
+                month.month.format("%Y-%m").to_string(),
+                locale.amount(month.budget),
+                locale.amount(month.spent),
+                locale.amount(month.balance)
+            );
+        }
+        let spent: f32 = months.iter().map(|month| month.spent).sum();
+        balances.push((category, spent, months.last().map(|month| month.balance)));
+    }
+
+    println!();
+    println!(
+        "{:<24} {:>14} {:>14} {:>14}",
+        "Category", "Budget", "Spent", "Balance"
+    );
+    for (category, spent, balance) in balances {
+        println!(
+            "{:<24} {:>14} {:>14} {:>14}",
+            category,
+            locale.amount(budgets[category]),
+            locale.amount(spent),
+            locale.amount(balance.unwrap_or_default())
+        );
+    }
+    let unbudgeted: f32 = spend_by
+        .iter()
+        .filter(|(category, _)| !budgets.contains_key(*category))
+        .flat_map(|(_, months)| months.values())
+        .sum();
+    if unbudgeted != 0.0 {
+        println!("Spent outside the budgets: {}", locale.amount(unbudgeted));
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+// --cache-dir: statements parsed before, kept as NDJSON by the SHA-256 of
+// their content, so the monthly re-run over a whole archive only parses the
+// statements that are new. The first line holds the statement's details,
+// each line after it one of its rows.
+use crate::layout::Custom;
+use crate::statement::Statement;
+use crate::{bank, location, ParseOptions, Transaction};
+use anyhow::{Context, Error};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+use std::process;
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    bank: String,
+    layout: String,
+    statement: Statement,
+}
+
+// Cache key of a statement. Besides its content, what it's parsed with: the
+// --name, --bank, --pages, --layout and profile owner it was parsed for, and
+// this version, whose parser may read it differently.
+pub fn key(data: &[u8], name: &str, options: ParseOptions) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!(
+        "{}|{}|{:?}|{:?}|{}|{:?}|{:?}|",
+        env!("CARGO_PKG_VERSION"),
+        name,
+        options.bank,
+        options.pages,
+        options.provenance,
+        options.owner,
+        options.layouts
+    ));
+    hasher.update(data);
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// The statement and rows cached under `key`, its layout one of the bank's or
+// of `custom` (--layout). Anything missing or unreadable is a miss, and the
+// statement is parsed again.
+pub fn get(dir: &str, key: &str, custom: &[Custom]) -> Option<(Statement, Vec<Transaction>)> {
+    let data = fs::read_to_string(Path::new(dir).join(format!("{}.ndjson", key))).ok()?;
+    let mut lines = data.lines();
+
+    let header: Header = serde_json::from_str(lines.next()?).ok()?;
+    let parser = bank::parser(&header.bank)?;
+    let layout = custom
+        .iter()
+        .map(|custom| custom.layout)
+        .chain(parser.layouts().iter().copied())
+        .find(|layout| layout.name == header.layout)?;
+    let mut statement = header.statement;
+    statement.bank = parser.name();
+    statement.layout = layout.name;
+    statement.rewards_unit = layout.rewards_unit;
+
+    let mut rows = Vec::new();
+    for line in lines {
+        let mut row: Transaction = serde_json::from_str(line).ok()?;
+        row.rewards_unit = layout.rewards_unit;
+        (row.city, row.state) = location::locate(&row.tx).unzip();
+        rows.push(row);
+    }
+    Some((statement, rows))
+}
+
+// Cache a parsed statement under `key`. Written under a temporary name and
+// renamed into place, so an interrupted run never leaves half an entry.
+pub fn put(dir: &str, key: &str, statement: &Statement, rows: &[Transaction]) -> Result<(), Error> {
+    fs::create_dir_all(dir).context(format!("failed to create {}", dir))?;
+
+    let header = Header {
+        bank: statement.bank.to_owned(),
+        layout: statement.layout.to_owned(),
+        statement: statement.clone(),
+    };
+    let mut data = serde_json::to_string(&header)?;
+    data.push('\n');
+    for row in rows {
+        data.push_str(&serde_json::to_string(row)?);
+        data.push('\n');
+    }
+
+    let path = Path::new(dir).join(format!("{}.ndjson", key));
+    let tmp = Path::new(dir).join(format!(".{}.tmp-{}", key, process::id()));
+    fs::write(&tmp, data).context(format!("failed to write {}", tmp.display()))?;
+    fs::rename(&tmp, &path).context(format!("failed to write {}", path.display()))
+}
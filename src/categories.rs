@@ -0,0 +1,508 @@
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+// A single categorization rule. A transaction matches when its description
+// contains one of `patterns` (case-insensitive) or matches `regex`, none of
+// the `exclude` patterns, and its absolute amount is within the bounds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Rule {
+    pub category: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    // rules with a higher priority are tried first, ties in file order.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub priority: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_amount: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<f32>,
+    #[serde(skip)]
+    compiled: Option<Regex>,
+}
+
+fn is_zero(n: &i32) -> bool {
+    *n == 0
+}
+
+impl Rule {
+    pub fn new(category: &str, patterns: Vec<String>) -> Self {
+        Rule {
+            category: category.to_owned(),
+            patterns,
+            ..Default::default()
+        }
+    }
+
+    // Only substring and exclude patterns, which is all the JSON format can hold.
+    fn is_simple(&self) -> bool {
+        self.regex.is_none()
+            && self.priority == 0
+            && self.min_amount.is_none()
+            && self.max_amount.is_none()
+    }
+
+    fn compile(&mut self) -> Result<(), Error> {
+        if let Some(regex) = &self.regex {
+            let compiled = Regex::new(&format!("(?i){}", regex))
+                .context(format!("invalid regex in {} rule", self.category))?;
+            self.compiled = Some(compiled);
+        }
+        Ok(())
+    }
+
+    // `description` is expected to be uppercased already.
+    fn matches(&self, description: &str, amount: f32) -> bool {
+        self.matched_by(description, amount).is_some()
+    }
+
+    // Not ruled out by an exclude pattern or the amount bounds?
+    fn allows(&self, description: &str, amount: f32) -> bool {
+        !matches_any(&self.exclude, description)
+            && !self.min_amount.is_some_and(|min| amount.abs() < min)
+            && !self.max_amount.is_some_and(|max| amount.abs() > max)
+    }
+
+    // The pattern (or regex) that made the rule match, if it does.
+    fn matched_by(&self, description: &str, amount: f32) -> Option<String> {
+        if !self.allows(description, amount) {
+            return None;
+        }
+
+        if let Some(pattern) = self
+            .patterns
+            .iter()
+            .find(|p| !p.is_empty() && description.contains(&p.to_uppercase()))
+        {
+            return Some(format!("pattern {:?}", pattern));
+        }
+        match &self.compiled {
+            Some(re) if re.is_match(description) => {
+                Some(format!("regex {:?}", self.regex.as_deref().unwrap_or("")))
+            }
+            _ => None,
+        }
+    }
+
+    // Best fuzzy similarity of any pattern to the description, and that pattern.
+    fn fuzzy_match(&self, description: &str, amount: f32) -> Option<(f32, &str)> {
+        if !self.allows(description, amount) {
+            return None;
+        }
+        self.patterns
+            .iter()
+            .map(|p| (similarity(p, description), p.as_str()))
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+    }
+}
+
+// TOML/YAML rules file layout:
+//
+// [[rules]]
+// category = "Food"
+// patterns = ["SWIGGY", "ZOMATO"]
+// priority = 10
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RulesFile {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl Format {
+    fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Format::Toml,
+            Some(ext) if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") => {
+                Format::Yaml
+            }
+            _ => Format::Json,
+        }
+    }
+}
+
+// A category in the JSON format, either just its patterns or patterns with
+// exclusions.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum JsonCategory {
+    Patterns(Vec<String>),
+    Rule {
+        patterns: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        exclude: Vec<String>,
+    },
+}
+
+// Category rules, loaded from a JSON file mapping category names to the
+// description substrings that belong to it, optionally with substrings
+// that rule it out,
+//
+// {"Food": ["SWIGGY", "ZOMATO"], "Shopping": {"patterns": ["AMAZON"], "exclude": ["PRIME"]}}
+//
+// or from a TOML/YAML file with a list of `Rule`s.
+#[derive(Debug, Clone, Default)]
+pub struct Categories {
+    // sorted by priority, highest first.
+    pub rules: Vec<Rule>,
+    // similarity threshold (0-1) for fuzzy matching patterns when no rule
+    // matches exactly, off when None.
+    pub fuzzy: Option<f32>,
+}
+
+impl Categories {
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let data =
+            fs::read_to_string(path).context(format!("failed to read categories file {}", path))?;
+
+        let mut rules = match Format::from_path(path) {
+            Format::Json => serde_json::from_str::<BTreeMap<String, JsonCategory>>(&data)
+                .context(format!("failed to parse categories file {}", path))?
+                .into_iter()
+                .map(|(category, rule)| match rule {
+                    JsonCategory::Patterns(patterns) => Rule::new(&category, patterns),
+                    JsonCategory::Rule { patterns, exclude } => Rule {
+                        exclude,
+                        ..Rule::new(&category, patterns)
+                    },
+                })
+                .collect(),
+            Format::Toml => {
+                toml::from_str::<RulesFile>(&data)
+                    .context(format!("failed to parse categories file {}", path))?
+                    .rules
+            }
+            Format::Yaml => {
+                serde_yaml::from_str::<RulesFile>(&data)
+                    .context(format!("failed to parse categories file {}", path))?
+                    .rules
+            }
+        };
+
+        for rule in rules.iter_mut() {
+            rule.compile()?;
+        }
+        rules.sort_by_key(|rule| Reverse(rule.priority));
+
+        Ok(Categories { rules, fuzzy: None })
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), Error> {
+        let data = match Format::from_path(path) {
+            Format::Json => {
+                if let Some(rule) = self.rules.iter().find(|rule| !rule.is_simple()) {
+                    return Err(anyhow!(
+                        "{} rule can't be written to JSON, use a TOML or YAML rules file",
+                        rule.category
+                    ));
+                }
+                let mut map: BTreeMap<&str, JsonCategory> = BTreeMap::new();
+                for rule in &self.rules {
+                    let entry = map
+                        .entry(&rule.category)
+                        .or_insert(JsonCategory::Patterns(Vec::new()));
+                    if !rule.exclude.is_empty() {
+                        if let JsonCategory::Patterns(patterns) = entry {
+                            *entry = JsonCategory::Rule {
+                                patterns: std::mem::take(patterns),
+                                exclude: Vec::new(),
+                            };
+                        }
+                    }
+                    match entry {
+                        JsonCategory::Patterns(patterns) => {
+                            patterns.extend(rule.patterns.iter().cloned())
+                        }
+                        JsonCategory::Rule { patterns, exclude } => {
+                            patterns.extend(rule.patterns.iter().cloned());
+                            exclude.extend(rule.exclude.iter().cloned());
+                        }
+                    }
+                }
+                serde_json::to_string_pretty(&map).context("failed to serialize categories")? + "\n"
+            }
+            Format::Toml => toml::to_string_pretty(&RulesFile {
+                rules: self.rules.clone(),
+            })
+            .context("failed to serialize categories")?,
+            Format::Yaml => serde_yaml::to_string(&RulesFile {
+                rules: self.rules.clone(),
+            })
+            .context("failed to serialize categories")?,
+        };
+
+        fs::write(path, data).context(format!("failed to write categories file {}", path))
+    }
+
+    // The first rule, by priority, matching the transaction.
+    pub fn rule_for(&self, transaction: &Transaction) -> Option<&Rule> {
+        let description = transaction.tx.to_uppercase();
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(&description, transaction.amount))
+            .or_else(|| {
+                self.fuzzy_match(&description, transaction.amount)
+                    .map(|(rule, _)| rule)
+            })
+    }
+
+    // The rule with the pattern most similar to the description, if above
+    // the fuzzy threshold. Earlier rules win ties.
+    fn fuzzy_match(&self, description: &str, amount: f32) -> Option<(&Rule, String)> {
+        let threshold = self.fuzzy?;
+        let mut best: Option<(&Rule, f32, &str)> = None;
+        for rule in &self.rules {
+            if let Some((score, pattern)) = rule.fuzzy_match(description, amount) {
+                if score >= threshold && best.is_none_or(|(_, s, _)| score > s) {
+                    best = Some((rule, score, pattern));
+                }
+            }
+        }
+        best.map(|(rule, score, pattern)| {
+            (rule, format!("fuzzy pattern {:?} ({:.2})", pattern, score))
+        })
+    }
+
+    pub fn categorize(&self, transaction: &Transaction) -> Option<&str> {
+        self.rule_for(transaction)
+            .map(|rule| rule.category.as_str())
+    }
+
+    // Every rule matching the transaction, in the order they are tried, with
+    // what made each one match. The first one decides the category.
+    pub fn explain(&self, transaction: &Transaction) -> Vec<(&Rule, String)> {
+        let description = transaction.tx.to_uppercase();
+        let matched: Vec<(&Rule, String)> = self
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                rule.matched_by(&description, transaction.amount)
+                    .map(|reason| (rule, reason))
+            })
+            .collect();
+        if !matched.is_empty() {
+            return matched;
+        }
+        self.fuzzy_match(&description, transaction.amount)
+            .into_iter()
+            .collect()
+    }
+
+    // Return every category with a rule matching the transaction. Used for
+    // tag rules, where a transaction can carry several tags at once.
+    pub fn all(&self, transaction: &Transaction) -> Vec<&str> {
+        let description = transaction.tx.to_uppercase();
+        let mut categories: Vec<&str> = Vec::new();
+        for rule in &self.rules {
+            if rule.matches(&description, transaction.amount)
+                && !categories.contains(&rule.category.as_str())
+            {
+                categories.push(&rule.category);
+            }
+        }
+        categories
+    }
+
+    // Add a pattern to a category, creating the category if needed.
+    pub fn learn(&mut self, category: &str, pattern: &str) {
+        let rule = match self
+            .rules
+            .iter()
+            .position(|rule| rule.category == category && rule.is_simple())
+        {
+            Some(i) => &mut self.rules[i],
+            None => {
+                self.rules.push(Rule::new(category, Vec::new()));
+                self.rules.last_mut().unwrap()
+            }
+        };
+        if !rule
+            .patterns
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(pattern))
+        {
+            rule.patterns.push(pattern.to_owned());
+        }
+    }
+
+    // Report rules that can never match because an earlier rule always wins,
+    // and patterns claimed by several categories at the same priority, where
+    // only the file order decides.
+    pub fn check(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        for (i, rule) in self.rules.iter().enumerate() {
+            if rule.patterns.is_empty() && rule.regex.is_none() {
+                problems.push(format!(
+                    "{} rule has no patterns or regex and never matches",
+                    rule.category
+                ));
+                continue;
+            }
+
+            if let Some(earlier) = self.rules[..i]
+                .iter()
+                .find(|earlier| shadows(earlier, rule))
+            {
+                problems.push(format!(
+                    "{} rule (priority {}) is unreachable, {} rule (priority {}) matches first",
+                    rule.category, rule.priority, earlier.category, earlier.priority
+                ));
+            }
+
+            for earlier in &self.rules[..i] {
+                if earlier.priority != rule.priority || earlier.category == rule.category {
+                    continue;
+                }
+                for pattern in &rule.patterns {
+                    if earlier
+                        .patterns
+                        .iter()
+                        .any(|p| p.eq_ignore_ascii_case(pattern))
+                    {
+                        problems.push(format!(
+                            "pattern {} is in both {} and {} at priority {}",
+                            pattern, earlier.category, rule.category, rule.priority
+                        ));
+                    }
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+// Does `earlier` match everything `later` could? Only decided for plain
+// pattern rules: every pattern of `later` must contain one of `earlier`'s.
+fn shadows(earlier: &Rule, later: &Rule) -> bool {
+    if later.regex.is_some() || !earlier.exclude.is_empty() {
+        return false;
+    }
+    // earlier's amount bounds must cover later's.
+    if earlier
+        .min_amount
+        .is_some_and(|min| later.min_amount.is_none_or(|l| l < min))
+        || earlier
+            .max_amount
+            .is_some_and(|max| later.max_amount.is_none_or(|l| l > max))
+    {
+        return false;
+    }
+
+    !later.patterns.is_empty()
+        && later.patterns.iter().all(|pattern| {
+            let pattern = pattern.to_uppercase();
+            earlier
+                .patterns
+                .iter()
+                .any(|p| !p.is_empty() && pattern.contains(&p.to_uppercase()))
+        })
+}
+
+// Fuzzy similarity (0-1) of a pattern to a description, ignoring case,
+// spaces and punctuation: 1 - the Levenshtein distance to the closest
+// stretch of the description, relative to the pattern length. So both
+// "SWIGGY LIMITED" ~ "SWIGGYLIMITED" and "ZOMATO" ~ "ZOMATTO BANGALORE" score
+// high.
+fn similarity(pattern: &str, description: &str) -> f32 {
+    let normalize = |s: &str| -> Vec<char> {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_uppercase)
+            .collect()
+    };
+    let pattern = normalize(pattern);
+    let description = normalize(description);
+    if pattern.is_empty() || description.is_empty() {
+        return 0.0;
+    }
+
+    // windows one char shorter and longer than the pattern allow for a
+    // dropped or extra character.
+    let mut best = pattern.len();
+    for len in pattern.len().saturating_sub(1).max(1)..=pattern.len() + 1 {
+        if len > description.len() {
+            best = best.min(levenshtein(&pattern, &description));
+            break;
+        }
+        for window in description.windows(len) {
+            best = best.min(levenshtein(&pattern, window));
+        }
+    }
+
+    1.0 - best as f32 / pattern.len() as f32
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev[b.len()]
+}
+
+// Is any of the patterns found in the (uppercased) description?
+fn matches_any(patterns: &[String], description: &str) -> bool {
+    patterns
+        .iter()
+        .any(|p| !p.is_empty() && description.contains(&p.to_uppercase()))
+}
+
+// Best-effort merchant name from a transaction description, used as the
+// pattern when learning new rules. "SWIGGY*ORDER BANGALORE" -> "SWIGGY".
+pub fn merchant_key(description: &str) -> String {
+    description
+        .split(|c: char| c.is_whitespace() || c == '*')
+        .map(|token| token.trim_matches(|c: char| !c.is_alphanumeric()))
+        .find(|token| !token.is_empty())
+        .unwrap_or("")
+        .to_uppercase()
+}
+
+// Prompt on the terminal for the category of an uncategorized merchant.
+// An empty answer skips the merchant.
+pub fn ask_category(description: &str, merchant: &str) -> Result<Option<String>, Error> {
+    let mut stderr = io::stderr();
+    write!(
+        stderr,
+        "{}\ncategory for {} (enter to skip): ",
+        description, merchant
+    )
+    .context("failed to write prompt")?;
+    stderr.flush().context("failed to write prompt")?;
+
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .context("failed to read category")?;
+
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(answer.to_owned()))
+}
@@ -0,0 +1,65 @@
+// Unencrypted copies of statements, for archiving them without passwords.
+use anyhow::{anyhow, bail, Context, Error};
+use lopdf::{Document, LoadOptions};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Open the statement with the first password that works and write it,
+// decrypted, to `dir` under its own file name. Returns the written path.
+// Statements of the same name from different directories or archives are
+// told apart by a suffix, eg. "statement-2.pdf", against the paths already
+// `written` this run. A file that's there from before is only overwritten
+// with `force`, and the statement itself never is.
+pub fn save(
+    path: &str,
+    data: &[u8],
+    passwords: &[String],
+    dir: &str,
+    written: &mut HashSet<PathBuf>,
+    force: bool,
+) -> Result<PathBuf, Error> {
+    let mut last = anyhow!("no password to try");
+    for password in passwords {
+        match Document::load_mem_with_options(data, LoadOptions::with_password(password)) {
+            Ok(mut document) => {
+                fs::create_dir_all(dir).context(format!("failed to create {}", dir))?;
+                let out = out_path(path, dir, written);
+                if out.exists() {
+                    let same = fs::canonicalize(&out).ok() == fs::canonicalize(path).ok();
+                    if same {
+                        bail!("{} is the statement itself, not overwritten", out.display());
+                    }
+                    if !force {
+                        bail!("{} exists, --force overwrites it", out.display());
+                    }
+                }
+                document
+                    .save(&out)
+                    .context(format!("failed to write {}", out.display()))?;
+                written.insert(out.clone());
+                return Ok(out);
+            }
+            Err(e) => last = e.into(),
+        }
+    }
+    Err(last.context(format!("failed to open file {}", path)))
+}
+
+// `path`'s file name in `dir`, suffixed with the first number that isn't
+// `written` yet.
+fn out_path(path: &str, dir: &str, written: &HashSet<PathBuf>) -> PathBuf {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| match n {
+            1 => Path::new(dir).join(format!("{}{}", stem, extension)),
+            n => Path::new(dir).join(format!("{}-{}{}", stem, n, extension)),
+        })
+        .find(|out| !written.contains(out))
+        .unwrap()
+}
@@ -0,0 +1,82 @@
+use crate::s3;
+use anyhow::{Context, Error};
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::process;
+
+// Where the output goes: stdout (the default), a local file, or an s3://
+// object. Files and objects are written to a temporary file first, which is
+// renamed over the target (or uploaded) on commit, so a failed run never
+// leaves a truncated output behind.
+pub enum Destination {
+    Stdout,
+    File { path: String, tmp: String },
+    S3 { url: String, tmp: String },
+}
+
+impl Destination {
+    pub fn new(path: Option<&str>) -> Self {
+        match path {
+            None | Some("-") => Destination::Stdout,
+            Some(url) if s3::is_s3(url) => Destination::S3 {
+                url: url.to_owned(),
+                tmp: env::temp_dir()
+                    .join(format!(
+                        "hdfc-cc-parser-{}-{}.out",
+                        process::id(),
+                        url.rsplit('/').next().unwrap_or_default()
+                    ))
+                    .to_string_lossy()
+                    .to_string(),
+            },
+            Some(path) => {
+                // next to the target, so the rename stays on one filesystem.
+                let target = Path::new(path);
+                let name = target.file_name().unwrap_or_default().to_string_lossy();
+                Destination::File {
+                    path: path.to_owned(),
+                    tmp: target
+                        .with_file_name(format!(".{}.tmp-{}", name, process::id()))
+                        .to_string_lossy()
+                        .to_string(),
+                }
+            }
+        }
+    }
+
+    pub fn writer(&self) -> Result<Box<dyn Write>, Error> {
+        let tmp = match self {
+            Destination::Stdout => return Ok(Box::new(io::stdout())),
+            Destination::File { tmp, .. } | Destination::S3 { tmp, .. } => tmp,
+        };
+        let file = File::create(tmp).context(format!("failed to create {}", tmp))?;
+        Ok(Box::new(BufWriter::new(file)))
+    }
+
+    // Called once the writer is done with and dropped.
+    pub fn commit(self) -> Result<(), Error> {
+        match self {
+            Destination::Stdout => Ok(()),
+            Destination::File { path, tmp } => {
+                fs::rename(&tmp, &path).context(format!("failed to write {}", path))
+            }
+            Destination::S3 { url, tmp } => {
+                let data = fs::read(&tmp).context(format!("failed to read {}", tmp))?;
+                fs::remove_file(&tmp).ok();
+                s3::put(&url, data)
+            }
+        }
+    }
+
+    // Throw away whatever was written, leaving the target untouched.
+    pub fn discard(self) {
+        match self {
+            Destination::Stdout => {}
+            Destination::File { tmp, .. } | Destination::S3 { tmp, .. } => {
+                fs::remove_file(tmp).ok();
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// `diff`: compare the CSV outputs of two runs, or two --output-dir
+// directories of them, to check a parser change against previous output.
+// Rows are matched by ID: the ID column when there is one, or the ID of
+// their date, description, amount and points. Rows whose ID only appears
+// on one side but whose date and amount match a row on the other are
+// reported as changed, as a description read differently.
+use crate::output;
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use chrono::{DateTime, NaiveDateTime};
+use chrono_tz::Asia::Kolkata;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+
+// A CSV row, header -> value.
+type Row = BTreeMap<String, String>;
+
+// Without --addheaders, the default columns.
+const DEFAULT_HEADERS: [&str; 4] = ["Date", "Description", "Points", "Amount"];
+
+#[derive(Debug, Default)]
+pub struct Diff {
+    pub added: Vec<Row>,
+    pub removed: Vec<Row>,
+    // old and new row
+    pub changed: Vec<(Row, Row)>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    // One line per row, prefixed with `prefix` (the file, for directories).
+    pub fn print(&self, prefix: &str) {
+        for row in &self.removed {
+            println!("{}- {}", prefix, describe(row));
+        }
+        for row in &self.added {
+            println!("{}+ {}", prefix, describe(row));
+        }
+        for (old, new) in &self.changed {
+            let changes: Vec<String> = old
+                .keys()
+                .chain(new.keys())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .filter(|column| old.get(*column) != new.get(*column))
+                .map(|column| {
+                    format!(
+                        "{} {:?} -> {:?}",
+                        column.to_lowercase(),
+                        old.get(column).map(String::as_str).unwrap_or_default(),
+                        new.get(column).map(String::as_str).unwrap_or_default()
+                    )
+                })
+                .collect();
+            println!("{}~ {}: {}", prefix, describe(old), changes.join(", "));
+        }
+    }
+}
+
+fn describe(row: &Row) -> String {
+    ["Date", "Description", "Amount"]
+        .iter()
+        .filter_map(|column| row.get(*column))
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Compare two CSV files, or the CSV files of two directories by name. Prints
+// the differences and returns whether there were any.
+pub fn run(old: &str, new: &str) -> Result<bool, Error> {
+    if !Path::new(old).is_dir() {
+        let diff = diff(&read(old)?, &read(new)?);
+        diff.print("");
+        return Ok(!diff.is_empty());
+    }
+
+    let (old_files, new_files) = (csv_files(old)?, csv_files(new)?);
+    let mut differ = false;
+    for name in old_files.union(&new_files) {
+        match (old_files.contains(name), new_files.contains(name)) {
+            (true, false) => println!("only in {}: {}", old, name),
+            (false, true) => println!("only in {}: {}", new, name),
+            _ => {
+                let old = Path::new(old).join(name);
+                let new = Path::new(new).join(name);
+                let diff = diff(
+                    &read(&old.to_string_lossy())?,
+                    &read(&new.to_string_lossy())?,
+                );
+                diff.print(&format!("{}: ", name));
+                if diff.is_empty() {
+                    continue;
+                }
+            }
+        }
+        differ = true;
+    }
+    Ok(differ)
+}
+
+fn csv_files(dir: &str) -> Result<BTreeSet<String>, Error> {
+    Ok(fs::read_dir(dir)
+        .context(format!("failed to read {}", dir))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "csv"))
+        .filter_map(|path| Some(path.file_name()?.to_string_lossy().to_string()))
+        .collect())
+}
+
+fn read(path: &str) -> Result<BTreeMap<String, Row>, Error> {
+    let file = File::open(path).context(format!("failed to open {}", path))?;
+    read_rows(file, path)
+}
+
+// The rows of a CSV output by ID. Rows with the same ID, as identical rows
+// had before they were numbered, are told apart by their order. `path` names
+// it in errors.
+pub fn read_rows<R: io::Read>(rdr: R, path: &str) -> Result<BTreeMap<String, Row>, Error> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(rdr);
+    let mut records = rdr.records();
+
+    let known: Vec<&str> = output::COLUMNS
+        .iter()
+        .map(|column| output::header(column))
+        .collect();
+    let first = match records.next() {
+        Some(record) => Some(record.context(format!("failed to read {}", path))?),
+        None => None,
+    };
+    let (headers, first): (Vec<String>, _) = match first {
+        // --excel-safe files start with a BOM.
+        Some(record)
+            if record
+                .iter()
+                .all(|h| known.contains(&h.trim_start_matches('\u{feff}'))) =>
+        {
+            let headers = record
+                .iter()
+                .map(|h| h.trim_start_matches('\u{feff}').to_owned())
+                .collect();
+            (headers, None)
+        }
+        first => (DEFAULT_HEADERS.map(str::to_owned).to_vec(), first),
+    };
+
+    let mut rows = BTreeMap::new();
+    // (statement file, ID) -> rows with it, without an ID column.
+    let mut occurrences: HashMap<(String, String), usize> = HashMap::new();
+    for (i, record) in first.into_iter().map(Ok).chain(records).enumerate() {
+        let record = record.context(format!("failed to read {}", path))?;
+        let row: Row = headers
+            .iter()
+            .cloned()
+            .zip(record.iter().map(str::to_owned))
+            .collect();
+        let id = match row.get("ID") {
+            Some(id) => id.clone(),
+            None => {
+                let mut transaction = transaction(&row)
+                    .ok_or_else(|| anyhow!("{}: unreadable row {}", path, i + 1))?;
+                let file = row.get("Statement File").cloned().unwrap_or_default();
+                let n = occurrences.entry((file, transaction.id())).or_default();
+                transaction.occurrence = *n;
+                *n += 1;
+                transaction.id()
+            }
+        };
+        let mut key = id.clone();
+        let mut n = 1;
+        while rows.contains_key(&key) {
+            n += 1;
+            key = format!("{}#{}", id, n);
+        }
+        rows.insert(key, row);
+    }
+    Ok(rows)
+}
+
+// A row without an ID column, with the fields the parser computes its ID
+// from.
+fn transaction(row: &Row) -> Option<Transaction> {
+    let date = row.get("Date")?.trim_start_matches('\u{feff}');
+    // RFC 3339 in IST or UTC, or --excel-safe's plain IST.
+    let date = DateTime::parse_from_rfc3339(date)
+        .map(|date| date.with_timezone(&Kolkata).naive_local())
+        .or_else(|_| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S"))
+        .ok()?;
+    Some(Transaction {
+        date,
+        tx: row.get("Description").cloned().unwrap_or_default(),
+        amount: row.get("Amount")?.parse().ok()?,
+        points: row
+            .get("Points")
+            .and_then(|p| p.parse().ok())
+            .unwrap_or_default(),
+        ..Default::default()
+    })
+}
+
+pub fn diff(old: &BTreeMap<String, Row>, new: &BTreeMap<String, Row>) -> Diff {
+    let mut diff = Diff::default();
+    for (id, row) in old {
+        match new.get(id) {
+            Some(new_row) if new_row != row => diff.changed.push((row.clone(), new_row.clone())),
+            Some(_) => {}
+            None => diff.removed.push(row.clone()),
+        }
+    }
+    let mut added: Vec<Row> = new
+        .iter()
+        .filter(|(id, _)| !old.contains_key(*id))
+        .map(|(_, row)| row.clone())
+        .collect();
+
+    // pair the rest up by date and amount.
+    let same =
+        |a: &Row, b: &Row| a.get("Date") == b.get("Date") && a.get("Amount") == b.get("Amount");
+    diff.removed
+        .retain(|row| match added.iter().position(|new| same(row, new)) {
+            Some(i) => {
+                diff.changed.push((row.clone(), added.remove(i)));
+                false
+            }
+            None => true,
+        });
+    diff.added = added;
+    diff
+}
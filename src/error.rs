@@ -0,0 +1,111 @@
+// Ways parsing a statement can fail, so callers can tell them apart and the
+// CLI can say what to do about each.
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ParseError {
+    // password protected and no password was given.
+    Encrypted {
+        file: String,
+    },
+    // none of the given passwords opens it. `hint` explains the bank's
+    // password scheme when the bank is known.
+    WrongPassword {
+        file: String,
+        hint: Option<String>,
+    },
+    // the layout's transaction table heading is nowhere in the statement.
+    NoTransactionSection {
+        file: String,
+    },
+    // nothing the parser understands, eg. a scanned statement.
+    UnsupportedLayout {
+        hint: String,
+    },
+    // the statement file couldn't be read.
+    Io {
+        file: String,
+        source: std::io::Error,
+    },
+    // the pdf couldn't be read, on `page` (1-based) if it's one page.
+    PdfError {
+        page: Option<usize>,
+        source: pdf::error::PdfError,
+    },
+    // the receiving end of the rows went away, eg. the writer failed.
+    Closed,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Encrypted { file } => write!(
+                f,
+                "{} is password protected, give --password, --passwords-file or --dob",
+                file
+            ),
+            ParseError::WrongPassword { file, hint } => {
+                write!(f, "incorrect password for {}", file)?;
+                match hint {
+                    Some(hint) => write!(f, " ({})", hint),
+                    None => Ok(()),
+                }
+            }
+            ParseError::NoTransactionSection { file } => write!(
+                f,
+                "no transactions table in {}, pick the statement's bank with --bank",
+                file
+            ),
+            ParseError::UnsupportedLayout { hint } => {
+                write!(f, "unsupported statement layout: {}", hint)
+            }
+            ParseError::PdfError {
+                page: Some(page),
+                source,
+            } => write!(f, "failed to read page {}: {}", page, source),
+            ParseError::PdfError { page: None, source } => {
+                write!(f, "failed to read pdf: {}", source)
+            }
+            ParseError::Io { file, source } => write!(f, "failed to read {}: {}", file, source),
+            ParseError::Closed => write!(f, "output closed"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::PdfError { source, .. } => Some(source),
+            ParseError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Process exit codes, so scripts can tell failures apart. clap exits with 2
+// on bad arguments.
+pub const EXIT_ERROR: i32 = 1;
+pub const EXIT_PASSWORD: i32 = 3;
+pub const EXIT_NO_TRANSACTIONS: i32 = 4;
+pub const EXIT_VALIDATION: i32 = 5;
+pub const EXIT_IO: i32 = 6;
+
+// The exit code for a failed run, by the first cause it recognizes.
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<ParseError>() {
+            return match e {
+                ParseError::Encrypted { .. } | ParseError::WrongPassword { .. } => EXIT_PASSWORD,
+                ParseError::NoTransactionSection { .. } | ParseError::UnsupportedLayout { .. } => {
+                    EXIT_NO_TRANSACTIONS
+                }
+                ParseError::PdfError { .. } => EXIT_ERROR,
+                ParseError::Io { .. } | ParseError::Closed => EXIT_IO,
+            };
+        }
+        if cause.is::<std::io::Error>() {
+            return EXIT_IO;
+        }
+    }
+    EXIT_ERROR
+}
@@ -0,0 +1,293 @@
+// Synthetic statements in the current HDFC layout, or one of the HDFC card
+// layouts like it, with known details and transactions, for end to end tests
+// without committing real statements (and the personal data in them).
+// `gen-fixture` writes one out.
+use crate::layout::{self, Layout, Rewards};
+use crate::Transaction;
+use chrono::{Duration, NaiveDate, NaiveTime};
+use lopdf::content::{Content, Operation};
+use lopdf::{
+    dictionary, Document, EncryptionState, EncryptionVersion, Object, Permissions, Stream,
+};
+
+const MERCHANTS: [&str; 8] = [
+    "SWIGGY LIMITED BANGALORE",
+    "AMAZON PAY INDIA PRIVATE BANGALORE",
+    "UBER INDIA SYSTEMS PVT MUMBAI",
+    "BIG BASKET BANGALORE",
+    "IRCTC NEW DELHI",
+    "ZOMATO LTD GURGAON",
+    "APOLLO PHARMACY CHENNAI",
+    "HP PETROL PUMP PUNE",
+];
+
+// Table header of the transactions, the name on the card following it.
+const HEADER: [&str; 4] = ["Date", "Transaction Description", "Reward Points", "Amount"];
+
+// Where the texts of a line start, in points from the left edge.
+const COLUMNS: [i64; 5] = [40, 130, 380, 450, 520];
+
+const ROWS_PER_PAGE: usize = 25;
+
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    pub name: String,
+    pub card_number: String,
+    pub statement_date: NaiveDate,
+    pub due_date: NaiveDate,
+    pub period: (NaiveDate, NaiveDate),
+    pub opening_balance: f32,
+    pub points_opening: i32,
+    // the layout it's drawn in, see `in_layout`.
+    pub layout: &'static Layout,
+    // the rows as parse should read them back: debits negative.
+    pub transactions: Vec<Transaction>,
+}
+
+impl Fixture {
+    // A month of `rows` transactions for `name`, every tenth a payment.
+    pub fn sample(name: &str, rows: usize) -> Self {
+        let from = NaiveDate::from_ymd_opt(2023, 12, 13).unwrap();
+        let to = NaiveDate::from_ymd_opt(2024, 1, 12).unwrap();
+
+        let transactions = (0..rows)
+            .map(|i| {
+                let date = (from + Duration::days(i as i64 % 30))
+                    .and_time(NaiveTime::from_hms_opt(10, i as u32 % 60, 0).unwrap());
+                if i % 10 == 9 {
+                    return Transaction {
+                        date,
+                        tx: format!("NETBANKING TRANSFER (Ref# {:014})", i),
+                        amount: 2000.0,
+                        ..Default::default()
+                    };
+                }
+                let amount = 100.0 + i as f32 * 37.5;
+                Transaction {
+                    date,
+                    tx: MERCHANTS[i % MERCHANTS.len()].to_owned(),
+                    points: (amount / 150.0) as i32,
+                    amount: -amount,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        Fixture {
+            name: name.to_owned(),
+            card_number: "4386 24XX XXXX 1234".to_owned(),
+            statement_date: to,
+            due_date: NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+            period: (from, to),
+            opening_balance: 5000.0,
+            points_opening: 1000,
+            layout: &layout::HDFC,
+            transactions,
+        }
+    }
+
+    // The same statement in another HDFC card layout: its markers printed
+    // on the first page, and the rewards column it has. Cashback layouts earn
+    // as much cashback as the rows had points.
+    pub fn in_layout(mut self, layout: &'static Layout) -> Self {
+        for row in &mut self.transactions {
+            if layout.rewards == Rewards::Cashback {
+                row.cashback = row.points as f32;
+            }
+            if layout.rewards != Rewards::Points {
+                row.points = 0;
+            }
+        }
+        self.layout = layout;
+        self
+    }
+
+    pub fn payments(&self) -> f32 {
+        self.transactions
+            .iter()
+            .filter(|t| t.amount > 0.0)
+            .map(|t| t.amount)
+            .sum()
+    }
+
+    pub fn purchases(&self) -> f32 {
+        -self
+            .transactions
+            .iter()
+            .filter(|t| t.amount < 0.0)
+            .map(|t| t.amount)
+            .sum::<f32>()
+    }
+
+    pub fn total_dues(&self) -> f32 {
+        self.opening_balance - self.payments() + self.purchases()
+    }
+
+    pub fn points_earned(&self) -> i32 {
+        self.transactions.iter().map(|t| t.points).sum()
+    }
+
+    // The lines of the statement, page by page, each the texts drawn on it
+    // from left to right: the details on the first page, then the
+    // transaction table, its header repeated on every page.
+    fn pages(&self) -> Vec<Vec<Vec<String>>> {
+        let date = |d: NaiveDate| d.format("%d/%m/%Y").to_string();
+        let owned = |texts: &[&str]| texts.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        let details = vec![
+            vec!["Credit Card Statement".to_owned()],
+            owned(self.layout.markers),
+            vec![self.name.clone()],
+            vec![format!("Card No: {}", self.card_number)],
+            vec![
+                format!("Statement Date:{}", date(self.statement_date)),
+                format!("Payment Due Date:{}", date(self.due_date)),
+            ],
+            vec![format!(
+                "Billing Period: {} - {}",
+                date(self.period.0),
+                date(self.period.1)
+            )],
+            owned(&[
+                "Opening Balance",
+                "Payment/ Credits",
+                "Purchase/ Debits",
+                "Finance Charges",
+                "Total Dues",
+            ]),
+            [
+                self.opening_balance,
+                self.payments(),
+                self.purchases(),
+                0.0,
+                self.total_dues(),
+            ]
+            .iter()
+            .map(|amount| format!("{:.2}", amount))
+            .collect(),
+            vec![
+                "Minimum Amount Due".to_owned(),
+                format!("{:.2}", (self.total_dues() * 0.05).round()),
+            ],
+            owned(&[
+                "Opening Balance",
+                "Feature + Bonus Reward Points Earned",
+                "Disbursed",
+                "Closing Balance",
+            ]),
+            [
+                self.points_opening,
+                self.points_earned(),
+                0,
+                self.points_opening + self.points_earned(),
+            ]
+            .iter()
+            .map(i32::to_string)
+            .collect(),
+        ];
+
+        // a statement without rows still has its (empty) table.
+        let mut chunks: Vec<&[Transaction]> = self.transactions.chunks(ROWS_PER_PAGE).collect();
+        if chunks.is_empty() {
+            chunks.push(&[]);
+        }
+        let mut pages = Vec::new();
+        for (i, rows) in chunks.into_iter().enumerate() {
+            let mut lines = if i == 0 { details.clone() } else { Vec::new() };
+            lines.push(vec!["Domestic Transactions".to_owned()]);
+            lines.push(owned(&HEADER));
+            lines.push(vec![self.name.clone()]);
+            for row in rows {
+                let debit = row.amount < 0.0;
+                let rewards = match self.layout.rewards {
+                    _ if !debit => String::new(),
+                    Rewards::Points => row.points.to_string(),
+                    Rewards::Cashback => format!("{:.2}", row.cashback),
+                    Rewards::None => String::new(),
+                };
+                lines.push(vec![
+                    row.date.format("%d/%m/%Y %H:%M:%S").to_string(),
+                    row.tx.clone(),
+                    rewards,
+                    format!("{:.2}", row.amount.abs()),
+                    if debit { "" } else { "Cr" }.to_owned(),
+                ]);
+            }
+            pages.push(lines);
+        }
+        pages
+    }
+
+    // The statement as a PDF, encrypted with `password` unless it's empty.
+    // Every text is drawn in a text object of its own.
+    pub fn pdf(&self, password: &str) -> Result<Vec<u8>, lopdf::Error> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+
+        let mut kids = Vec::new();
+        for lines in self.pages() {
+            let mut operations = Vec::new();
+            for (i, texts) in lines.iter().enumerate() {
+                for (text, x) in texts.iter().zip(COLUMNS) {
+                    operations.extend([
+                        Operation::new("BT", vec![]),
+                        Operation::new("Tf", vec!["F1".into(), 8.into()]),
+                        Operation::new("Td", vec![x.into(), (800 - 16 * i as i64).into()]),
+                        Operation::new("Tj", vec![Object::string_literal(text.as_str())]),
+                        Operation::new("ET", vec![]),
+                    ]);
+                }
+            }
+            let content = Content { operations }.encode()?;
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+            kids.push(Object::Reference(doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            })));
+        }
+
+        let count = kids.len() as i64;
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => count,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        // the encryption key is derived from the file ID.
+        let id = Object::string_literal(format!("{:016}", self.transactions.len()));
+        doc.trailer.set("ID", vec![id.clone(), id]);
+
+        if !password.is_empty() {
+            let state = EncryptionState::try_from(EncryptionVersion::V2 {
+                document: &doc,
+                owner_password: password,
+                user_password: password,
+                key_length: 128,
+                permissions: Permissions::all(),
+            })?;
+            doc.encrypt(&state)?;
+        }
+
+        let mut out = Vec::new();
+        doc.save_to(&mut out)?;
+        Ok(out)
+    }
+}
@@ -0,0 +1,170 @@
+// `--forecast`: next month's spend per category, from a least squares trend
+// over the monthly spend of the parsed months, with a 95% prediction
+// interval, and the next 12 months' total at that trend, to budget with.
+use crate::categories::Categories;
+use crate::locale::Locale;
+use crate::matching;
+use crate::summary::UNCATEGORIZED;
+use crate::{Transaction, TransactionType};
+use anyhow::{anyhow, Error};
+use chrono::{Datelike, Months, NaiveDate};
+use std::collections::BTreeMap;
+
+const TOTAL: &str = "Total";
+
+// Two-sided 95% Student's t quantiles by degrees of freedom, 1 to 10; 1.96
+// past them.
+const T95: [f32; 10] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228,
+];
+
+// What a month's trend projects, and the bounds of the 95% interval.
+#[derive(Debug, Clone, Copy, Default)]
+struct Projection {
+    value: f32,
+    low: f32,
+    high: f32,
+}
+
+// Least squares line over `spend`, month 0 first, projected `ahead` months
+// past the last. Spend can't go below 0, so neither can the bounds.
+fn project(spend: &[f32], ahead: usize) -> Projection {
+    let n = spend.len() as f32;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = spend.iter().sum::<f32>() / n;
+    let sxx: f32 = (0..spend.len()).map(|x| (x as f32 - mean_x).powi(2)).sum();
+    let sxy: f32 = spend
+        .iter()
+        .enumerate()
+        .map(|(x, y)| (x as f32 - mean_x) * (y - mean_y))
+        .sum();
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+
+    let x = n - 1.0 + ahead as f32;
+    let value = intercept + slope * x;
+    // residual standard error, with n - 2 degrees of freedom.
+    let margin = match spend.len() {
+        0..=2 => 0.0,
+        len => {
+            let sse: f32 = spend
+                .iter()
+                .enumerate()
+                .map(|(x, y)| (y - intercept - slope * x as f32).powi(2))
+                .sum();
+            let s = (sse / (n - 2.0)).sqrt();
+            let t = T95.get(len - 3).copied().unwrap_or(1.96);
+            t * s * (1.0 + 1.0 / n + (x - mean_x).powi(2) / sxx).sqrt()
+        }
+    };
+    Projection {
+        value: value.max(0.0),
+        low: (value - margin).max(0.0),
+        high: (value + margin).max(0.0),
+    }
+}
+
+// Spend counts purchases net of refunds; with --link-emi, a purchase
+// converted to EMI by its installments.
+pub fn spend(transaction: &Transaction) -> f32 {
+    if transaction.emi_group.is_some() && !matching::emi_installment(transaction) {
+        return 0.0;
+    }
+    match transaction.transaction_type() {
+        TransactionType::Purchase | TransactionType::Refund => -transaction.amount,
+        _ => 0.0,
+    }
+}
+
+fn month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap()
+}
+
+// Print the forecast per category (all of the spend without `categories`)
+// and in total. Needs rows over at least two months.
+pub fn run(
+    rows: impl Iterator<Item = Transaction>,
+    categories: Option<&Categories>,
+    locale: Locale,
+) -> Result<(), Error> {
+    // category -> month -> spend
+    let mut spend_by: BTreeMap<String, BTreeMap<NaiveDate, f32>> = BTreeMap::new();
+    let mut months: Option<(NaiveDate, NaiveDate)> = None;
+    for transaction in rows {
+        let date = month(transaction.date.date());
+        months = Some(match months {
+            Some((first, last)) => (first.min(date), last.max(date)),
+            None => (date, date),
+        });
+        let spend = spend(&transaction);
+        if spend == 0.0 {
+            continue;
+        }
+        let category = categories
+            .and_then(|categories| categories.categorize(&transaction))
+            .unwrap_or(UNCATEGORIZED);
+        for key in [category, TOTAL] {
+            *spend_by
+                .entry(key.to_owned())
+                .or_default()
+                .entry(date)
+                .or_default() += spend;
+        }
+    }
+
+    let (first, last) = months.ok_or_else(|| anyhow!("no transactions to forecast from"))?;
+    // every month in between, spent in or not.
+    let all: Vec<NaiveDate> = (0..)
+        .map_while(|i| first.checked_add_months(Months::new(i)))
+        .take_while(|month| *month <= last)
+        .collect();
+    if all.len() < 2 {
+        return Err(anyhow!(
+            "a forecast needs statements over at least two months"
+        ));
+    }
+    let next = last.checked_add_months(Months::new(1)).unwrap();
+
+    println!(
+        "Forecast for {} from {} to {} ({} months)",
+        next.format("%Y-%m"),
+        first.format("%Y-%m"),
+        last.format("%Y-%m"),
+        all.len()
+    );
+    println!(
+        "{:<24} {:>14} {:>14} {:>29} {:>14} {:>29}",
+        "Category", "Last month", "Next month", "95% interval", "Next 12", "95% interval"
+    );
+    // the total last.
+    let total = spend_by.remove(TOTAL);
+    let rows = spend_by
+        .into_iter()
+        .chain(total.map(|total| (TOTAL.to_owned(), total)));
+    for (category, by_month) in rows {
+        let spend: Vec<f32> = all
+            .iter()
+            .map(|month| by_month.get(month).copied().unwrap_or_default())
+            .collect();
+        let next = project(&spend, 1);
+        // the bounds of a year add up the months', erring wide.
+        let year = (1..=12).map(|ahead| project(&spend, ahead)).fold(
+            Projection::default(),
+            |year, month| Projection {
+                value: year.value + month.value,
+                low: year.low + month.low,
+                high: year.high + month.high,
+            },
+        );
+        println!(
+            "{:<24} {:>14} {:>14} {:>29} {:>14} {:>29}",
+            category,
+            locale.amount(*spend.last().unwrap()),
+            locale.amount(next.value),
+            format!("{} - {}", locale.amount(next.low), locale.amount(next.high)),
+            locale.amount(year.value),
+            format!("{} - {}", locale.amount(year.low), locale.amount(year.high)),
+        );
+    }
+    Ok(())
+}
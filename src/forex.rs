@@ -0,0 +1,101 @@
+// `--forex-report`: international spend per currency, with the INR rate
+// each transaction was converted at, and with `--rates`, the markup over
+// the reference rate of its date.
+use crate::fx::{self, Rates};
+use crate::locale::Locale;
+use crate::Transaction;
+use std::collections::BTreeMap;
+
+// Per currency running totals.
+#[derive(Debug, Default)]
+struct Totals {
+    count: usize,
+    foreign: f32,
+    inr: f32,
+    // the INR amount at the reference rates, of the rows with one.
+    reference: f32,
+    // the INR amount of those rows.
+    inr_referenced: f32,
+}
+
+// Print a line per foreign currency debit and the totals per currency.
+// Refunds of international purchases are left out, so the markup is that of
+// what was paid.
+pub fn run(rows: impl Iterator<Item = Transaction>, rates: Option<&Rates>, locale: Locale) {
+    let mut totals: BTreeMap<&str, Totals> = BTreeMap::new();
+
+    println!(
+        "{:<10} {:<32} {:>14} {:>14} {:>9} {:>9} {:>8}",
+        "Date", "Description", "Foreign", "INR", "Rate", "Ref rate", "Markup"
+    );
+    for transaction in rows {
+        if transaction.amount >= 0.0 {
+            continue;
+        }
+        let Some((currency, foreign)) = fx::foreign_amount(&transaction.tx) else {
+            continue;
+        };
+        let inr = -transaction.amount;
+        let rate = inr / foreign;
+        let reference = rates.and_then(|rates| rates.rate(currency, transaction.date.date()));
+
+        let total = totals.entry(currency).or_default();
+        total.count += 1;
+        total.foreign += foreign;
+        total.inr += inr;
+        if let Some(reference) = reference {
+            total.reference += foreign * reference;
+            total.inr_referenced += inr;
+        }
+
+        let description: String = transaction.tx.chars().take(32).collect();
+        println!(
+            "{:<10} {:<32} {:>14} {:>14} {:>9.4} {:>9} {:>8}",
+            transaction.date.date(),
+            description,
+            format!("{} {}", currency, locale.amount(foreign)),
+            locale.amount(inr),
+            rate,
+            reference.map(|r| format!("{:.4}", r)).unwrap_or_default(),
+            reference
+                .map(|r| format!("{:.2}%", markup(rate, r)))
+                .unwrap_or_default(),
+        );
+    }
+
+    if totals.is_empty() {
+        println!("No foreign currency transactions.");
+        return;
+    }
+    println!();
+    println!(
+        "{:<8} {:>6} {:>14} {:>14} {:>9} {:>14} {:>8}",
+        "Currency", "Count", "Foreign", "INR", "Avg rate", "Markup INR", "Markup"
+    );
+    for (currency, total) in &totals {
+        let referenced = total.inr_referenced > 0.0;
+        println!(
+            "{:<8} {:>6} {:>14} {:>14} {:>9.4} {:>14} {:>8}",
+            currency,
+            total.count,
+            locale.amount(total.foreign),
+            locale.amount(total.inr),
+            total.inr / total.foreign,
+            if referenced {
+                locale.amount(total.inr_referenced - total.reference)
+            } else {
+                String::new()
+            },
+            if referenced {
+                format!("{:.2}%", markup(total.inr_referenced, total.reference))
+            } else {
+                String::new()
+            },
+        );
+    }
+}
+
+// How much `paid` is over `reference`, in percent.
+fn markup(paid: f32, reference: f32) -> f32 {
+    (paid / reference - 1.0) * 100.0
+}
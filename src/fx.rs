@@ -0,0 +1,83 @@
+// Foreign currency transactions: the amount in the currency a card was
+// charged in, which HDFC statements end international descriptions with
+// ("AMAZON WEB SERVICES SEATTLE USD 23.60"), and dated exchange rates to
+// compare the INR amounts with.
+use anyhow::{anyhow, Context, Error};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io;
+
+// Currency codes seen at the end of international descriptions.
+const CURRENCIES: &[&str] = &[
+    "USD", "EUR", "GBP", "AED", "SGD", "JPY", "AUD", "CAD", "CHF", "HKD", "THB", "MYR", "LKR",
+    "NZD", "SAR", "QAR", "CNY", "IDR", "VND", "NPR", "OMR", "KWD", "BHD", "SEK", "NOK", "DKK",
+    "ZAR", "TRY",
+];
+
+// The currency and amount a description ends with, as "USD 23.60",
+// "USD23.60" or "USD 1,499.00". Amounts are positive.
+pub fn foreign_amount(description: &str) -> Option<(&'static str, f32)> {
+    let mut words = description.split_whitespace().rev();
+    let last = words.next()?;
+    let (currency, amount) = match CURRENCIES.iter().find(|c| last.starts_with(**c)) {
+        Some(currency) if last.len() > currency.len() => (*currency, &last[currency.len()..]),
+        _ => {
+            let code = words.next()?;
+            (*CURRENCIES.iter().find(|c| **c == code)?, last)
+        }
+    };
+    let amount: f32 = amount.replace(',', "").parse().ok()?;
+    (amount > 0.0).then_some((currency, amount))
+}
+
+// Exchange rates by date, in INR per unit of each currency, read from a CSV
+// of `date,currency,rate` rows ("2025-01-31,USD,86.62"), with or without
+// that header.
+#[derive(Debug, Default)]
+pub struct Rates {
+    rates: BTreeMap<String, BTreeMap<NaiveDate, f32>>,
+}
+
+impl Rates {
+    pub fn load(path: &str) -> Result<Rates, Error> {
+        let file = File::open(path).context(format!("failed to open {}", path))?;
+        Rates::read(file).context(format!("failed to read {}", path))
+    }
+
+    pub fn read<R: io::Read>(rdr: R) -> Result<Rates, Error> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .trim(csv::Trim::All)
+            .from_reader(rdr);
+        let mut rates = Rates::default();
+        for (i, record) in rdr.records().enumerate() {
+            let record = record?;
+            if i == 0 && record.get(0) == Some("date") {
+                continue;
+            }
+            let field = |n: usize| record.get(n).unwrap_or_default();
+            let date = NaiveDate::parse_from_str(field(0), "%Y-%m-%d")
+                .map_err(|e| anyhow!("line {}: bad date {:?}: {}", i + 1, field(0), e))?;
+            let rate: f32 = field(2)
+                .parse()
+                .map_err(|_| anyhow!("line {}: bad rate {:?}", i + 1, field(2)))?;
+            rates
+                .rates
+                .entry(field(1).to_uppercase())
+                .or_default()
+                .insert(date, rate);
+        }
+        Ok(rates)
+    }
+
+    // The rate of `currency` on `date`, or the last one before it, as rates
+    // aren't published on holidays.
+    pub fn rate(&self, currency: &str, date: NaiveDate) -> Option<f32> {
+        self.rates
+            .get(currency)?
+            .range(..=date)
+            .next_back()
+            .map(|(_, rate)| *rate)
+    }
+}
@@ -0,0 +1,241 @@
+// `serve-grpc`: a gRPC service (proto/parser.proto) parsing the statements
+// sent to it, streaming their transactions back, and summarizing their spend,
+// for services that would rather not post multipart forms. Needs a build with
+// the grpc feature.
+use crate::categories::Categories;
+use anyhow::Error;
+
+// `capacity` rows are buffered between a statement's parser and its reader.
+pub fn serve(listen: &str, categories: Option<Categories>, capacity: usize) -> Result<(), Error> {
+    server::serve(listen, categories, capacity)
+}
+
+#[cfg(feature = "grpc")]
+mod server {
+    use crate::categories::{merchant_key, Categories};
+    use crate::error::ParseError;
+    use crate::summary;
+    use crate::{bank, ParseOptions, Transaction};
+    use anyhow::{anyhow, Context, Error};
+    use chrono::NaiveDate;
+    use hdfc_cc_parser_rs::parse_async;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+    use tokio_stream::wrappers::ReceiverStream;
+    use tonic::transport::Server;
+    use tonic::{Request, Response, Status};
+
+    mod proto {
+        tonic::include_proto!("hdfc_cc_parser.v1");
+    }
+    use proto::parser_service_server::{ParserService, ParserServiceServer};
+
+    // statements are sent whole, and can be past tonic's default of 4 MiB.
+    const MAX_MESSAGE: usize = 32 * 1024 * 1024;
+
+    struct Parser {
+        categories: Arc<Option<Categories>>,
+        capacity: usize,
+    }
+
+    fn categorize(categories: &Option<Categories>, transaction: &mut Transaction) {
+        if let Some(categories) = categories {
+            transaction.category = categories.categorize(transaction).map(str::to_owned);
+        }
+    }
+
+    fn message(transaction: &Transaction) -> proto::Transaction {
+        proto::Transaction {
+            id: transaction.id(),
+            date: transaction.timestamp(false),
+            description: transaction.tx.clone(),
+            merchant: merchant_key(&transaction.tx),
+            amount: transaction.amount as f64,
+            points: transaction.points,
+            cashback: transaction.cashback as f64,
+            rewards_unit: transaction.rewards_unit.to_owned(),
+            r#type: transaction.transaction_type().name().to_owned(),
+            balance: transaction.balance.map(f64::from),
+            category: transaction.category.clone().unwrap_or_default(),
+            tags: transaction.tags.clone(),
+            card: transaction.card.clone().unwrap_or_default(),
+            statement_date: transaction
+                .statement_date
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            statement_file: transaction.statement_file.clone(),
+            city: transaction.city.unwrap_or_default().to_owned(),
+            state: transaction.state.unwrap_or_default().to_owned(),
+        }
+    }
+
+    // errors are the request's, but for a statement that doesn't open.
+    fn status(e: Error) -> Status {
+        let message = format!("{:#}", e);
+        match e.downcast_ref::<ParseError>() {
+            Some(ParseError::Encrypted { .. } | ParseError::WrongPassword { .. }) => {
+                Status::permission_denied(message)
+            }
+            Some(ParseError::Closed) => Status::cancelled(message),
+            _ => Status::invalid_argument(message),
+        }
+    }
+
+    // Parse a statement of a request, sending its rows to `tx`.
+    async fn parse_request(
+        request: proto::ParseStatementRequest,
+        tx: mpsc::Sender<Transaction>,
+    ) -> Result<(), Error> {
+        let bank = match request.bank.as_str() {
+            "" => None,
+            bank => match bank::BANKS.into_iter().find(|name| *name == bank) {
+                Some(name) => Some(name),
+                None => return Err(anyhow!("unknown bank {}", bank)),
+            },
+        };
+        let mut passwords = request.passwords;
+        if passwords.is_empty() {
+            passwords.push(String::new());
+        }
+        let options = ParseOptions {
+            bank,
+            ..Default::default()
+        };
+        parse_async(
+            request.filename,
+            request.pdf,
+            request.name,
+            passwords,
+            options,
+            tx,
+        )
+        .await
+        .map(drop)
+        .map_err(Error::from)
+    }
+
+    fn date(date: &str) -> Result<Option<NaiveDate>, Error> {
+        if date.is_empty() {
+            return Ok(None);
+        }
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(Some)
+            .context(format!("{} isn't YYYY-MM-DD", date))
+    }
+
+    #[tonic::async_trait]
+    impl ParserService for Parser {
+        type ParseStatementStream = ReceiverStream<Result<proto::Transaction, Status>>;
+
+        async fn parse_statement(
+            &self,
+            request: Request<proto::ParseStatementRequest>,
+        ) -> Result<Response<Self::ParseStatementStream>, Status> {
+            let request = request.into_inner();
+            let (categories, capacity) = (self.categories.clone(), self.capacity);
+            let (out, stream) = mpsc::channel(capacity);
+            // rows are sent on as they come.
+            tokio::spawn(async move {
+                let (tx, mut rx) = mpsc::channel::<Transaction>(capacity);
+                let forward = async {
+                    while let Some(mut transaction) = rx.recv().await {
+                        categorize(&categories, &mut transaction);
+                        // the client's gone: stop, and let the parser see it.
+                        if out.send(Ok(message(&transaction))).await.is_err() {
+                            break;
+                        }
+                    }
+                    drop(rx);
+                };
+                let (parsed, ()) = tokio::join!(parse_request(request, tx), forward);
+                if let Err(e) = parsed {
+                    out.send(Err(status(e))).await.ok();
+                }
+            });
+            Ok(Response::new(ReceiverStream::new(stream)))
+        }
+
+        async fn summarize(
+            &self,
+            request: Request<proto::SummarizeRequest>,
+        ) -> Result<Response<proto::Summary>, Status> {
+            let request = request.into_inner();
+            let (from, to) = (
+                date(&request.from).map_err(status)?,
+                date(&request.to).map_err(status)?,
+            );
+            let statements = request.statements.len() as u32;
+            let mut rows = Vec::new();
+            for statement in request.statements {
+                let (tx, mut rx) = mpsc::channel(self.capacity);
+                let collect = async {
+                    while let Some(transaction) = rx.recv().await {
+                        rows.push(transaction);
+                    }
+                };
+                let (parsed, ()) = tokio::join!(parse_request(statement, tx), collect);
+                parsed.map_err(status)?;
+            }
+
+            rows.iter_mut()
+                .for_each(|transaction| categorize(&self.categories, transaction));
+            let by_category = summary::spend_by_category(rows.iter().filter(|transaction| {
+                let date = transaction.date.date();
+                from.is_none_or(|from| date >= from) && to.is_none_or(|to| date <= to)
+            }));
+            let summary = proto::Summary {
+                total: by_category
+                    .iter()
+                    .fold(0.0, |total, (_, spend, _)| total + *spend as f64),
+                categories: by_category
+                    .into_iter()
+                    .map(|(category, spend, n)| proto::CategorySpend {
+                        category,
+                        spend: spend as f64,
+                        transactions: n as u32,
+                    })
+                    .collect(),
+                statements,
+            };
+            Ok(Response::new(summary))
+        }
+    }
+
+    pub fn serve(
+        listen: &str,
+        categories: Option<Categories>,
+        capacity: usize,
+    ) -> Result<(), Error> {
+        let addr = listen
+            .parse()
+            .context(format!("invalid --listen address {}", listen))?;
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("failed to start grpc runtime")?;
+        let service = ParserServiceServer::new(Parser {
+            categories: Arc::new(categories),
+            capacity,
+        })
+        .max_decoding_message_size(MAX_MESSAGE);
+        eprintln!("serve-grpc: listening on {}", addr);
+        rt.block_on(Server::builder().add_service(service).serve(addr))
+            .context(format!("failed to serve on {}", addr))
+    }
+}
+
+#[cfg(not(feature = "grpc"))]
+mod server {
+    use crate::categories::Categories;
+    use anyhow::{anyhow, Error};
+
+    pub fn serve(
+        _listen: &str,
+        _categories: Option<Categories>,
+        _capacity: usize,
+    ) -> Result<(), Error> {
+        Err(anyhow!(
+            "serve-grpc needs a build with the grpc feature (cargo build --features grpc)"
+        ))
+    }
+}
@@ -0,0 +1,106 @@
+// `--heatmap`: a calendar heatmap of the spend per day, a column per week
+// and a row per day of the week, to spot patterns like weekend food
+// delivery.
+use crate::locale::Locale;
+use crate::matching;
+use crate::Transaction;
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use std::collections::BTreeMap;
+
+// No spend, then the quartiles of the days with some.
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+#[derive(Debug, Default)]
+pub struct Heatmap {
+    // debits per day, positive.
+    days: BTreeMap<NaiveDate, f32>,
+}
+
+impl Heatmap {
+    pub fn add(&mut self, transaction: &Transaction) {
+        if transaction.amount >= 0.0
+            || (transaction.emi_group.is_some() && !matching::emi_installment(transaction))
+        {
+            return;
+        }
+        *self.days.entry(transaction.date.date()).or_default() -= transaction.amount;
+    }
+
+    pub fn print(&self, locale: Locale) {
+        let (Some(first), Some(last)) = (self.days.keys().next(), self.days.keys().next_back())
+        else {
+            return;
+        };
+        // weeks start on Monday.
+        let start = *first - Duration::days(first.weekday().num_days_from_monday() as i64);
+        let weeks = (*last - start).num_days() / 7 + 1;
+
+        let mut spends: Vec<f32> = self.days.values().copied().collect();
+        spends.sort_by(f32::total_cmp);
+        let quartile = |q: usize| spends[(spends.len() - 1) * q / 4];
+        let bounds = [quartile(1), quartile(2), quartile(3)];
+        let shade = |spend: f32| {
+            if spend <= 0.0 {
+                SHADES[0]
+            } else {
+                SHADES[1 + bounds.iter().filter(|b| spend >= **b).count()]
+            }
+        };
+
+        // the month over its first week, where there's room.
+        let mut months = vec![' '; weeks as usize];
+        let mut free = 0;
+        for week in 0..weeks as usize {
+            let monday = start + Duration::weeks(week as i64);
+            let (new_month, day) = match week {
+                0 => (true, *first),
+                _ => (monday.day() <= 7, monday),
+            };
+            if new_month && week >= free {
+                for (i, c) in day.format("%b").to_string().chars().enumerate() {
+                    if let Some(cell) = months.get_mut(week + i) {
+                        *cell = c;
+                    }
+                }
+                free = week + 4;
+            }
+        }
+        let months: String = months.into_iter().collect();
+        println!("Spend per day");
+        println!("    {}", months);
+
+        for weekday in [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ] {
+            let mut total = 0.0;
+            let cells: String = (0..weeks)
+                .map(|week| {
+                    let day = start
+                        + Duration::weeks(week)
+                        + Duration::days(weekday.num_days_from_monday() as i64);
+                    let spend = self.days.get(&day).copied().unwrap_or_default();
+                    total += spend;
+                    shade(spend)
+                })
+                .collect();
+            println!("{} {}  {}", weekday, cells, locale.amount(total));
+        }
+        println!(
+            "    {} none  {} below {}  {} below {}  {} below {}  {} more",
+            SHADES[0],
+            SHADES[1],
+            locale.amount(bounds[0]),
+            SHADES[2],
+            locale.amount(bounds[1]),
+            SHADES[3],
+            locale.amount(bounds[2]),
+            SHADES[4],
+        );
+    }
+}
@@ -0,0 +1,745 @@
+use crate::{bank, statement, Provenance, Transaction};
+use anyhow::{anyhow, Context, Error};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Deserialize;
+use std::fs;
+
+// How a layout's table rows are put back together from the drawn texts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rows {
+    // One text per column, with the column count taken from the table header
+    // (which ends with the card holder's name). The current HDFC template.
+    Columns,
+    // Each row starts with a date; the texts up to the next date are its
+    // description and amount.
+    Dated,
+    // Bank account rows: date, narration, reference, value date, withdrawal
+    // or deposit, and the closing balance, which tells the two apart.
+    Balance,
+}
+
+// What a card earns per transaction, if the rows show it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rewards {
+    None,
+    // an integer reward points column.
+    Points,
+    // a cashback amount column, drawn before the transaction amount.
+    Cashback,
+}
+
+// A statement template: where its transaction tables are and how to read
+// their rows.
+#[derive(Debug)]
+pub struct Layout {
+    pub name: &'static str,
+    // texts only this template draws, all of which must be present (as
+    // part of a drawn text).
+    pub markers: &'static [&'static str],
+    // texts starting a transaction table.
+    pub sections: &'static [&'static str],
+    // texts ending a transaction table.
+    pub terminators: &'static [&'static str],
+    pub rows: Rows,
+    pub date_formats: &'static [&'static str],
+    pub rewards: Rewards,
+    // the rewards column's name, to label outputs with.
+    pub rewards_unit: &'static str,
+    // texts marking an amount as a credit or debit, on their own or after
+    // the amount. Amounts without one are debits.
+    pub credit_marks: &'static [&'static str],
+    pub debit_marks: &'static [&'static str],
+}
+
+// The default, the current HDFC template.
+pub const HDFC: Layout = Layout {
+    name: "hdfc",
+    markers: &[],
+    sections: &["Domestic Transactions", "International Transactions"],
+    terminators: &[],
+    rows: Rows::Columns,
+    date_formats: &["%d/%m/%Y %H:%M:%S", "%d/%m/%Y"],
+    rewards: Rewards::Points,
+    rewards_unit: "Reward Points",
+    credit_marks: &["Cr", "CR"],
+    debit_marks: &[],
+};
+
+// HDFC statements from before the redesign: a dense two-column layout with a
+// "Transaction Description"/"Amount (in Rs.)" header and no points per row.
+pub const HDFC_LEGACY: Layout = Layout {
+    name: "hdfc-legacy",
+    markers: &["Transaction Description", "Amount (in Rs.)"],
+    sections: &["Amount (in Rs.)"],
+    terminators: &[
+        "Reward Points Summary",
+        "Past Dues",
+        "Important Information",
+        "Page Total",
+    ],
+    rows: Rows::Dated,
+    // some of these statements drop the year from their rows.
+    date_formats: &["%d/%m/%Y", "%d/%m"],
+    rewards: Rewards::None,
+    rewards_unit: "Reward Points",
+    credit_marks: &["Cr", "CR"],
+    debit_marks: &[],
+};
+
+// Diners Club (Black, Privilege, ...) statements: their own section
+// headings, and the reward points column drawn before the amount.
+pub const DINERS: Layout = Layout {
+    name: "diners",
+    markers: &["Diners Club"],
+    sections: &[
+        "Domestic Transactions",
+        "International Transactions",
+        "Domestic Transaction Details",
+        "International Transaction Details",
+    ],
+    terminators: &[
+        "Reward Points Summary",
+        "Past Dues",
+        "Important Information",
+        "Page Total",
+    ],
+    rows: Rows::Dated,
+    date_formats: &["%d/%m/%Y %H:%M:%S", "%d/%m/%Y"],
+    rewards: Rewards::Points,
+    rewards_unit: "Reward Points",
+    credit_marks: &["Cr", "CR"],
+    debit_marks: &[],
+};
+
+// Millennia and MoneyBack(+) statements, with a CashBack column in place of
+// reward points.
+pub const CASHBACK: Layout = Layout {
+    name: "hdfc-cashback",
+    markers: &["CashBack"],
+    sections: &["Domestic Transactions", "International Transactions"],
+    terminators: &[
+        "CashBack Summary",
+        "Reward Points Summary",
+        "Past Dues",
+        "Important Information",
+        "Page Total",
+    ],
+    rows: Rows::Dated,
+    date_formats: &["%d/%m/%Y %H:%M:%S", "%d/%m/%Y"],
+    rewards: Rewards::Cashback,
+    rewards_unit: "CashBack",
+    credit_marks: &["Cr", "CR"],
+    debit_marks: &[],
+};
+
+// Tata Neu co-branded cards, earning NeuCoins (1 NeuCoin = ₹1, so read like
+// cashback).
+pub const TATA_NEU: Layout = Layout {
+    name: "tata-neu",
+    markers: &["NeuCoins"],
+    sections: &["Domestic Transactions", "International Transactions"],
+    terminators: &[
+        "NeuCoins Summary",
+        "Past Dues",
+        "Important Information",
+        "Page Total",
+    ],
+    rows: Rows::Dated,
+    date_formats: &["%d/%m/%Y %H:%M:%S", "%d/%m/%Y"],
+    rewards: Rewards::Cashback,
+    rewards_unit: "NeuCoins",
+    credit_marks: &["Cr", "CR"],
+    debit_marks: &[],
+};
+
+// Swiggy co-branded cards, with a cashback column credited to the card.
+pub const SWIGGY: Layout = Layout {
+    name: "swiggy",
+    markers: &["Swiggy HDFC Bank"],
+    sections: &["Domestic Transactions", "International Transactions"],
+    terminators: &[
+        "Cashback Summary",
+        "CashBack Summary",
+        "Past Dues",
+        "Important Information",
+        "Page Total",
+    ],
+    rows: Rows::Dated,
+    date_formats: &["%d/%m/%Y %H:%M:%S", "%d/%m/%Y"],
+    rewards: Rewards::Cashback,
+    rewards_unit: "Swiggy Cashback",
+    credit_marks: &["Cr", "CR"],
+    debit_marks: &[],
+};
+
+// SBI Card: "Date, Transaction Details, Amount" rows with "05 Jan 23" dates,
+// and amounts marked "C" (credit) or "D" (debit).
+pub const SBI: Layout = Layout {
+    name: "sbi",
+    markers: &["SBI Card"],
+    sections: &["TRANSACTIONS FOR", "Transactions for"],
+    terminators: &[
+        "Reward Summary",
+        "REWARD SUMMARY",
+        "Important Messages",
+        "IMPORTANT MESSAGES",
+        "Page ",
+    ],
+    rows: Rows::Dated,
+    date_formats: &["%d %b %y", "%d %b %Y", "%d/%m/%Y"],
+    rewards: Rewards::None,
+    rewards_unit: "Reward Points",
+    credit_marks: &["C"],
+    debit_marks: &["D"],
+};
+
+// American Express India: "Date, Details, Amount" rows without per row
+// rewards, credits marked "CR". Membership Rewards are summarized separately.
+pub const AMEX: Layout = Layout {
+    name: "amex",
+    markers: &["American Express"],
+    sections: &["Details", "DETAILS"],
+    terminators: &[
+        "Membership Rewards",
+        "Total of New Transactions",
+        "Important Notice",
+        "Page ",
+    ],
+    rows: Rows::Dated,
+    // %y first: %Y takes "24" as the year 24.
+    date_formats: &["%d/%m/%y", "%d/%m/%Y", "%B %d, %Y", "%d %b %Y"],
+    rewards: Rewards::None,
+    rewards_unit: "Membership Rewards Points",
+    credit_marks: &["CR", "Cr"],
+    debit_marks: &[],
+};
+
+// HDFC savings and current account statements.
+pub const HDFC_ACCOUNT: Layout = Layout {
+    name: "hdfc-account",
+    markers: &["Withdrawal Amt.", "Deposit Amt.", "Closing Balance"],
+    sections: &["Closing Balance"],
+    terminators: &[
+        "STATEMENT SUMMARY",
+        "Page No",
+        "HDFC BANK LIMITED",
+        "*Closing balance includes",
+        "Contents of this statement",
+    ],
+    rows: Rows::Balance,
+    date_formats: &["%d/%m/%y", "%d/%m/%Y"],
+    rewards: Rewards::None,
+    rewards_unit: "",
+    credit_marks: &[],
+    debit_marks: &[],
+};
+
+// Layouts tried in order by `detect`, most specific first.
+pub const LAYOUTS: [&Layout; 6] = [&TATA_NEU, &SWIGGY, &DINERS, &CASHBACK, &HDFC_LEGACY, &HDFC];
+
+// ICICI Bank credit cards: "Date, SerNo., Transaction Details, Reward
+// Points, Intl.# amount, Amount (in Rs.)" rows, credits marked "CR".
+pub const ICICI: Layout = Layout {
+    name: "icici",
+    markers: &["ICICI Bank"],
+    sections: &["Transaction Details", "TRANSACTION DETAILS"],
+    terminators: &[
+        "Total Amount due",
+        "Reward Points Summary",
+        "Important Messages",
+        "Page ",
+    ],
+    rows: Rows::Dated,
+    date_formats: &["%d/%m/%Y"],
+    rewards: Rewards::Points,
+    rewards_unit: "Reward Points",
+    credit_marks: &["Cr", "CR"],
+    debit_marks: &[],
+};
+
+// --layout: layouts read from a TOML file of [[layout]] tables, to patch
+// support for a template that differs a little from a built-in one without
+// a new build. Each starts from a built-in layout and replaces the rules it
+// sets:
+//
+//   [[layout]]
+//   name = "hdfc-2026"                  # shown as the statement's layout
+//   base = "hdfc"                       # the built-in layout, hdfc by default
+//   bank = "hdfc"                       # whose details to read, base's by default
+//   markers = ["Infinia Credit Card Statement"]
+//   sections = ["Domestic Transactions", "Domestic Transactions (contd.)"]
+//   terminators = ["Reward Points Summary"]
+//   rows = "dated"                      # columns, dated or balance
+//   date_formats = ["%d/%m/%Y"]
+//   rewards = "points"                  # none, points or cashback
+//   rewards_unit = "Reward Points"
+//   credit_marks = ["Cr"]
+//   debit_marks = []
+//
+// They're scored along with the built-in layouts, winning ties, and one
+// without markers is used in place of the default when none is detected.
+#[derive(Debug, Clone, Copy)]
+pub struct Custom {
+    // the bank whose parser reads the statement details.
+    pub bank: &'static str,
+    pub layout: &'static Layout,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutTable {
+    name: String,
+    base: Option<String>,
+    bank: Option<String>,
+    markers: Option<Vec<String>>,
+    sections: Option<Vec<String>>,
+    terminators: Option<Vec<String>>,
+    rows: Option<Rows>,
+    date_formats: Option<Vec<String>>,
+    rewards: Option<Rewards>,
+    rewards_unit: Option<String>,
+    credit_marks: Option<Vec<String>>,
+    debit_marks: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutsFile {
+    layout: Vec<LayoutTable>,
+}
+
+// Layouts are read once a run and live as long as it, like the built-in
+// ones, so their texts are leaked into the &'static strs Layout holds.
+fn leak(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
+
+fn leak_all(texts: Vec<String>) -> &'static [&'static str] {
+    Box::leak(texts.into_iter().map(leak).collect())
+}
+
+pub fn load(path: &str) -> Result<Vec<Custom>, Error> {
+    let data = fs::read_to_string(path).context(format!("failed to read {}", path))?;
+    let file: LayoutsFile = toml::from_str(&data).context(format!("failed to parse {}", path))?;
+    if file.layout.is_empty() {
+        return Err(anyhow!("{}: no layouts", path));
+    }
+    file.layout
+        .into_iter()
+        .map(|table| {
+            let base_name = table.base.as_deref().unwrap_or(HDFC.name);
+            let (parser, base) = bank::layout(base_name).ok_or_else(|| {
+                anyhow!(
+                    "{}: layout {}: unknown base layout {}",
+                    path,
+                    table.name,
+                    base_name
+                )
+            })?;
+            let bank = match table.bank.as_deref() {
+                Some(name) => bank::parser(name)
+                    .ok_or_else(|| {
+                        anyhow!("{}: layout {}: unknown bank {}", path, table.name, name)
+                    })?
+                    .name(),
+                None => parser.name(),
+            };
+            if table.date_formats.as_ref().is_some_and(Vec::is_empty) {
+                return Err(anyhow!(
+                    "{}: layout {}: date_formats can't be empty",
+                    path,
+                    table.name
+                ));
+            }
+            let layout = Layout {
+                name: leak(table.name),
+                markers: table.markers.map_or(base.markers, leak_all),
+                sections: table.sections.map_or(base.sections, leak_all),
+                terminators: table.terminators.map_or(base.terminators, leak_all),
+                rows: table.rows.unwrap_or(base.rows),
+                date_formats: table.date_formats.map_or(base.date_formats, leak_all),
+                rewards: table.rewards.unwrap_or(base.rewards),
+                rewards_unit: table.rewards_unit.map_or(base.rewards_unit, leak),
+                credit_marks: table.credit_marks.map_or(base.credit_marks, leak_all),
+                debit_marks: table.debit_marks.map_or(base.debit_marks, leak_all),
+            };
+            Ok(Custom {
+                bank,
+                layout: Box::leak(Box::new(layout)),
+            })
+        })
+        .collect()
+}
+
+impl Layout {
+    // Whether the texts drawn on a statement are this layout's. Layouts
+    // without markers are fallbacks and never detected.
+    pub fn detect(&self, texts: &[String]) -> bool {
+        self.score(texts, texts.len()).is_some()
+    }
+
+    // How well the texts drawn on a statement match the layout, its first
+    // page being the first `first_page` of them: 2 for each marker drawn on
+    // the first page, 1 for each drawn only later. None when the layout
+    // isn't detected.
+    pub fn score(&self, texts: &[String], first_page: usize) -> Option<usize> {
+        if self.markers.is_empty() {
+            return None;
+        }
+        let (first, rest) = texts.split_at(first_page.min(texts.len()));
+        let drawn = |texts: &[String], marker: &str| texts.iter().any(|t| t.contains(marker));
+        self.markers
+            .iter()
+            .map(|marker| {
+                if drawn(first, marker) {
+                    Some(2)
+                } else if drawn(rest, marker) {
+                    Some(1)
+                } else {
+                    None
+                }
+            })
+            .sum()
+    }
+
+    // The best score a statement can get.
+    pub fn max_score(&self) -> usize {
+        2 * self.markers.len()
+    }
+
+    // Parse a row date in one of the layout's formats. Formats without a year
+    // take it from `end`, the last day of the statement period: rows after
+    // it are from the year before, as a December row on a January statement.
+    pub fn parse_date(&self, s: &str, end: Option<NaiveDate>) -> Option<NaiveDateTime> {
+        let midnight = |date| NaiveDateTime::new(date, NaiveTime::from_hms_opt(0, 0, 0).unwrap());
+        let s = s.trim();
+        self.date_formats.iter().find_map(|format| {
+            if !format.contains("%Y") && !format.contains("%y") {
+                let end = end?;
+                let in_year = |year: i32| {
+                    NaiveDate::parse_from_str(&format!("{} {}", s, year), &format!("{} %Y", format))
+                        .ok()
+                };
+                return in_year(end.year())
+                    .filter(|date| *date <= end)
+                    .or_else(|| in_year(end.year() - 1))
+                    .map(midnight);
+            }
+            NaiveDateTime::parse_from_str(s, format)
+                .ok()
+                .or_else(|| NaiveDate::parse_from_str(s, format).ok().map(midnight))
+        })
+    }
+
+    // A date at the start of `text`, and the rest of it. Dates may span a
+    // few words, as in "05 Jan 23".
+    fn split_date<'a>(
+        &self,
+        text: &'a str,
+        end: Option<NaiveDate>,
+    ) -> Option<(NaiveDateTime, &'a str)> {
+        let words: Vec<(usize, &str)> = text
+            .split(' ')
+            .scan(0, |offset, word| {
+                let start = *offset;
+                *offset += word.len() + 1;
+                Some((start + word.len(), word))
+            })
+            .take(3)
+            .collect();
+
+        words.iter().rev().find_map(|&(split, _)| {
+            let date = self.parse_date(&text[..split], end)?;
+            Some((date, text[split..].trim()))
+        })
+    }
+
+    // Rebuild the rows of a `Rows::Dated` or `Rows::Balance` layout from all
+    // the texts of a statement, in drawing order. `end` is the last day of
+    // the statement period, see `parse_date`. Rows carry the indices of the
+    // texts they were read from.
+    pub fn rows(&self, texts: &[String], end: Option<NaiveDate>) -> Vec<Transaction> {
+        let mut reader = RowReader::new(self, end);
+        if self.rows == Rows::Balance {
+            reader.balance = opening_balance(texts);
+        }
+        let mut rows = reader.read(texts);
+        rows.extend(reader.finish());
+        rows
+    }
+
+    // Whether rows can be dated in a format without a year, which needs the
+    // statement period.
+    pub fn yearless(&self) -> bool {
+        self.date_formats
+            .iter()
+            .any(|format| !format.contains("%Y") && !format.contains("%y"))
+    }
+}
+
+// The opening balance of an HDFC account statement's summary, which
+// `Rows::Balance` rows are signed against.
+pub fn opening_balance(texts: &[String]) -> Option<f32> {
+    let number = |s: &str| s.trim().replace(',', "").parse::<f32>().ok();
+    statement::table_values(texts, &bank::ACCOUNT_SUMMARY, number).map(|values| values[0])
+}
+
+// Reads the rows of a `Rows::Dated` or `Rows::Balance` layout from the texts
+// of a statement as they're drawn, a page at a time. The last row read is
+// held back, as it can go on over the next page.
+pub struct RowReader<'a> {
+    layout: &'a Layout,
+    // the last day of the statement period, see `Layout::parse_date`.
+    pub end: Option<NaiveDate>,
+    // `Rows::Balance`: the balance before the next row, to tell withdrawals
+    // from deposits. The opening balance, to start with.
+    pub balance: Option<f32>,
+    in_table: bool,
+    // texts read so far.
+    read: usize,
+    // the row being read.
+    row: Option<Row>,
+    // `Rows::Balance`: the last row read, which narration wrapped past its
+    // amounts still continues.
+    last: Option<Transaction>,
+}
+
+impl<'a> RowReader<'a> {
+    pub fn new(layout: &'a Layout, end: Option<NaiveDate>) -> Self {
+        RowReader {
+            layout,
+            end,
+            balance: None,
+            in_table: false,
+            read: 0,
+            row: None,
+            last: None,
+        }
+    }
+
+    // Read the next texts drawn, returning the rows they finish.
+    pub fn read(&mut self, texts: &[String]) -> Vec<Transaction> {
+        let mut transactions = Vec::new();
+        for text in texts {
+            let token = self.read;
+            self.read += 1;
+            match self.layout.rows {
+                Rows::Balance => transactions.extend(self.read_balance(text, token)),
+                _ => transactions.extend(self.read_dated(text, token)),
+            }
+        }
+        transactions
+    }
+
+    // The rows held back, once every text is read.
+    pub fn finish(&mut self) -> Vec<Transaction> {
+        let row = match self.layout.rows {
+            Rows::Balance => None,
+            _ => self.row.take().and_then(|row| row.finish(self.layout)),
+        };
+        self.last.take().into_iter().chain(row).collect()
+    }
+
+    // The first text of the rows held back.
+    pub fn held(&self) -> Option<usize> {
+        let last = self.last.as_ref().and_then(|last| last.provenance.as_ref());
+        last.and_then(|provenance| provenance.tokens.first().copied())
+            .or_else(|| self.row.as_ref().map(|row| row.tokens[0]))
+    }
+
+    fn read_dated(&mut self, text: &str, token: usize) -> Option<Transaction> {
+        let layout = self.layout;
+        if layout
+            .sections
+            .iter()
+            .any(|s| text == *s || text.starts_with(s))
+        {
+            self.in_table = true;
+            return None;
+        }
+        if layout.terminators.iter().any(|t| text.starts_with(t)) {
+            self.in_table = false;
+            return self.row.take().and_then(|row| row.finish(layout));
+        }
+        if !self.in_table {
+            return None;
+        }
+
+        // a date (possibly followed by more text in the same draw) starts a row.
+        if let Some((date, rest)) = layout.split_date(text, self.end) {
+            let finished = self.row.take().and_then(|row| row.finish(layout));
+            let mut row = Row::new(date, token);
+            if !rest.is_empty() {
+                row.push(rest, layout);
+            }
+            self.row = Some(row);
+            return finished;
+        }
+
+        if let Some(row) = &mut self.row {
+            row.tokens.push(token);
+            row.push(text, layout);
+        }
+        None
+    }
+
+    fn read_balance(&mut self, text: &str, token: usize) -> Option<Transaction> {
+        let layout = self.layout;
+        if layout.sections.contains(&text) {
+            self.in_table = true;
+            return None;
+        }
+        if layout.terminators.iter().any(|t| text.starts_with(t)) {
+            self.in_table = false;
+            return None;
+        }
+        if !self.in_table {
+            return None;
+        }
+
+        let mut finished = None;
+        match &mut self.row {
+            // narration wrapped past the amounts continues the last row.
+            None => match layout.parse_date(text, self.end) {
+                Some(date) => {
+                    finished = self.last.take();
+                    self.row = Some(Row::new(date, token));
+                }
+                None => {
+                    if let Some(last) = &mut self.last {
+                        last.tx = format!("{} {}", last.tx, text);
+                        if let Some(provenance) = &mut last.provenance {
+                            provenance.tokens.push(token);
+                        }
+                    }
+                }
+            },
+            Some(row) => {
+                row.tokens.push(token);
+                if let Some(amount) = amount(text) {
+                    row.amounts.push(amount);
+                } else if layout.parse_date(text, self.end).is_none() && row.amounts.is_empty() {
+                    // the value date is skipped, the reference kept with the
+                    // narration.
+                    row.description.push(text.to_owned());
+                }
+            }
+        }
+
+        // withdrawal or deposit, then the closing balance, ends a row.
+        if let Some(row) = self.row.take_if(|row| row.amounts.len() == 2) {
+            let (amount, closing) = (row.amounts[0], row.amounts[1]);
+            let deposit = self.balance.is_some_and(|b| closing > b);
+            self.last = Some(Transaction {
+                date: row.date,
+                tx: row.description.join(" "),
+                amount: if deposit { amount } else { -amount },
+                balance: Some(closing),
+                provenance: Some(Provenance {
+                    tokens: row.tokens,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            });
+            self.balance = Some(closing);
+        }
+        finished
+    }
+}
+
+// A `Rows::Dated` row being collected.
+struct Row {
+    date: NaiveDateTime,
+    description: Vec<String>,
+    // every amount in the row, the last one being the transaction amount.
+    amounts: Vec<f32>,
+    credit: bool,
+    points: i32,
+    tokens: Vec<usize>,
+}
+
+impl Row {
+    // `token` is the text the row's date was read from.
+    fn new(date: NaiveDateTime, token: usize) -> Self {
+        Row {
+            date,
+            description: Vec::new(),
+            amounts: Vec::new(),
+            credit: false,
+            points: 0,
+            tokens: vec![token],
+        }
+    }
+
+    fn push(&mut self, text: &str, layout: &Layout) {
+        if layout.credit_marks.contains(&text) {
+            self.credit = true;
+            return;
+        }
+        if layout.debit_marks.contains(&text) {
+            return;
+        }
+        let marked = |marks: &[&str]| {
+            marks
+                .iter()
+                .find_map(|mark| text.strip_suffix(mark).and_then(amount))
+        };
+        if let Some(amount) = marked(layout.credit_marks) {
+            self.amounts.push(amount);
+            self.credit = true;
+            return;
+        }
+        if let Some(amount) = marked(layout.debit_marks) {
+            self.amounts.push(amount);
+            return;
+        }
+        if let Some(amount) = amount(text) {
+            self.amounts.push(amount);
+            return;
+        }
+        if layout.rewards == Rewards::Points {
+            if let Ok(p) = text.replace("- ", "-").parse::<i32>() {
+                self.points = p;
+                return;
+            }
+        }
+        // text after the amount is a continuation of the next table, not
+        // this row's description.
+        if self.amounts.is_empty() {
+            self.description.push(text.to_owned());
+        }
+    }
+
+    // Debits are negative, credits positive, like the default layout.
+    fn finish(self, layout: &Layout) -> Option<Transaction> {
+        let (&amount, rest) = self.amounts.split_last()?;
+        let cashback = match (layout.rewards, rest.last()) {
+            (Rewards::Cashback, Some(&cashback)) => cashback,
+            _ => 0.0,
+        };
+        Some(Transaction {
+            date: self.date,
+            tx: self.description.join(" "),
+            points: self.points,
+            cashback,
+            rewards_unit: layout.rewards_unit,
+            amount: if self.credit { amount } else { -amount },
+            provenance: Some(Provenance {
+                tokens: self.tokens,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+}
+
+// "1,234.50"
+fn amount(s: &str) -> Option<f32> {
+    let s = s.trim();
+    if !s.contains('.') {
+        return None;
+    }
+    s.replace(',', "").parse::<f32>().ok()
+}
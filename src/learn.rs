@@ -0,0 +1,126 @@
+use crate::categories::Categories;
+use anyhow::{anyhow, Context, Error};
+use std::collections::{BTreeMap, HashMap};
+
+// A token is kept as a rule only if at least this share of the rows it
+// appears in carry the same category.
+const MIN_PURITY: f32 = 0.9;
+
+// Build category rules from a CSV of already labeled transactions.
+//
+// Descriptions are split into tokens, and tokens that (almost) always occur
+// with one category become patterns of that category. Tokens are picked
+// greedily per category, most frequent first (and, on ties, the one found
+// closest to the start of the description, where the merchant name usually
+// sits), skipping those that don't cover any row the already picked ones
+// miss. A token has to appear in `min_support` rows of its category at
+// least, as a one-off fragment matches unrelated merchants later (rules
+// match as substrings).
+pub fn learn(
+    path: &str,
+    description_column: &str,
+    category_column: &str,
+    min_support: usize,
+) -> Result<Categories, Error> {
+    let mut rdr = csv::Reader::from_path(path).context(format!("failed to open {}", path))?;
+
+    let headers = rdr.headers().context("failed to read CSV headers")?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name))
+            .ok_or_else(|| anyhow!("column {} not found in {}", name, path))
+    };
+    let description_idx = column(description_column)?;
+    let category_idx = column(category_column)?;
+
+    // (category, tokens) per labeled row
+    let mut rows: Vec<(String, Vec<String>)> = Vec::new();
+    for record in rdr.records() {
+        let record = record.context("failed to read CSV row")?;
+        let category = record.get(category_idx).unwrap_or("").trim();
+        if category.is_empty() {
+            continue;
+        }
+        let tokens = tokenize(record.get(description_idx).unwrap_or(""));
+        rows.push((category.to_owned(), tokens));
+    }
+
+    // token -> category -> rows
+    let mut counts: HashMap<&str, HashMap<&str, usize>> = HashMap::new();
+    // token -> earliest position in a description
+    let mut positions: HashMap<&str, usize> = HashMap::new();
+    for (category, tokens) in &rows {
+        for (position, token) in tokens.iter().enumerate() {
+            let earliest = positions.entry(token.as_str()).or_insert(position);
+            *earliest = (*earliest).min(position);
+
+            *counts
+                .entry(token.as_str())
+                .or_default()
+                .entry(category.as_str())
+                .or_default() += 1;
+        }
+    }
+
+    // category -> [(token, support)] of tokens dominated by that category
+    let mut candidates: BTreeMap<&str, Vec<(&str, usize)>> = BTreeMap::new();
+    for (token, per_category) in &counts {
+        let total: usize = per_category.values().sum();
+        let (category, support) = per_category
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+            .unwrap();
+        if *support >= min_support && *support as f32 / total as f32 >= MIN_PURITY {
+            candidates
+                .entry(category)
+                .or_default()
+                .push((token, *support));
+        }
+    }
+
+    let mut categories = Categories::default();
+    for (category, mut tokens) in candidates {
+        tokens.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then(positions[a.0].cmp(&positions[b.0]))
+                .then(a.0.cmp(b.0))
+        });
+
+        let mut uncovered: Vec<&Vec<String>> = rows
+            .iter()
+            .filter(|(c, _)| c == category)
+            .map(|(_, tokens)| tokens)
+            .collect();
+
+        for (token, _) in tokens {
+            if uncovered.is_empty() {
+                break;
+            }
+            let before = uncovered.len();
+            uncovered.retain(|row| !row.iter().any(|t| t == token));
+            if uncovered.len() < before {
+                categories.learn(category, token);
+            }
+        }
+    }
+
+    Ok(categories)
+}
+
+// Split a description into unique candidate pattern tokens, in order,
+// dropping numbers and short fragments that say nothing about the merchant.
+fn tokenize(description: &str) -> Vec<String> {
+    let mut tokens: Vec<String> = Vec::new();
+    for token in description
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|token| token.chars().count() >= 3)
+        .filter(|token| !token.chars().all(|c| c.is_ascii_digit()))
+    {
+        let token = token.to_uppercase();
+        if !tokens.contains(&token) {
+            tokens.push(token);
+        }
+    }
+    tokens
+}
@@ -0,0 +1,890 @@
+// The statement parser, and the PDF text extraction it's built on, as a
+// library: `parse` reads one statement, sending its rows down a channel and
+// returning its details.
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Asia::Kolkata;
+use error::ParseError;
+use layout::Layout;
+use pdf::file::File as pdfFile;
+use pdf_tools::{DocumentFonts, FontCache, TextOp};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use statement::Statement;
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::SyncSender;
+
+#[cfg(feature = "tokio")]
+pub use nonblocking::{parse_async, parse_file_async};
+
+pub mod bank;
+pub mod categories;
+pub mod error;
+pub mod fixture;
+pub mod fx;
+pub mod layout;
+pub mod locale;
+pub mod location;
+pub mod matching;
+#[cfg(feature = "tokio")]
+mod nonblocking;
+pub mod output;
+pub mod pages;
+pub mod password;
+pub mod pdf_tools;
+pub mod points;
+pub mod redact;
+pub mod statement;
+
+// Transaction row representation. The &'static str fields come from the
+// crate's own tables, and are looked up again on rows read back from
+// --cache-dir.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transaction {
+    pub date: NaiveDateTime,
+    pub tx: String,
+    pub points: i32,
+    // cashback earned, on cards that earn it instead of points.
+    pub cashback: f32,
+    // what the statement calls its rewards: "Reward Points", "CashBack",
+    // "NeuCoins", ...
+    #[serde(skip)]
+    pub rewards_unit: &'static str,
+    pub amount: f32,
+    // running balance after the transaction, on bank account statements.
+    pub balance: Option<f32>,
+    // the statement the row came from, to trace merged outputs back.
+    pub statement_file: String,
+    pub statement_date: Option<NaiveDate>,
+    // the card number of that statement, masked as it prints it.
+    #[serde(default)]
+    pub card: Option<String>,
+    // --profiles: whose statement it is.
+    #[serde(default)]
+    pub owner: Option<String>,
+    // where the transaction took place, from the end of the description.
+    #[serde(skip)]
+    pub city: Option<&'static str>,
+    #[serde(skip)]
+    pub state: Option<&'static str>,
+    // filled in from the --categories/--tags rules before output.
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    // --match-refunds: the ID of the purchase a refund undoes, on both rows.
+    #[serde(default)]
+    pub match_id: Option<String>,
+    // --link-emi: the ID of the purchase converted to EMI, on the purchase,
+    // the credit reversing it and its installments.
+    #[serde(default)]
+    pub emi_group: Option<String>,
+    // --convert-to: the amount in that currency, at the --rates rate of its
+    // date.
+    #[serde(default)]
+    pub converted_amount: Option<f32>,
+    // with ParseOptions::provenance, where the row came from.
+    #[serde(default)]
+    pub provenance: Option<Provenance>,
+    // how many identical rows (the same purchase twice on a day) came before
+    // it in its statement, so they get IDs of their own.
+    #[serde(default)]
+    pub occurrence: usize,
+}
+
+// The texts a row was read from. Tokens number the non-empty texts drawn on
+// the statement's pages from 0, in drawing order; `page` is the 1-based page
+// of the first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+    pub page: usize,
+    pub tokens: Vec<usize>,
+    pub texts: Vec<String>,
+}
+
+// What a row is: debits are purchases (fees and charges included), credits
+// bill payments, cashback and instant discounts, or refunds and reversals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionType {
+    Purchase,
+    Payment,
+    Cashback,
+    Refund,
+}
+
+impl TransactionType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionType::Purchase => "purchase",
+            TransactionType::Payment => "payment",
+            TransactionType::Cashback => "cashback",
+            TransactionType::Refund => "refund",
+        }
+    }
+}
+
+// Descriptions of cashback credits, and of bill payments.
+const CASHBACK_CREDITS: [&str; 5] = [
+    "CASHBACK",
+    "CASH BACK",
+    "INSTANT DISCOUNT",
+    "INSTANT DISC",
+    "EMI DISCOUNT",
+];
+const PAYMENT_CREDITS: [&str; 9] = [
+    "NETBANKING TRANSFER",
+    "PAYMENT RECEIVED",
+    "CC PAYMENT",
+    "BPPY",
+    "AUTOPAY",
+    "TELE TRANSFER CREDIT",
+    "NEFT",
+    "IMPS",
+    "UPI",
+];
+
+// default values for new Transaction.
+impl Default for Transaction {
+    fn default() -> Self {
+        Transaction {
+            date: NaiveDateTime::new(
+                NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
+                NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+            ),
+            tx: "".to_owned(),
+            points: 0,
+            cashback: 0.0,
+            rewards_unit: "",
+            amount: 0.0,
+            balance: None,
+            statement_file: String::new(),
+            statement_date: None,
+            city: None,
+            state: None,
+            category: None,
+            tags: Vec::new(),
+            card: None,
+            owner: None,
+            match_id: None,
+            emi_group: None,
+            converted_amount: None,
+            provenance: None,
+            occurrence: 0,
+        }
+    }
+}
+
+impl Transaction {
+    // Deterministic ID derived from the parsed fields, stable across re-runs
+    // over the same statements. Used for deduplication by importers.
+    pub fn id(&self) -> String {
+        let mut key = format!(
+            "{}|{}|{:.2}|{}",
+            self.date, self.tx, self.amount, self.points
+        );
+        // the first of identical rows keeps the ID it always had.
+        if self.occurrence > 0 {
+            key += &format!("|{}", self.occurrence);
+        }
+        let digest = Sha256::digest(key);
+        digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    pub fn transaction_type(&self) -> TransactionType {
+        if self.amount < 0.0 {
+            return TransactionType::Purchase;
+        }
+        let tx = self.tx.to_uppercase();
+        if CASHBACK_CREDITS.iter().any(|p| tx.contains(p)) {
+            TransactionType::Cashback
+        } else if PAYMENT_CREDITS.iter().any(|p| tx.contains(p)) {
+            TransactionType::Payment
+        } else {
+            TransactionType::Refund
+        }
+    }
+
+    // The row's date as an RFC 3339 timestamp. Statements are dated in IST,
+    // so it carries +05:30, or is converted to UTC (Z) with `utc`.
+    pub fn timestamp(&self, utc: bool) -> String {
+        let ist = Kolkata.from_local_datetime(&self.date).unwrap();
+        if utc {
+            ist.with_timezone(&Utc)
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        } else {
+            ist.to_rfc3339_opts(SecondsFormat::Secs, true)
+        }
+    }
+
+    // JSON representation used by the sinks.
+    pub fn to_json(&self, utc: bool) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id(),
+            "date": self.timestamp(utc),
+            "description": self.tx,
+            "merchant": categories::merchant_key(&self.tx),
+            "city": self.city,
+            "state": self.state,
+            "points": self.points,
+            "cashback": self.cashback,
+            "rewards_unit": self.rewards_unit,
+            "amount": self.amount,
+            "type": self.transaction_type().name(),
+            "balance": self.balance,
+            "statement_file": self.statement_file,
+            "statement_date": self.statement_date.map(|d| d.format("%Y-%m-%d").to_string()),
+            "category": self.category,
+            "tags": self.tags,
+            "card": self.card,
+            "owner": self.owner,
+            "match_id": self.match_id,
+            "emi_group": self.emi_group,
+            "converted_amount": self.converted_amount,
+        })
+    }
+}
+
+// How each statement is parsed: what --bank and --pages ask for.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions<'a> {
+    // --bank, instead of detecting it.
+    pub bank: Option<&'a str>,
+    // --pages, or every page.
+    pub pages: Option<&'a pages::Pages>,
+    // --provenance: record where each row came from.
+    pub provenance: bool,
+    // --profiles: the owner of the rows.
+    pub owner: Option<&'a str>,
+    // --layout: layouts tried before the built-in ones.
+    pub layouts: &'a [layout::Custom],
+}
+
+// Parse the pdf, sending its transactions to `sender`, and return the
+// statement details.
+pub fn parse(
+    path: String,
+    data: Vec<u8>,
+    name: String,
+    passwords: &[String],
+    options: ParseOptions,
+    sender: &SyncSender<Transaction>,
+) -> Result<Statement, ParseError> {
+    let mut rows = StatementIter::new(path, data, name, passwords, options)?;
+    for row in &mut rows {
+        sender.send(row?).map_err(|_| ParseError::Closed)?;
+    }
+    Ok(rows.into_statement())
+}
+
+// Hooks into parsing a statement, for integrators who'd rather not set up a
+// channel, see `parse_visit`. They all do nothing by default.
+pub trait Visitor {
+    // a transaction table starts under `heading`, on `page` (1-based).
+    fn on_section_start(&mut self, _heading: &str, _page: usize) {}
+
+    fn on_transaction(&mut self, _transaction: Transaction) {}
+
+    // the statement details, once every page is read.
+    fn on_metadata(&mut self, _statement: &Statement) {}
+
+    // something read wrong but not bad enough to give up on the statement,
+    // as a page that couldn't be decoded. They're kept in the details too.
+    fn on_warning(&mut self, _warning: &str) {}
+}
+
+// Parse the pdf, telling `visitor` what it comes across, and return the
+// statement details.
+pub fn parse_visit(
+    path: String,
+    data: Vec<u8>,
+    name: String,
+    passwords: &[String],
+    options: ParseOptions,
+    visitor: &mut impl Visitor,
+) -> Result<Statement, ParseError> {
+    let mut rows = StatementIter::new(path, data, name, passwords, options)?;
+    // warnings told so far.
+    let mut warned = 0;
+    loop {
+        let event = rows.next_event();
+        let warnings = &rows.statement().warnings;
+        for warning in &warnings[warned..] {
+            visitor.on_warning(warning);
+        }
+        warned = warnings.len();
+        match event {
+            Some(Ok(Event::Section(heading, page))) => visitor.on_section_start(&heading, page),
+            Some(Ok(Event::Row(transaction))) => visitor.on_transaction(*transaction),
+            Some(Err(e)) => return Err(e),
+            None => break,
+        }
+    }
+    let statement = rows.into_statement();
+    visitor.on_metadata(&statement);
+    Ok(statement)
+}
+
+// Parse the pdf, handing each row to `f`, and return the statement details.
+pub fn parse_with<F: FnMut(Transaction)>(
+    path: String,
+    data: Vec<u8>,
+    name: String,
+    passwords: &[String],
+    options: ParseOptions,
+    f: F,
+) -> Result<Statement, ParseError> {
+    struct Rows<F>(F);
+
+    impl<F: FnMut(Transaction)> Visitor for Rows<F> {
+        fn on_transaction(&mut self, transaction: Transaction) {
+            (self.0)(transaction)
+        }
+    }
+
+    parse_visit(path, data, name, passwords, options, &mut Rows(f))
+}
+
+// Decodes page `i` (0-based) of an opened statement into text operators.
+type DecodePage = Box<dyn FnMut(usize) -> Result<Vec<TextOp>, pdf::error::PdfError>>;
+
+// The pages of an opened statement, decoded one at a time, and the texts
+// drawn on those read so far.
+struct PageReader<'a> {
+    decode: DecodePage,
+    count: usize,
+    // the next page to read, 0-based.
+    next: usize,
+    // --pages, or every page.
+    selected: Option<&'a pages::Pages>,
+    // every text drawn, in order, for the statement details.
+    texts: Vec<String>,
+    // the page number and first token of each page read.
+    starts: Vec<(usize, usize)>,
+}
+
+impl PageReader<'_> {
+    // The next selected page's text operators, its texts added to `texts`, or
+    // None past the last. Pages that can't be read are skipped, with a
+    // warning.
+    fn next(&mut self, warnings: &mut Vec<String>) -> Option<Vec<TextOp>> {
+        while self.next < self.count {
+            let i = self.next;
+            self.next += 1;
+            if self.selected.is_some_and(|pages| !pages.contains(i + 1)) {
+                continue;
+            }
+            let ops = match (self.decode)(i) {
+                Ok(ops) => ops,
+                Err(source) => {
+                    let e = ParseError::PdfError {
+                        page: Some(i + 1),
+                        source,
+                    };
+                    warnings.push(e.to_string());
+                    continue;
+                }
+            };
+
+            self.starts.push((i + 1, self.texts.len()));
+            for op in &ops {
+                if let TextOp::Text(s) = op {
+                    if !s.trim().is_empty() {
+                        self.texts.push(s.trim().to_owned());
+                    }
+                }
+            }
+            return Some(ops);
+        }
+        None
+    }
+}
+
+// What reading a statement comes across, in drawing order: a transaction
+// table's heading with the page it's on, or a row.
+enum Event {
+    Section(String, usize),
+    Row(Box<Transaction>),
+}
+
+// A statement's rows, read a page at a time as they're asked for, so a caller
+// after a few of them can stop without decoding the rest of the document.
+// The bank and layout are detected from the first page with text. The
+// details rows carry (statement date, card, period) are read from each page
+// until they're found, the rest of them once the last page is read. A
+// statement without a transactions table ends in NoTransactionSection.
+pub struct StatementIter<'a> {
+    name: String,
+    options: ParseOptions<'a>,
+    statement: Statement,
+    parser: &'static dyn bank::StatementParser,
+    layout: &'static Layout,
+    pages: PageReader<'a>,
+    // headings and rows read but not yet yielded.
+    pending: VecDeque<Event>,
+    // texts looked through for headings.
+    scanned: usize,
+    // `Rows::Dated` and `Rows::Balance` layouts: the rows read so far, and
+    // how many of the texts it was handed.
+    reader: layout::RowReader<'static>,
+    fed: usize,
+    // every page is read, and once its rows are yielded, the statement
+    // finished.
+    read: bool,
+    done: bool,
+    // ID -> rows yielded with it, to number identical rows.
+    occurrences: HashMap<String, usize>,
+}
+
+impl<'a> StatementIter<'a> {
+    // Open the pdf with the first of `passwords` that does, and read up to
+    // its first page with text.
+    pub fn new(
+        path: String,
+        data: Vec<u8>,
+        name: String,
+        passwords: &[String],
+        options: ParseOptions<'a>,
+    ) -> Result<Self, ParseError> {
+        // try each candidate password, keeping the last error.
+        let mut opened = Err(pdf::error::PdfError::InvalidPassword);
+        let mut used = None;
+        for (i, password) in passwords.iter().enumerate() {
+            opened = pdfFile::from_data_password(data.clone(), password.as_bytes());
+            if opened.is_ok() {
+                used = Some(i + 1).filter(|_| passwords.len() > 1);
+                break;
+            }
+        }
+
+        let file = match opened {
+            Ok(file) => file,
+            Err(pdf::error::PdfError::InvalidPassword)
+                if passwords.iter().all(String::is_empty) =>
+            {
+                return Err(ParseError::Encrypted { file: path });
+            }
+            Err(pdf::error::PdfError::InvalidPassword) => {
+                return Err(ParseError::WrongPassword {
+                    file: path,
+                    // with --bank, say how that bank's passwords are made up.
+                    hint: options.bank.and_then(bank::parser).map(|parser| {
+                        format!(
+                            "{} statement passwords are {}",
+                            parser.name().to_uppercase(),
+                            parser.password_hint()
+                        )
+                    }),
+                });
+            }
+            Err(source) => return Err(ParseError::PdfError { page: None, source }),
+        };
+
+        let mut statement = Statement::new(&path);
+        statement.password = used;
+        statement.pages = file.num_pages() as usize;
+        // fonts repeat from page to page, decode each once.
+        let mut document_fonts = DocumentFonts::default();
+        let mut pages = PageReader {
+            decode: Box::new(move |i| {
+                let page = file.get_page(i as u32)?;
+                let ops = match &page.contents {
+                    Some(content) => content.operations(&file)?,
+                    None => Vec::new(),
+                };
+                let fonts = FontCache::cached(&page, &file, &mut document_fonts);
+                Ok(pdf_tools::ops_with_text_state(&ops, &fonts))
+            }),
+            count: statement.pages,
+            next: 0,
+            selected: options.pages,
+            texts: Vec::new(),
+            starts: Vec::new(),
+        };
+
+        let first = loop {
+            match pages.next(&mut statement.warnings) {
+                Some(ops) if !pages.texts.is_empty() => break ops,
+                Some(_) => continue,
+                None => {
+                    return Err(ParseError::UnsupportedLayout {
+                        hint: match options.pages {
+                            Some(_) => format!("no text in the selected pages of {}", path),
+                            None => {
+                                format!("no text in {}, scanned statements aren't supported", path)
+                            }
+                        },
+                    })
+                }
+            }
+        };
+
+        let detection = bank::detect(
+            &pages.texts,
+            pages.texts.len(),
+            options.bank,
+            options.layouts,
+        );
+        let (parser, layout) = (detection.parser, detection.layout);
+        statement.bank = parser.name();
+        statement.layout = layout.name;
+        statement.rewards_unit = layout.rewards_unit;
+        statement.layout_score = detection.score.map(|score| (score, layout.max_score()));
+        let mut rows = StatementIter {
+            name,
+            options,
+            statement,
+            parser,
+            layout,
+            pages,
+            pending: VecDeque::new(),
+            scanned: 0,
+            reader: layout::RowReader::new(layout, None),
+            fed: 0,
+            read: false,
+            done: false,
+            occurrences: HashMap::new(),
+        };
+        rows.queue(&first, false);
+        Ok(rows)
+    }
+
+    // The statement details, its totals those of the rows yielded so far.
+    pub fn statement(&self) -> &Statement {
+        &self.statement
+    }
+
+    pub fn into_statement(self) -> Statement {
+        self.statement
+    }
+
+    // Queue the rows of the page just read, `ops`, or with `last` those
+    // still held back once every page is read. `Rows::Dated` and
+    // `Rows::Balance` rows can go on over the next page, so the last one read
+    // waits for the next. They're read with details printed anywhere in the
+    // statement: rows dated without a year wait for its period, and an
+    // account's rows for the opening balance they're signed against.
+    fn queue(&mut self, ops: &[TextOp], last: bool) {
+        let start = self.pages.starts.last().map_or(0, |&(_, start)| start);
+        if !last {
+            self.read_details(start);
+        }
+        let texts = &self.pages.texts;
+        let (rows, upto) = if self.layout.rows == layout::Rows::Columns {
+            let mut rows = page_rows(ops, &self.name, self.layout, self.statement.period_end());
+            for provenance in rows.iter_mut().filter_map(|row| row.provenance.as_mut()) {
+                provenance
+                    .tokens
+                    .iter_mut()
+                    .for_each(|token| *token += start);
+            }
+            (rows, texts.len())
+        } else {
+            let reader = &mut self.reader;
+            let ready = (reader.end.is_some() || !self.layout.yearless())
+                && (self.layout.rows != layout::Rows::Balance || reader.balance.is_some());
+            let mut rows = Vec::new();
+            if ready || last {
+                rows = reader.read(&texts[self.fed..]);
+                self.fed = texts.len();
+            }
+            if last {
+                rows.extend(reader.finish());
+            }
+            (rows, reader.held().unwrap_or(self.fed))
+        };
+
+        // headings and rows by the token they start at; headings after a row
+        // held back wait for it.
+        let starts = &self.pages.starts;
+        let mut events: Vec<(usize, Event)> = texts[self.scanned..upto.max(self.scanned)]
+            .iter()
+            .enumerate()
+            .filter(|(_, text)| self.layout.sections.iter().any(|s| text.starts_with(s)))
+            .map(|(i, text)| {
+                let token = self.scanned + i;
+                let page = starts.partition_point(|&(_, start)| start <= token);
+                let page = starts[page.saturating_sub(1)].0;
+                (token, Event::Section(text.clone(), page))
+            })
+            .collect();
+        self.scanned = upto.max(self.scanned);
+        events.extend(rows.into_iter().map(|transaction| {
+            let token = transaction
+                .provenance
+                .as_ref()
+                .and_then(|provenance| provenance.tokens.first().copied())
+                .unwrap_or_default();
+            (token, Event::Row(Box::new(transaction)))
+        }));
+        events.sort_by_key(|(token, _)| *token);
+        self.pending
+            .extend(events.into_iter().map(|(_, event)| event));
+    }
+
+    // The details rows carry, and those `Rows::Dated` and `Rows::Balance`
+    // rows are read with, from the page starting at token `start`, when
+    // they're not yet known.
+    fn read_details(&mut self, start: usize) {
+        let texts = &self.pages.texts[start..];
+        let mut details = Statement::new(&self.statement.file);
+        self.parser.read_details(&mut details, texts);
+        let statement = &mut self.statement;
+        statement.statement_date = statement.statement_date.or(details.statement_date);
+        statement.card_number = statement.card_number.take().or(details.card_number);
+        statement.period = statement.period.or(details.period);
+        self.reader.end = statement.period_end();
+        if self.layout.rows == layout::Rows::Balance && self.reader.balance.is_none() {
+            self.reader.balance = layout::opening_balance(texts);
+        }
+    }
+
+    // Once every page is read: the details from all of them, and whether
+    // the statement had a transactions table at all.
+    fn finish(&mut self) -> Result<(), ParseError> {
+        let (texts, statement) = (&self.pages.texts, &mut self.statement);
+        self.parser.read_details(statement, texts);
+        if statement.count == 0 {
+            let has_section = texts
+                .iter()
+                .any(|t| self.layout.sections.iter().any(|s| t.starts_with(s)));
+            if !has_section {
+                return Err(ParseError::NoTransactionSection {
+                    file: statement.file.clone(),
+                });
+            }
+            statement.warnings.push("no transactions found".to_owned());
+        }
+        Ok(())
+    }
+
+    // Ready a row to hand over, adding it to the statement's totals. Its
+    // tokens are looked up with --provenance, or dropped without.
+    fn yield_row(&mut self, mut transaction: Transaction) -> Transaction {
+        let (starts, texts) = (&self.pages.starts, &self.pages.texts);
+        transaction.provenance = match transaction.provenance.take() {
+            Some(mut provenance) if self.options.provenance => {
+                let first = provenance.tokens.first().copied().unwrap_or_default();
+                let page = starts.partition_point(|&(_, start)| start <= first);
+                provenance.page = starts[page.saturating_sub(1)].0;
+                provenance.texts = provenance
+                    .tokens
+                    .iter()
+                    .filter_map(|&token| texts.get(token).cloned())
+                    .collect();
+                Some(provenance)
+            }
+            _ => None,
+        };
+        transaction.owner = self.options.owner.map(str::to_owned);
+        // numbered by the ID of the cleaned up description, which rows told
+        // apart only by stray characters share.
+        self.statement.add(&mut transaction);
+        let n = self.occurrences.entry(transaction.id()).or_default();
+        transaction.occurrence = *n;
+        *n += 1;
+        transaction
+    }
+}
+
+impl StatementIter<'_> {
+    fn next_event(&mut self) -> Option<Result<Event, ParseError>> {
+        loop {
+            match self.pending.pop_front() {
+                Some(Event::Row(transaction)) => {
+                    return Some(Ok(Event::Row(Box::new(self.yield_row(*transaction)))))
+                }
+                Some(event) => return Some(Ok(event)),
+                None => {}
+            }
+            if self.done {
+                return None;
+            }
+            if self.read {
+                self.done = true;
+                return self.finish().err().map(Err);
+            }
+            match self.pages.next(&mut self.statement.warnings) {
+                Some(ops) => self.queue(&ops, false),
+                None => {
+                    self.read = true;
+                    self.queue(&[], true);
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for StatementIter<'_> {
+    type Item = Result<Transaction, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.next_event()? {
+                Ok(Event::Row(transaction)) => return Some(Ok(*transaction)),
+                Ok(Event::Section(..)) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+// The rows of the transaction tables of one page of a `Rows::Columns`
+// layout.
+fn page_rows(
+    ops: &[TextOp],
+    name: &str,
+    layout: &Layout,
+    period_end: Option<NaiveDate>,
+) -> Vec<Transaction> {
+    let mut state = ParserState::new(name, layout, period_end);
+    ops.iter()
+        .filter_map(|op| state.step(op))
+        .map(|transaction| Transaction {
+            rewards_unit: layout.rewards_unit,
+            ..transaction
+        })
+        .collect()
+}
+
+// The row state machine of `Rows::Columns` layouts, fed a page's text
+// operators one at a time. Nothing is read before a transaction section
+// heading; the table header, up to the name on the card, gives the column
+// count that ends each row. Rows carry the page's tokens they were read
+// from.
+pub struct ParserState<'a> {
+    name: &'a str,
+    layout: &'a Layout,
+    // to date rows without a year.
+    period_end: Option<NaiveDate>,
+    in_section: bool,
+    transaction: Transaction,
+    found_row: bool,
+    column_ct: usize,
+    header_assigned: bool,
+    header_column_ct: usize,
+    prev_value: &'a str,
+    // non-empty texts seen on the page, and those read into the row.
+    seen: usize,
+    tokens: Vec<usize>,
+}
+
+impl<'a> ParserState<'a> {
+    pub fn new(name: &'a str, layout: &'a Layout, period_end: Option<NaiveDate>) -> Self {
+        ParserState {
+            name,
+            layout,
+            period_end,
+            in_section: false,
+            transaction: Transaction::default(),
+            found_row: false,
+            column_ct: 0,
+            header_assigned: false,
+            header_column_ct: 0,
+            prev_value: "",
+            seen: 0,
+            tokens: Vec::new(),
+        }
+    }
+
+    // Take the next operator, returning the row it completes.
+    pub fn step(&mut self, op: &'a TextOp) -> Option<Transaction> {
+        let token = self.seen;
+        if matches!(op, TextOp::Text(s) if !s.trim().is_empty()) {
+            self.seen += 1;
+        }
+        if !self.in_section {
+            match op {
+                TextOp::Text(s) if self.layout.sections.contains(&s.trim()) => {
+                    self.in_section = true
+                }
+                _ => return None,
+            }
+        }
+
+        match op {
+            TextOp::Text(s) => {
+                // figure out the header column count from the table header.
+                // This makes it easier to figure out the end of transaction lines.
+                let d = s.trim();
+
+                if !self.header_assigned {
+                    // save this value to check in next iteration of Op::BeginText to count header columns.
+                    self.prev_value = d;
+
+                    // read till name. (that is the header columns)
+                    if d == self.name {
+                        self.header_assigned = true;
+                        // +1 considering 'Cr' (credit/debit)
+                        self.header_column_ct += 1;
+                    }
+                    return None;
+                }
+
+                self.column_ct += 1;
+                if d.is_empty() {
+                    if !self.found_row {
+                        self.column_ct -= 1;
+                    }
+                    return None;
+                }
+                self.tokens.push(token);
+
+                if self.column_ct == 1 {
+                    if let Some(tx_date) = self.layout.parse_date(d, self.period_end) {
+                        self.found_row = true;
+                        self.transaction.date = tx_date;
+                        return None;
+                    }
+                }
+
+                if self.column_ct > 2 && d.contains('.') {
+                    if let Ok(amt) = d.replace(',', "").parse::<f32>() {
+                        self.transaction.amount = -amt;
+                        return None;
+                    }
+                }
+
+                // Must be description or debit/credit representation or reward points
+                // skip reward points
+                if let Ok(p) = d.replace("- ", "-").parse::<i32>() {
+                    self.transaction.points = p;
+                    return None;
+                }
+
+                // mark it as credit
+                if self.column_ct > 3 && self.layout.credit_marks.contains(&d) {
+                    self.transaction.amount *= -1.0;
+                    return None;
+                }
+
+                // assume transaction description to be next to date
+                if self.column_ct == 2 {
+                    self.transaction.tx = d.to_owned();
+                }
+                None
+            }
+
+            TextOp::BeginText => {
+                if !self.header_assigned
+                    && !self.prev_value.is_empty()
+                    && !self.layout.sections.contains(&self.prev_value)
+                {
+                    self.header_column_ct += 1;
+                }
+                None
+            }
+
+            TextOp::EndText => {
+                if self.column_ct == 0 || self.column_ct != self.header_column_ct || !self.found_row
+                {
+                    return None;
+                }
+                // reset the row
+                self.found_row = false;
+                self.column_ct = 0;
+                self.transaction.provenance = Some(Provenance {
+                    tokens: std::mem::take(&mut self.tokens),
+                    ..Default::default()
+                });
+                Some(std::mem::take(&mut self.transaction))
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+// --locale: how reports write amounts. Plain floats are hard to read at
+// Indian magnitudes, en-IN groups them in lakhs and crores ("1,23,45,678.90").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    // "12345678.90", as before --locale.
+    #[default]
+    Plain,
+    // "1,23,45,678.90"
+    EnIn,
+    // "12,345,678.90"
+    EnUs,
+}
+
+pub const LOCALES: [&str; 3] = ["plain", "en-IN", "en-US"];
+
+// Parses a --locale value for clap.
+pub fn parse(s: &str) -> Result<Locale, String> {
+    match s {
+        "plain" => Ok(Locale::Plain),
+        "en-IN" | "en_IN" => Ok(Locale::EnIn),
+        "en-US" | "en_US" => Ok(Locale::EnUs),
+        _ => Err(format!(
+            "unknown locale {:?}, one of {}",
+            s,
+            LOCALES.join(", ")
+        )),
+    }
+}
+
+impl Locale {
+    // `amount` to two decimals, grouped. NaN and infinities, as from a
+    // conversion without a rate, are written as they are.
+    pub fn amount(&self, amount: f32) -> String {
+        let formatted = format!("{:.2}", amount);
+        let Some((whole, paise)) = formatted.split_once('.') else {
+            return formatted;
+        };
+        format!("{}.{}", self.group(whole), paise)
+    }
+
+    // Whole numbers, as reward points, grouped.
+    pub fn number(&self, n: i64) -> String {
+        self.group(&n.to_string())
+    }
+
+    // Groups the digits of `whole`, which may start with a minus sign: the
+    // last three, then in twos for en-IN or threes otherwise.
+    fn group(&self, whole: &str) -> String {
+        let (sign, digits) = match whole.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", whole),
+        };
+        let size = match self {
+            Locale::Plain => return whole.to_owned(),
+            Locale::EnIn => 2,
+            Locale::EnUs => 3,
+        };
+
+        let mut groups = Vec::new();
+        let mut rest = digits;
+        let mut next = 3;
+        while rest.len() > next {
+            let (head, tail) = rest.split_at(rest.len() - next);
+            groups.push(tail);
+            rest = head;
+            next = size;
+        }
+        groups.push(rest);
+        groups.reverse();
+        format!("{}{}", sign, groups.join(","))
+    }
+}
@@ -0,0 +1,102 @@
+// Where a card transaction took place, from the city HDFC statements end
+// most descriptions with, eg. "SWIGGY LIMITED BANGALORE".
+
+// City spellings seen at the end of descriptions, uppercase, with the city
+// and state they stand for. Multi-word names come before their last word
+// ("NAVI MUMBAI" before "MUMBAI").
+const CITIES: &[(&str, &str, &str)] = &[
+    ("NAVI MUMBAI", "Navi Mumbai", "Maharashtra"),
+    ("NEW DELHI", "New Delhi", "Delhi"),
+    ("GREATER NOIDA", "Greater Noida", "Uttar Pradesh"),
+    ("MUMBAI", "Mumbai", "Maharashtra"),
+    ("BOMBAY", "Mumbai", "Maharashtra"),
+    ("THANE", "Thane", "Maharashtra"),
+    ("PUNE", "Pune", "Maharashtra"),
+    ("NAGPUR", "Nagpur", "Maharashtra"),
+    ("NASHIK", "Nashik", "Maharashtra"),
+    ("AURANGABAD", "Aurangabad", "Maharashtra"),
+    ("DELHI", "Delhi", "Delhi"),
+    ("GURGAON", "Gurugram", "Haryana"),
+    ("GURUGRAM", "Gurugram", "Haryana"),
+    ("FARIDABAD", "Faridabad", "Haryana"),
+    ("NOIDA", "Noida", "Uttar Pradesh"),
+    ("GHAZIABAD", "Ghaziabad", "Uttar Pradesh"),
+    ("LUCKNOW", "Lucknow", "Uttar Pradesh"),
+    ("KANPUR", "Kanpur", "Uttar Pradesh"),
+    ("VARANASI", "Varanasi", "Uttar Pradesh"),
+    ("AGRA", "Agra", "Uttar Pradesh"),
+    ("BANGALORE", "Bengaluru", "Karnataka"),
+    ("BENGALURU", "Bengaluru", "Karnataka"),
+    ("MYSORE", "Mysuru", "Karnataka"),
+    ("MYSURU", "Mysuru", "Karnataka"),
+    ("MANGALORE", "Mangaluru", "Karnataka"),
+    ("HUBLI", "Hubballi", "Karnataka"),
+    ("CHENNAI", "Chennai", "Tamil Nadu"),
+    ("MADRAS", "Chennai", "Tamil Nadu"),
+    ("COIMBATORE", "Coimbatore", "Tamil Nadu"),
+    ("MADURAI", "Madurai", "Tamil Nadu"),
+    ("TRICHY", "Tiruchirappalli", "Tamil Nadu"),
+    ("HYDERABAD", "Hyderabad", "Telangana"),
+    ("SECUNDERABAD", "Secunderabad", "Telangana"),
+    ("VISAKHAPATNAM", "Visakhapatnam", "Andhra Pradesh"),
+    ("VIJAYAWADA", "Vijayawada", "Andhra Pradesh"),
+    ("KOCHI", "Kochi", "Kerala"),
+    ("COCHIN", "Kochi", "Kerala"),
+    ("ERNAKULAM", "Kochi", "Kerala"),
+    ("TRIVANDRUM", "Thiruvananthapuram", "Kerala"),
+    ("THIRUVANANTHAPURAM", "Thiruvananthapuram", "Kerala"),
+    ("KOZHIKODE", "Kozhikode", "Kerala"),
+    ("CALICUT", "Kozhikode", "Kerala"),
+    ("THRISSUR", "Thrissur", "Kerala"),
+    ("KOLKATA", "Kolkata", "West Bengal"),
+    ("CALCUTTA", "Kolkata", "West Bengal"),
+    ("AHMEDABAD", "Ahmedabad", "Gujarat"),
+    ("SURAT", "Surat", "Gujarat"),
+    ("VADODARA", "Vadodara", "Gujarat"),
+    ("BARODA", "Vadodara", "Gujarat"),
+    ("RAJKOT", "Rajkot", "Gujarat"),
+    ("GANDHINAGAR", "Gandhinagar", "Gujarat"),
+    ("JAIPUR", "Jaipur", "Rajasthan"),
+    ("JODHPUR", "Jodhpur", "Rajasthan"),
+    ("UDAIPUR", "Udaipur", "Rajasthan"),
+    ("CHANDIGARH", "Chandigarh", "Chandigarh"),
+    ("MOHALI", "Mohali", "Punjab"),
+    ("LUDHIANA", "Ludhiana", "Punjab"),
+    ("AMRITSAR", "Amritsar", "Punjab"),
+    ("BHOPAL", "Bhopal", "Madhya Pradesh"),
+    ("INDORE", "Indore", "Madhya Pradesh"),
+    ("PATNA", "Patna", "Bihar"),
+    ("RANCHI", "Ranchi", "Jharkhand"),
+    ("BHUBANESWAR", "Bhubaneswar", "Odisha"),
+    ("GUWAHATI", "Guwahati", "Assam"),
+    ("DEHRADUN", "Dehradun", "Uttarakhand"),
+    ("RAIPUR", "Raipur", "Chhattisgarh"),
+    ("GOA", "Goa", "Goa"),
+    ("PANAJI", "Panaji", "Goa"),
+    ("SRINAGAR", "Srinagar", "Jammu and Kashmir"),
+    ("JAMMU", "Jammu", "Jammu and Kashmir"),
+    ("SHIMLA", "Shimla", "Himachal Pradesh"),
+    ("PONDICHERRY", "Puducherry", "Puducherry"),
+    ("PUDUCHERRY", "Puducherry", "Puducherry"),
+];
+
+// Country codes some descriptions end with after the city.
+const COUNTRY: [&str; 2] = ["IN", "IND"];
+
+// The city and state a description ends with, if it's a known one.
+pub fn locate(description: &str) -> Option<(&'static str, &'static str)> {
+    let upper = description.to_uppercase();
+    let mut words: Vec<&str> = upper.split_whitespace().collect();
+    if words.len() > 1 && COUNTRY.contains(words.last()?) {
+        words.pop();
+    }
+
+    CITIES.iter().find_map(|&(name, city, state)| {
+        let n = name.split(' ').count();
+        // the city alone isn't a location, it's the merchant's name.
+        if words.len() <= n || words[words.len() - n..].join(" ") != name {
+            return None;
+        }
+        Some((city, state))
+    })
+}
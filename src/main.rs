@@ -1,202 +1,74 @@
 use anyhow::{Context, Error};
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-use clap::{arg, Command};
-use pdf::content::*;
-use pdf::file::File as pdfFile;
+use categories::Categories;
+use chrono::NaiveDate;
+use clap::{arg, Arg, ArgAction, ArgMatches, Command};
+use error::ParseError;
+use hdfc_cc_parser_rs::fixture::Fixture;
+use hdfc_cc_parser_rs::{
+    bank, categories, error, fx, layout, locale, location, matching, output, pages, parse,
+    password, points, redact, statement, ParseOptions, Transaction, TransactionType,
+};
 use regex::Regex;
-use std::io;
+use statement::Statement;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
 use std::process::exit;
-use std::str::FromStr;
-use std::sync::mpsc::{self, Sender};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 use std::{fs, vec};
 
-// Transaction row representation.
-#[derive(Debug, Clone)]
-pub struct Transaction {
-    pub date: NaiveDateTime,
-    pub tx: String,
-    pub points: i32,
-    pub amount: f32,
-}
-
-// default values for new Transaction.
-impl Default for Transaction {
-    fn default() -> Self {
-        Transaction {
-            date: NaiveDateTime::new(
-                NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
-                NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
-            ),
-            tx: "".to_owned(),
-            points: 0,
-            amount: 0.0,
-        }
-    }
+mod archive;
+mod budgets;
+mod cache;
+mod decrypt;
+mod destination;
+mod diff;
+mod forecast;
+mod forex;
+mod grpc;
+mod heatmap;
+mod learn;
+mod mcp;
+mod metrics;
+mod milestones;
+mod notify;
+mod profiles;
+mod query;
+mod report;
+mod review;
+mod s3;
+mod selftest;
+mod sinks;
+mod summary;
+mod template;
+mod validate;
+
+// Date in a statement's file name, in --sortformat.
+fn file_date(path: &str, sort_format: &str) -> Option<NaiveDate> {
+    let re = date_format_to_regex(sort_format);
+    re.find(path)
+        .and_then(|date_str| NaiveDate::parse_from_str(date_str.as_str(), sort_format).ok())
 }
 
-// Parse the pdf and return a list of transactions.
-pub fn parse(
-    path: String,
-    name: String,
-    _password: String,
-    sender: &Sender<Vec<String>>,
-) -> Result<(), Error> {
-    let file = pdfFile::<Vec<u8>>::open_password(path.clone(), _password.as_bytes())
-        .context(format!("failed to open file {}", path))?;
-
-    // Iterate through pages
-    for page in file.pages() {
-        if let Ok(page) = page {
-            if let Some(content) = &page.contents {
-                if let Ok(ops) = content.operations(&file) {
-                    let mut transaction = Transaction::default();
-
-                    let mut found_row = false;
-                    let mut column_ct = 0;
-                    let mut header_assigned = false;
-                    let mut header_column_ct = 0;
-                    let mut prev_value = "";
-
-                    for op in ops.iter().skip_while(|op| match op {
-                        Op::TextDraw { ref text } => {
-                            let data = text.as_bytes();
-                            if let Ok(s) = std::str::from_utf8(data) {
-                                return s.trim() != "Domestic Transactions"
-                                    && s.trim() != "International Transactions";
-                            }
-                            return true;
-                        }
-                        _ => return true,
-                    }) {
-                        match op {
-                            Op::TextDraw { ref text } => {
-                                let data = text.as_bytes();
-                                if let Ok(s) = std::str::from_utf8(data) {
-                                    // figure out the header column count from the table header.
-                                    // This makes it easier to figure out the end of transaction lines.
-                                    let d = s.trim();
-
-                                    if !header_assigned {
-                                        // save this value to check in next iteration of Op::BeginText to count header columns.
-                                        prev_value = d;
-
-                                        // read till name. (that is the header columns)
-                                        match d {
-                                            x if x == name => {
-                                                header_assigned = true;
-                                                // +1 considering 'Cr' (credit/debit)
-                                                header_column_ct += 1;
-                                                continue;
-                                            }
-                                            "" | _ => continue,
-                                        }
-                                    }
-
-                                    column_ct += 1;
-                                    if d == "" {
-                                        if !found_row {
-                                            column_ct -= 1;
-                                        }
-
-                                        continue;
-                                    }
-
-                                    if column_ct == 1 {
-                                        if let Ok(tx_date) =
-                                            NaiveDateTime::parse_from_str(d, "%d/%m/%Y %H:%M:%S")
-                                        {
-                                            found_row = true;
-                                            transaction.date = tx_date;
-                                            continue;
-                                        }
-                                        if let Ok(tx_date) =
-                                            NaiveDate::parse_from_str(d, "%d/%m/%Y")
-                                        {
-                                            found_row = true;
-                                            transaction.date = NaiveDateTime::new(
-                                                tx_date,
-                                                NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
-                                            );
-                                            continue;
-                                        }
-                                    }
-
-                                    if column_ct > 2 && d.contains(".") {
-                                        if let Ok(amt) = d.replace(",", "").parse::<f32>() {
-                                            transaction.amount = amt * -1.0;
-                                            continue;
-                                        }
-                                    }
-
-                                    // Must be description or debit/credit representation or reward points
-                                    if let Ok(tx) = String::from_str(d) {
-                                        // skip reward points
-                                        if let Ok(p) = tx.replace("- ", "-").parse::<i32>() {
-                                            transaction.points = p;
-                                            continue;
-                                        }
-
-                                        // mark it as credit
-                                        if column_ct > 3 && tx == "Cr" {
-                                            transaction.amount *= -1.0;
-                                            continue;
-                                        }
-
-                                        // assume transaction description to be next to date
-                                        if column_ct == 2 {
-                                            transaction.tx = tx;
-                                        }
-                                    }
-                                }
-                            }
-
-                            Op::BeginText => {
-                                if !header_assigned {
-                                    match prev_value {
-                                        "" => continue,
-                                        "Domestic Transactions" | "International Transactions" => {
-                                            continue
-                                        }
-                                        _ => header_column_ct += 1,
-                                    }
-                                }
-                            }
-
-                            Op::EndText => {
-                                match column_ct {
-                                    // ignore 0 column_ct
-                                    0 => continue,
-
-                                    x if x == header_column_ct && found_row => {
-                                        // write to stdout
-                                        sender
-                                            .send(vec![
-                                                transaction.date.to_string(),
-                                                transaction.tx.clone(),
-                                                transaction.points.to_string(),
-                                                transaction.amount.to_string(),
-                                            ])
-                                            .context("Failed to write row")?;
-
-                                        // reset found flag
-                                        found_row = false;
-                                        transaction = Transaction::default();
-                                        column_ct = 0;
-                                    }
-
-                                    _ => continue,
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                }
-            }
-        }
+// Months without a statement in a --dir run. A statement's month comes from
+// its statement date, or the date in its file name with --sortformat.
+fn missing_months(matches: &ArgMatches, statements: &[Statement]) -> Vec<String> {
+    if matches.get_one::<String>("dir").is_none() {
+        return Vec::new();
     }
-
-    Ok(())
+    let sort_format = matches.get_one::<String>("sortformat");
+    let dates: Vec<NaiveDate> = statements
+        .iter()
+        .filter_map(|statement| {
+            statement
+                .statement_date
+                .or_else(|| file_date(&statement.file, sort_format?))
+        })
+        .collect();
+    validate::missing_months(&dates)
 }
 
 fn date_format_to_regex(date_format: &str) -> Regex {
@@ -213,69 +85,131 @@ fn date_format_to_regex(date_format: &str) -> Regex {
     Regex::new(&regex_str).unwrap()
 }
 
-fn main() -> Result<(), Error> {
-    let matches = Command::new("HDFC credit card statement parser")
-        .arg(
-            arg!(--dir <path_to_directory>)
-                .required_unless_present("file")
-                .conflicts_with("file"),
-        )
-        .arg(
-            arg!(--file <path_to_file>)
-                .required_unless_present("dir")
-                .conflicts_with("dir"),
-        )
-        .arg(arg!(--name <name>).required(true))
-        .arg(arg!(--password <password>).required(false))
-        .arg(arg!(--sortformat <date_format>).required(false))
-        .arg(arg!(--addheaders).required(false))
-        .get_matches();
+// Arguments selecting and opening the statements, shared by the subcommands.
+fn input_args() -> Vec<Arg> {
+    vec![
+        arg!(--dir <path_to_directory>)
+            .required_unless_present_any(["file", "profiles"])
+            .conflicts_with("file"),
+        arg!(--file <path_to_file>)
+            .required_unless_present_any(["dir", "profiles"])
+            .conflicts_with("dir"),
+        arg!(--name <name>).required_unless_present("profiles"),
+        arg!(--profiles <path>)
+            .required(false)
+            .conflicts_with_all(["dir", "file", "name"]),
+        arg!(--password <password>)
+            .required(false)
+            .action(ArgAction::Append),
+        arg!(--"passwords-file" <path>).required(false),
+        arg!(--"password-pattern" <pattern>).required(false),
+        arg!(--dob <date_of_birth>)
+            .required(false)
+            .value_parser(clap::value_parser!(NaiveDate)),
+        arg!(--sortformat <date_format>).required(false),
+        arg!(--bank <bank>)
+            .required(false)
+            .value_parser(bank::BANKS),
+        arg!(--"wrong-password" <action>)
+            .value_parser(["skip", "prompt", "abort"])
+            .default_value("skip"),
+        arg!(--"skip-errors").required(false),
+        arg!(--pages <pages>)
+            .required(false)
+            .value_parser(pages::parse),
+        arg!(--"cache-dir" <dir>).required(false),
+        arg!(--verbose).required(false),
+        arg!(--layout <path>)
+            .required(false)
+            .value_parser(|path: &str| layout::load(path).map_err(|e| format!("{:#}", e))),
+        capacity_arg(),
+    ]
+}
+
+// End of run warnings of the statements whose transactions table has no
+// rows, a quiet month being worth a look rather than a failure. Only a run
+// that wrote no rows at all exits with EXIT_NO_TRANSACTIONS.
+fn report_empty(statements: &[Statement]) {
+    for statement in statements.iter().filter(|s| s.count == 0) {
+        eprintln!("{}: no transactions found", statement.file);
+    }
+    if !statements.is_empty() && statements.iter().all(|s| s.count == 0) {
+        exit(error::EXIT_NO_TRANSACTIONS);
+    }
+}
 
-    let dir_path = matches.get_one::<String>("dir");
-    let file_path = matches.get_one::<String>("file");
-    let name = matches.get_one::<String>("name");
-    let _password = matches.get_one::<String>("password");
-    let add_headers = matches.get_flag("addheaders");
+// Rows buffered between the parser and whatever reads them, so a parser
+// outrunning a slow sink waits for it instead of queueing every row in
+// memory.
+fn capacity_arg() -> Arg {
+    arg!(--"channel-capacity" <rows>)
+        .required(false)
+        .value_parser(clap::value_parser!(usize))
+        .default_value("1000")
+}
+
+fn channel_capacity(matches: &ArgMatches) -> usize {
+    *matches.get_one::<usize>("channel-capacity").unwrap()
+}
 
+// Fuzzy matching threshold for category patterns, eg. 0.85.
+fn fuzzy_arg() -> Arg {
+    arg!(--fuzzy <threshold>)
+        .required(false)
+        .value_parser(clap::value_parser!(f32))
+}
+
+// Resolve --dir/--file into the list of statement files to parse, sorted
+// by the dates in their names with --sortformat.
+fn statement_files(
+    dir_path: Option<&String>,
+    file_path: Option<&String>,
+    sort_format: Option<&String>,
+) -> Vec<String> {
     let mut pdf_files = Vec::new();
 
     // path is directory?
     if let Some(dir_path) = dir_path {
-        let entries = match fs::read_dir(dir_path) {
-            Ok(file) => file,
-            Err(err) => {
-                eprintln!("Error opening statements directory: {}", err);
-                exit(1);
+        let entries: Vec<PathBuf> = if s3::is_s3(dir_path) {
+            match s3::list(dir_path) {
+                Ok(urls) => urls.into_iter().map(PathBuf::from).collect(),
+                Err(err) => {
+                    eprintln!("Error opening statements directory: {:#}", err);
+                    exit(error::EXIT_IO);
+                }
+            }
+        } else {
+            match fs::read_dir(dir_path) {
+                Ok(file) => file
+                    .filter_map(Result::ok)
+                    .map(|entry| entry.path())
+                    .collect(),
+                Err(err) => {
+                    eprintln!("Error opening statements directory: {}", err);
+                    exit(error::EXIT_IO);
+                }
             }
         };
 
-        // Filter pdf files, sort the statement files based on dates in the file names.
+        // Filter pdf (and zip/eml) files, sort the statement files based on dates in the file names.
         pdf_files = entries
-            .filter_map(Result::ok)
-            .map(|entry| entry.path())
+            .into_iter()
             .filter(|path| {
-                path.extension()
-                    .map_or(false, |ext| ext == "pdf" || ext == "PDF")
+                path.extension().is_some_and(|ext| {
+                    ["pdf", "zip", "eml"]
+                        .iter()
+                        .any(|e| ext.eq_ignore_ascii_case(e))
+                })
             })
             .map(|path| path.to_string_lossy().to_string())
             .collect();
 
         // Sort only if there is a date format specified
-        if let Some(sort_format) = matches.get_one::<String>("sortformat") {
+        if let Some(sort_format) = sort_format {
             pdf_files.sort_by(|a, b| {
-                let re = date_format_to_regex(sort_format);
-                let a_date = match re.find(a) {
-                    Some(date_str) => {
-                        NaiveDate::parse_from_str(date_str.as_str(), sort_format).unwrap()
-                    }
-                    None => NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
-                };
-                let b_date = match re.find(b) {
-                    Some(date_str) => {
-                        NaiveDate::parse_from_str(date_str.as_str(), sort_format).unwrap()
-                    }
-                    None => NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
-                };
+                let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+                let a_date = file_date(a, sort_format).unwrap_or(epoch);
+                let b_date = file_date(b, sort_format).unwrap_or(epoch);
                 a_date.cmp(&b_date)
             })
         }
@@ -283,54 +217,1582 @@ fn main() -> Result<(), Error> {
 
     // path is file?
     if let Some(file_path) = file_path {
+        if s3::is_s3(file_path) {
+            pdf_files.push(file_path.to_string());
+            return pdf_files;
+        }
         match fs::metadata(file_path) {
             Ok(_) => pdf_files.push(file_path.to_string()),
             Err(err) => {
                 eprintln!("Error opening statement file: {}", err);
-                exit(1);
+                exit(error::EXIT_IO);
             }
         };
     }
 
-    let (tx, rx) = mpsc::channel();
+    pdf_files
+}
+
+// Read a statement from disk or s3. ZIP archives yield every PDF inside,
+// emails every PDF attachment.
+fn read_statements(path: &str) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let data = if s3::is_s3(path) {
+        s3::get(path)?
+    } else {
+        fs::read(path).context(format!("failed to read file {}", path))?
+    };
+
+    if archive::is_zip(path) {
+        return archive::pdfs(path, data);
+    }
+    if archive::is_eml(path) {
+        return archive::eml_pdfs(path, data);
+    }
+    Ok(vec![(path.to_owned(), data)])
+}
+
+// Candidate statement passwords: every --password, the lines of
+// --passwords-file, then the one derived from --password-pattern, or with
+// just --dob every known pattern. Without any, statements are opened with an
+// empty password.
+fn passwords(matches: &ArgMatches, name: &str) -> Result<Vec<String>, Error> {
+    let mut passwords: Vec<String> = matches
+        .get_many::<String>("password")
+        .map(|passwords| passwords.map(|p| password::resolve(p)).collect())
+        .transpose()?
+        .unwrap_or_default();
+
+    if let Some(path) = matches.get_one::<String>("passwords-file") {
+        let data = fs::read_to_string(path).context(format!("failed to read {}", path))?;
+        passwords.extend(
+            data.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_owned),
+        );
+    }
+
+    let dob = matches.get_one::<NaiveDate>("dob").copied();
+    match matches.get_one::<String>("password-pattern") {
+        Some(pattern) => passwords.push(password::derive(pattern, name, dob)?),
+        None if dob.is_some() => {
+            for pattern in password::KNOWN_PATTERNS {
+                passwords.push(password::derive(pattern, name, dob)?);
+            }
+        }
+        None => {}
+    }
+
+    if passwords.is_empty() {
+        passwords.push(String::new());
+    }
+    let mut seen = HashSet::new();
+    passwords.retain(|password| seen.insert(password.clone()));
+    Ok(passwords)
+}
+
+// The statements of one holder and how to open them: those of the input
+// args, or of a --profiles entry.
+struct Source {
+    // the owner of the rows, with --profiles.
+    owner: Option<String>,
+    name: String,
+    files: Vec<String>,
+    passwords: Vec<String>,
+}
+
+fn sources(matches: &ArgMatches) -> Result<Vec<Source>, Error> {
+    let sort_format = matches.get_one::<String>("sortformat");
+    let Some(path) = matches.get_one::<String>("profiles") else {
+        let name = matches
+            .get_one::<String>("name")
+            .cloned()
+            .unwrap_or_default();
+        return Ok(vec![Source {
+            owner: None,
+            files: statement_files(
+                matches.get_one::<String>("dir"),
+                matches.get_one::<String>("file"),
+                sort_format,
+            ),
+            passwords: passwords(matches, &name)?,
+            name,
+        }]);
+    };
+
+    profiles::load(path)?
+        .into_iter()
+        .map(|profile| {
+            let mut candidates = profile
+                .passwords
+                .iter()
+                .map(|p| password::resolve(p))
+                .collect::<Result<Vec<_>, _>>()?;
+            candidates.extend(passwords(matches, &profile.name)?);
+            let mut seen = HashSet::new();
+            candidates.retain(|password| seen.insert(password.clone()));
+            Ok(Source {
+                owner: Some(profile.owner().to_owned()),
+                files: statement_files(profile.dir.as_ref(), profile.file.as_ref(), sort_format),
+                name: profile.name,
+                passwords: candidates,
+            })
+        })
+        .collect()
+}
+
+// --bank and --pages.
+fn parse_options(matches: &ArgMatches) -> ParseOptions<'_> {
+    ParseOptions {
+        bank: matches.get_one::<String>("bank").map(String::as_str),
+        pages: matches.get_one::<pages::Pages>("pages"),
+        provenance: matches
+            .try_get_one::<String>("provenance")
+            .is_ok_and(|path| path.is_some()),
+        owner: None,
+        layouts: matches
+            .get_one::<Vec<layout::Custom>>("layout")
+            .map_or(&[], Vec::as_slice),
+    }
+}
+
+// How the input args ask for each statement to be parsed.
+#[derive(Clone, Copy)]
+struct Input<'a> {
+    name: &'a str,
+    options: ParseOptions<'a>,
+    // --wrong-password: skip, prompt or abort.
+    wrong_password: &'a str,
+    // --cache-dir, to serve unchanged statements from.
+    cache_dir: Option<&'a str>,
+    // --verbose: report the layout each statement is detected as.
+    verbose: bool,
+    // --channel-capacity.
+    capacity: usize,
+}
 
-    let writer_thread = thread::spawn(move || -> Result<(), Error> {
-        let mut wtr = csv::Writer::from_writer(io::stdout());
+impl<'a> Input<'a> {
+    fn new(matches: &'a ArgMatches) -> Self {
+        Input {
+            name: matches
+                .get_one::<String>("name")
+                .map(String::as_str)
+                .unwrap_or_default(),
+            options: parse_options(matches),
+            wrong_password: matches.get_one::<String>("wrong-password").unwrap(),
+            cache_dir: matches.get_one::<String>("cache-dir").map(String::as_str),
+            verbose: matches.get_flag("verbose"),
+            capacity: channel_capacity(matches),
+        }
+    }
+}
+
+// Parse one statement. When none of the passwords opens it and
+// --wrong-password is prompt, ask for the right one (kept in `passwords` for
+// the statements that follow) until it opens or the answer is empty.
+fn parse_retrying(
+    file: &str,
+    data: Vec<u8>,
+    passwords: &mut Vec<String>,
+    input: Input,
+    tx: &SyncSender<Transaction>,
+) -> Result<Statement, Error> {
+    let mut prompted = None;
+    loop {
+        let tries = match &prompted {
+            Some(password) => std::slice::from_ref(password),
+            None => passwords.as_slice(),
+        };
+        let err = match parse(
+            file.to_owned(),
+            data.clone(),
+            input.name.to_owned(),
+            tries,
+            input.options,
+            tx,
+        ) {
+            Ok(statement) => {
+                if let Some(n) = statement.password {
+                    eprintln!("{}: opened with password {} of {}", file, n, tries.len());
+                }
+                passwords.extend(prompted);
+                return Ok(statement);
+            }
+            Err(e @ (ParseError::WrongPassword { .. } | ParseError::Encrypted { .. })) => e,
+            Err(e) => return Err(e.into()),
+        };
 
-        if add_headers {
-            //  writes the header rows to CSV if user passes --addheaders param
-            wtr.write_record(&["Date", "Description", "Points", "Amount"])
-                .context("Failed to write headers")?;
+        if input.wrong_password != "prompt" {
+            return Err(err.into());
+        }
+        eprintln!("{:#}", err);
+        prompted = password::prompt(file)?;
+        if prompted.is_none() {
+            return Err(err.into());
         }
+    }
+}
+
+// parse_retrying, through --cache-dir: a statement parsed before is served
+// from the cache, and one that isn't is parsed and cached, its rows copied
+// on their way to `tx`.
+fn parse_cached(
+    file: &str,
+    data: Vec<u8>,
+    passwords: &mut Vec<String>,
+    input: Input,
+    tx: &SyncSender<Transaction>,
+) -> Result<Statement, Error> {
+    let dir = match input.cache_dir {
+        Some(dir) => dir,
+        None => return parse_retrying(file, data, passwords, input, tx),
+    };
 
-        for record in rx {
-            wtr.write_record(&record).context("Failed to write row")?;
+    let key = cache::key(&data, input.name, input.options);
+    if let Some((mut statement, rows)) = cache::get(dir, &key, input.options.layouts) {
+        // the key is the content's, which may have been moved or copied
+        // since: it's read from `file` now.
+        statement.file = file.to_owned();
+        for mut row in rows {
+            row.statement_file = file.to_owned();
+            tx.send(row).map_err(|_| ParseError::Closed)?;
         }
+        return Ok(statement);
+    }
 
-        wtr.flush().context("Error flushing to stdout")?;
-        Ok(())
+    let (rows_tx, rows_rx) = mpsc::sync_channel::<Transaction>(input.capacity);
+    let (parsed, rows) = thread::scope(|scope| {
+        let forward = scope.spawn(move || {
+            let mut rows = Vec::new();
+            for row in rows_rx {
+                // the writer's gone: stop, and let the parser see it.
+                if tx.send(row.clone()).is_err() {
+                    break;
+                }
+                rows.push(row);
+            }
+            rows
+        });
+        let parsed = parse_retrying(file, data, passwords, input, &rows_tx);
+        drop(rows_tx);
+        (parsed, forward.join().unwrap_or_default())
     });
 
-    let pass: String = match _password {
-        Some(s) => s.clone(),
-        None => "".to_string(),
+    let statement = parsed?;
+    if let Err(e) = cache::put(dir, &key, &statement, &rows) {
+        eprintln!("{}: {:#}, not cached", file, e);
+    }
+    Ok(statement)
+}
+
+// The writer for --format: the library's, but for the template format,
+// which renders the summary totals too.
+fn new_output<W: Write + 'static>(
+    format: &str,
+    out: W,
+    options: output::Options,
+) -> Result<Box<dyn output::Output>, Error> {
+    if format != "template" {
+        return output::new(format, out, options);
+    }
+    let path = options
+        .template
+        .as_deref()
+        .ok_or_else(|| anyhow::anyhow!("--format template needs --template <path>"))?;
+    Ok(Box::new(template::Template::new(out, path, options.utc)?))
+}
+
+// A --provenance line: the row's ID, and the file, page and texts it was
+// read from.
+fn provenance_json(transaction: &Transaction) -> serde_json::Value {
+    let provenance = transaction.provenance.clone().unwrap_or_default();
+    serde_json::json!({
+        "id": transaction.id(),
+        "file": transaction.statement_file,
+        "page": provenance.page,
+        "tokens": provenance.tokens,
+        "texts": provenance.texts,
+    })
+}
+
+// --stats: how fast the statements parsed, on stderr. The time includes
+// waiting on the output or sink.
+fn print_stats(statements: &[Statement], elapsed: Duration) {
+    let pages: usize = statements.iter().map(|s| s.pages).sum();
+    let rows: usize = statements.iter().map(|s| s.count).sum();
+    let secs = elapsed.as_secs_f64();
+    eprintln!(
+        "{} statements, {} pages, {} transactions in {:.2}s: {:.1} pages/sec, {:.1} transactions/sec",
+        statements.len(),
+        pages,
+        rows,
+        secs,
+        pages as f64 / secs,
+        rows as f64 / secs
+    );
+}
+
+// Files left out of the run, and why.
+type Failures = Vec<(String, Error)>;
+
+// Parse every statement selected by the input args, sending rows to `tx`
+// and returning each statement's details. A file that fails is logged and
+// left out, and returned among the failures, instead of failing the run when
+// it's a password failure (unless --wrong-password is abort) or with
+// --skip-errors.
+fn parse_statements(
+    matches: &ArgMatches,
+    tx: &SyncSender<Transaction>,
+) -> Result<(Vec<Statement>, Failures), Error> {
+    let input = Input::new(matches);
+    let sources = sources(matches)?;
+    let skip_errors = matches.get_flag("skip-errors");
+
+    let mut statements = Vec::new();
+    let mut failures = Vec::new();
+    let mut fail = |file: String, e: Error| {
+        let password = error::exit_code(&e) == error::EXIT_PASSWORD;
+        if !skip_errors && (!password || input.wrong_password == "abort") {
+            return Err(e);
+        }
+        eprintln!("{}: {:#}, skipped", file, e);
+        failures.push((file, e));
+        Ok(())
+    };
+    for source in sources {
+        let input = Input {
+            name: &source.name,
+            options: ParseOptions {
+                owner: source.owner.as_deref(),
+                ..input.options
+            },
+            ..input
+        };
+        let mut passwords = source.passwords;
+        for file in source.files {
+            let pdfs = match read_statements(&file) {
+                Ok(pdfs) => pdfs,
+                Err(e) => {
+                    fail(file, e)?;
+                    continue;
+                }
+            };
+            for (file, data) in pdfs {
+                match parse_cached(&file, data, &mut passwords, input, tx)
+                    .context("Failed to parse statement")
+                {
+                    Ok(statement) => {
+                        if input.verbose {
+                            eprintln!("{}: {}", file, detected(&statement));
+                        }
+                        statements.push(statement)
+                    }
+                    Err(e) => fail(file, e)?,
+                }
+            }
+        }
+    }
+
+    Ok((statements, failures))
+}
+
+// The bank and layout a statement was detected as, and how well it matched.
+fn detected(statement: &Statement) -> String {
+    match statement.layout_score {
+        Some((score, max)) => format!(
+            "{} ({} layout), scored {} of {}",
+            statement.bank, statement.layout, score, max
+        ),
+        None => format!(
+            "{} ({} layout), by default as no layout's markers matched",
+            statement.bank, statement.layout
+        ),
+    }
+}
+
+// End of run report of the files left out, exiting with the first one's
+// exit code if there are any.
+fn report_failures(failures: &Failures) {
+    if failures.is_empty() {
+        return;
+    }
+    eprintln!("{} files failed:", failures.len());
+    for (file, e) in failures {
+        eprintln!("  {}: {:#}", file, e);
+    }
+    exit(error::exit_code(&failures[0].1));
+}
+
+// A sender for rows that aren't needed, drained as they come so parsing
+// never waits on a full channel.
+fn discard_rows(capacity: usize) -> SyncSender<Transaction> {
+    let (tx, rx) = mpsc::sync_channel(capacity);
+    thread::spawn(move || rx.into_iter().for_each(drop));
+    tx
+}
+
+// The --categories rules, matched with --fuzzy.
+fn load_categories(matches: &ArgMatches) -> Result<Option<Categories>, Error> {
+    let Some(path) = matches.get_one::<String>("categories") else {
+        return Ok(None);
+    };
+    let mut categories = Categories::load(path)?;
+    categories.fuzzy = matches.get_one::<f32>("fuzzy").copied();
+    Ok(Some(categories))
+}
+
+// Run `parse`, collecting the rows it sends, for the subcommands that need
+// every row at once.
+fn collect<T>(
+    capacity: usize,
+    parse: impl FnOnce(&SyncSender<Transaction>) -> Result<T, Error>,
+) -> Result<(T, Vec<Transaction>), Error> {
+    let (tx, rx) = mpsc::sync_channel(capacity);
+    let collector = thread::spawn(move || rx.into_iter().collect::<Vec<_>>());
+    let parsed = parse(&tx);
+    drop(tx);
+    let rows = collector
+        .join()
+        .map_err(|e| anyhow::anyhow!("Thread panicked: {:?}", e))?;
+    Ok((parsed?, rows))
+}
+
+// Parse the statements selected by the input args, collecting their rows,
+// and the files left out.
+fn collect_rows(matches: &ArgMatches) -> Result<(Vec<Transaction>, Failures), Error> {
+    let ((_, failures), rows) = collect(channel_capacity(matches), |tx| {
+        parse_statements(matches, tx)
+    })?;
+    Ok((rows, failures))
+}
+
+// Parse the statements and open the review TUI over the collected rows.
+fn review(matches: &ArgMatches) -> Result<(), Error> {
+    let path = matches.get_one::<String>("categories").unwrap();
+
+    // start from an empty rule set if the categories file doesn't exist yet.
+    let categories = match fs::metadata(path) {
+        Ok(_) => load_categories(matches)?.unwrap_or_default(),
+        Err(_) => Categories {
+            fuzzy: matches.get_one::<f32>("fuzzy").copied(),
+            ..Default::default()
+        },
+    };
+
+    let (rows, failures) = collect_rows(matches)?;
+    review::run(rows, categories, path)?;
+    report_failures(&failures);
+    Ok(())
+}
+
+// Parse the statements and run --sql over the collected rows.
+fn query(matches: &ArgMatches) -> Result<(), Error> {
+    let categories = load_categories(matches)?;
+    let (rows, failures) = collect_rows(matches)?;
+    let table = query::run(
+        rows,
+        categories.as_ref(),
+        matches.get_one::<String>("sql").unwrap(),
+    )?;
+    query::print(&table, matches.get_one::<String>("format").unwrap())?;
+    report_failures(&failures);
+    Ok(())
+}
+
+// Parse the statements and serve them, and any statement asked for, to MCP
+// clients over stdio.
+fn mcp(matches: &ArgMatches) -> Result<(), Error> {
+    let categories = load_categories(matches)?;
+    let (rows, failures) = collect_rows(matches)?;
+    for (file, e) in &failures {
+        eprintln!("{}: {:#}, not served", file, e);
+    }
+
+    // stdin is the client's, so there's no prompting for passwords.
+    let input = Input {
+        wrong_password: "skip",
+        ..Input::new(matches)
+    };
+    let known = passwords(matches, input.name)?;
+    let parse_statement = |path: &str, password: Option<&str>| -> Result<Vec<Transaction>, Error> {
+        let mut candidates: Vec<String> = password
+            .map(str::to_owned)
+            .into_iter()
+            .chain(known.iter().cloned())
+            .collect();
+        let ((), rows) = collect(input.capacity, |tx| {
+            read_statements(path).and_then(|pdfs| {
+                pdfs.into_iter().try_for_each(|(file, data)| {
+                    parse_cached(&file, data, &mut candidates, input, tx).map(drop)
+                })
+            })
+        })?;
+        Ok(rows)
+    };
+    mcp::serve(rows, categories.as_ref(), parse_statement)
+}
+
+// Parse the statements and render the month's report, sent by email with
+// --email.
+fn report(matches: &ArgMatches) -> Result<(), Error> {
+    let categories = load_categories(matches)?;
+    let month = matches
+        .get_one::<String>("month")
+        .map(|month| {
+            NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+                .context(format!("--month should be YYYY-MM, got {}", month))
+        })
+        .transpose()?;
+    // the config is read before parsing, so a bad one fails fast.
+    let smtp = match matches.get_flag("email") {
+        true => Some(report::Smtp::load(
+            matches.get_one::<String>("config").unwrap(),
+        )?),
+        false => None,
+    };
+
+    let (mut rows, failures) = collect_rows(matches)?;
+    if let Some(categories) = &categories {
+        for transaction in &mut rows {
+            transaction.category = categories.categorize(transaction).map(str::to_owned);
+        }
+    }
+
+    let locale = *matches.get_one::<locale::Locale>("locale").unwrap();
+    let (subject, html) = report::render(&rows, month, locale)?;
+    let output = matches.get_one::<String>("output");
+    if output.is_some() || smtp.is_none() {
+        let destination = destination::Destination::new(output.map(String::as_str));
+        let written = destination.writer().and_then(|mut out| {
+            out.write_all(html.as_bytes())
+                .and(out.flush())
+                .map_err(Error::from)
+        });
+        match written {
+            Ok(()) => destination.commit()?,
+            Err(e) => {
+                destination.discard();
+                return Err(e);
+            }
+        }
+    }
+    if let Some(smtp) = smtp {
+        smtp.send(&subject, &html)?;
+        eprintln!("report: sent {}", subject);
+    }
+    report_failures(&failures);
+    Ok(())
+}
+
+// Parse the statements and cross-check their details, exiting with
+// EXIT_VALIDATION on any problem, or the exit code of a file that failed.
+fn validate(matches: &ArgMatches) -> Result<(), Error> {
+    let tx = discard_rows(channel_capacity(matches));
+    let (statements, failures) = parse_statements(matches, &tx)?;
+
+    let mut problems: Vec<String> = failures
+        .iter()
+        .map(|(file, e)| format!("{}: {:#}", file, e))
+        .collect();
+    problems.extend(validate::check(&statements));
+    for month in missing_months(matches, &statements) {
+        problems.push(format!("no statement for {}", month));
+    }
+    if problems.is_empty() {
+        println!("{} statements ok", statements.len());
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    exit(
+        failures
+            .first()
+            .map_or(error::EXIT_VALIDATION, |(_, e)| error::exit_code(e)),
+    );
+}
+
+// --dry-run: list each statement that would be parsed, whether it opens,
+// its detected bank and layout and its period, without writing any rows.
+fn dry_run(matches: &ArgMatches) -> Result<(), Error> {
+    let options = parse_options(matches);
+
+    let tx = discard_rows(channel_capacity(matches));
+    for source in sources(matches)? {
+        for file in &source.files {
+            let pdfs = match read_statements(file) {
+                Ok(pdfs) => pdfs,
+                Err(e) => {
+                    println!("{}: {:#}", file, e);
+                    continue;
+                }
+            };
+            for (file, data) in pdfs {
+                let statement = match parse(
+                    file.clone(),
+                    data,
+                    source.name.clone(),
+                    &source.passwords,
+                    options,
+                    &tx,
+                ) {
+                    Ok(statement) => statement,
+                    Err(e) => {
+                        println!("{}: {}", file, e);
+                        continue;
+                    }
+                };
+                let period = match statement.period {
+                    Some((from, to)) => format!("{} to {}", from, to),
+                    None => "period not found".to_owned(),
+                };
+                println!(
+                    "{}: opens, {} ({} layout), {}",
+                    file, statement.bank, statement.layout, period
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+// Write a synthetic statement to --output, see `fixture`.
+fn gen_fixture(matches: &ArgMatches) -> Result<(), Error> {
+    let output = matches.get_one::<String>("output").unwrap();
+    let fixture = Fixture::sample(
+        matches.get_one::<String>("name").unwrap(),
+        *matches.get_one::<usize>("rows").unwrap(),
+    );
+    let pdf = fixture.pdf(matches.get_one::<String>("password").unwrap())?;
+    fs::write(output, pdf).context(format!("failed to write {}", output))?;
+
+    // the CSV a run over it writes with --addheaders, for `selftest`.
+    if let Some(path) = matches.get_one::<String>("expected") {
+        let out = fs::File::create(path).context(format!("failed to create {}", path))?;
+        let mut csv = output::new(
+            "csv",
+            out,
+            output::Options {
+                add_headers: true,
+                ..Default::default()
+            },
+        )?;
+        for transaction in &fixture.transactions {
+            csv.write(transaction)?;
+        }
+        csv.finish()?;
+    }
+    Ok(())
+}
+
+// Write a decrypted copy of every statement selected by the input args to
+// --output-dir.
+fn decrypt(matches: &ArgMatches) -> Result<(), Error> {
+    let dir = matches.get_one::<String>("output-dir").unwrap();
+    let force = matches.get_flag("force");
+
+    let mut failures = Vec::new();
+    let mut written = HashSet::new();
+    for source in sources(matches)? {
+        for file in source.files {
+            let saved = read_statements(&file).and_then(|pdfs| {
+                for (file, data) in pdfs {
+                    let out =
+                        decrypt::save(&file, &data, &source.passwords, dir, &mut written, force)?;
+                    eprintln!("{}: wrote {}", file, out.display());
+                }
+                Ok(())
+            });
+            match saved {
+                Err(e) if matches.get_flag("skip-errors") => {
+                    eprintln!("{}: {:#}, skipped", file, e);
+                    failures.push((file, e));
+                }
+                result => result?,
+            }
+        }
+    }
+    report_failures(&failures);
+    Ok(())
+}
+
+// Category rule maintenance commands.
+fn categorize(matches: &ArgMatches) -> Result<(), Error> {
+    match matches.subcommand() {
+        Some(("learn", sub_matches)) => {
+            let categories = learn::learn(
+                sub_matches.get_one::<String>("labeled").unwrap(),
+                sub_matches.get_one::<String>("description-column").unwrap(),
+                sub_matches.get_one::<String>("category-column").unwrap(),
+                *sub_matches.get_one::<usize>("min-support").unwrap(),
+            )?;
+            categories.save(sub_matches.get_one::<String>("output").unwrap())
+        }
+        Some(("check", sub_matches)) => {
+            let path = sub_matches.get_one::<String>("categories").unwrap();
+            let categories = Categories::load(path)?;
+
+            let problems = categories.check();
+            if problems.is_empty() {
+                println!("{}: {} rules ok", path, categories.rules.len());
+                return Ok(());
+            }
+            for problem in &problems {
+                println!("{}: {}", path, problem);
+            }
+            exit(error::EXIT_VALIDATION);
+        }
+        Some(("test", sub_matches)) => {
+            let mut categories =
+                Categories::load(sub_matches.get_one::<String>("categories").unwrap())?;
+            categories.fuzzy = sub_matches.get_one::<f32>("fuzzy").copied();
+            let transaction = Transaction {
+                tx: sub_matches
+                    .get_one::<String>("description")
+                    .unwrap()
+                    .clone(),
+                amount: *sub_matches.get_one::<f32>("amount").unwrap_or(&0.0),
+                ..Default::default()
+            };
+
+            let matched = categories.explain(&transaction);
+            let (rule, reason) = match matched.first() {
+                Some(m) => m,
+                None => {
+                    println!("no rule matched, uncategorized");
+                    exit(1);
+                }
+            };
+
+            println!("category: {}", rule.category);
+            println!("priority: {}", rule.priority);
+            println!("matched:  {}", reason);
+            for (rule, reason) in &matched[1..] {
+                println!(
+                    "also matched {} (priority {}) on {}",
+                    rule.category, rule.priority, reason
+                );
+            }
+            Ok(())
+        }
+        _ => unreachable!("subcommand is required"),
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("Error: {:?}", e);
+        exit(error::exit_code(&e));
+    }
+}
+
+fn run() -> Result<(), Error> {
+    let matches = Command::new("HDFC credit card statement parser")
+        .subcommand_negates_reqs(true)
+        .args_conflicts_with_subcommands(true)
+        .args(input_args())
+        .arg(arg!(--addheaders).required(false))
+        .arg(
+            arg!(--format <format>)
+                .value_parser(output::FORMATS)
+                .default_value("csv"),
+        )
+        .arg(arg!(--output <path>).required(false))
+        .arg(arg!(--template <path>).required_if_eq("format", "template"))
+        .arg(arg!(--accounts <path>).required(false))
+        .arg(
+            arg!(--columns <columns>)
+                .required(false)
+                .value_delimiter(',')
+                .value_parser(output::COLUMNS)
+                .conflicts_with("sink"),
+        )
+        .arg(arg!(--"meta-dir" <path>).required(false))
+        .arg(
+            arg!(--provenance <path>)
+                .required(false)
+                .conflicts_with_all(["summary", "dry-run"]),
+        )
+        .arg(
+            arg!(--"dry-run")
+                .required(false)
+                .conflicts_with_all(["sink", "output", "summary"]),
+        )
+        .arg(arg!(--"excel-safe").required(false).conflicts_with("sink"))
+        .arg(
+            arg!(--"sign-convention" <convention>)
+                .value_parser(output::SIGN_CONVENTIONS)
+                .default_value("debit-negative")
+                .conflicts_with("sink"),
+        )
+        .arg(arg!(--utc).required(false))
+        .arg(arg!(--redact).required(false))
+        .arg(
+            arg!(--"redact-merchants")
+                .required(false)
+                .requires("redact"),
+        )
+        .arg(arg!(--stats).required(false))
+        .arg(
+            arg!(--"split-by" <key>)
+                .required(false)
+                .value_parser(["month"])
+                .requires("output-dir")
+                .conflicts_with_all(["sink", "summary"]),
+        )
+        .arg(
+            arg!(--"output-dir" <path>)
+                .required(false)
+                .requires("split-by")
+                .conflicts_with("output"),
+        )
+        .arg(
+            arg!(--sink <sink>)
+                .required(false)
+                .value_parser(sinks::SINKS)
+                .conflicts_with_all(["format", "summary", "output"]),
+        )
+        .arg(arg!(--url <url>).required(false).requires("sink"))
+        .arg(arg!(--token <token>).required(false).requires("sink"))
+        .arg(arg!(--secret <key>).required(false).requires("sink"))
+        .arg(
+            arg!(--"batch-size" <n>)
+                .required(false)
+                .value_parser(clap::value_parser!(usize))
+                .default_value("100"),
+        )
+        .arg(
+            arg!(--account <name>)
+                .required(false)
+                .default_value("HDFC Credit Card"),
+        )
+        .arg(
+            arg!(--credentials <path_to_service_account_json>)
+                .required(false)
+                .requires("sink"),
+        )
+        .arg(arg!(--spreadsheet <id>).required(false).requires("sink"))
+        .arg(
+            arg!(--worksheet <name>)
+                .required(false)
+                .default_value("Sheet1"),
+        )
+        .arg(arg!(--brokers <servers>).required(false).requires("sink"))
+        .arg(
+            arg!(--"schema-registry" <url>)
+                .required(false)
+                .requires("sink"),
+        )
+        .arg(arg!(--database <id>).required(false).requires("sink"))
+        .arg(arg!(--group <id>).required(false).requires("sink"))
+        .arg(
+            arg!(--topic <topic>)
+                .required(false)
+                .default_value("transactions"),
+        )
+        .arg(arg!(--categories <path>).required(false))
+        .arg(fuzzy_arg().requires("categories"))
+        .arg(arg!(--summary).required(false))
+        .arg(
+            arg!(--"summary-by" <key>)
+                .value_parser(["category", "city", "state"])
+                .default_value("category")
+                .requires("summary"),
+        )
+        .arg(arg!(--heatmap).required(false).requires("summary"))
+        .arg(
+            arg!(--"points-expiry" <months>)
+                .value_parser(clap::value_parser!(u32))
+                .default_value("24"),
+        )
+        .arg(
+            arg!(--locale <locale>)
+                .value_parser(locale::parse)
+                .default_value("plain"),
+        )
+        .arg(
+            arg!(--interactive)
+                .required(false)
+                .requires("summary")
+                .requires("categories"),
+        )
+        .arg(arg!(--tags <path>).required(false))
+        .arg(arg!(--"forex-report").required(false).conflicts_with_all([
+            "summary",
+            "sink",
+            "output",
+            "dry-run",
+            "provenance",
+        ]))
+        .arg(
+            arg!(--"convert-to" <currency>)
+                .required(false)
+                .requires("rates"),
+        )
+        .arg(arg!(--rates <path>).required(false))
+        .arg(arg!(--forecast).required(false).conflicts_with_all([
+            "summary",
+            "forex-report",
+            "sink",
+            "output",
+            "dry-run",
+            "provenance",
+        ]))
+        .arg(arg!(--budgets <path>).required(false).conflicts_with_all([
+            "summary",
+            "forex-report",
+            "forecast",
+            "sink",
+            "output",
+            "dry-run",
+            "provenance",
+        ]))
+        .arg(
+            arg!(--milestone <amount>)
+                .required(false)
+                .action(ArgAction::Append)
+                .value_parser(milestones::parse)
+                .requires("anniversary")
+                .conflicts_with_all([
+                    "summary",
+                    "forex-report",
+                    "sink",
+                    "output",
+                    "dry-run",
+                    "provenance",
+                ]),
+        )
+        .arg(
+            arg!(--anniversary <date>)
+                .required(false)
+                .value_parser(milestones::parse_anniversary)
+                .requires("milestone"),
+        )
+        .arg(arg!(--"match-refunds").required(false))
+        .arg(arg!(--"link-emi").required(false))
+        .arg(arg!(--"metrics-out" <path>).required(false))
+        .arg(arg!(--notify <target>).required(false))
+        .arg(
+            arg!(--"notify-token" <token>)
+                .required(false)
+                .requires("notify"),
+        )
+        .arg(
+            arg!(--"refund-window" <days>)
+                .value_parser(clap::value_parser!(i64))
+                .default_value("60")
+                .requires("match-refunds"),
+        )
+        .arg(
+            arg!(--tag <tag>)
+                .required(false)
+                .action(ArgAction::Append)
+                .requires("tags"),
+        )
+        .subcommand(
+            Command::new("review")
+                .about("Review parsed transactions and categorize them interactively")
+                .args(input_args())
+                .arg(arg!(--categories <path>).required(true))
+                .arg(fuzzy_arg()),
+        )
+        .subcommand(
+            Command::new("query")
+                .about("Run SQL over the parsed transactions, as the table `transactions`")
+                .args(input_args())
+                .arg(arg!(--sql <sql>).required(true))
+                .arg(arg!(--categories <path>).required(false))
+                .arg(fuzzy_arg().requires("categories"))
+                .arg(
+                    arg!(--format <format>)
+                        .value_parser(["table", "csv"])
+                        .default_value("table"),
+                ),
+        )
+        .subcommand(
+            Command::new("mcp")
+                .about("Serve the statements to LLM assistants over the Model Context Protocol on stdio")
+                .args(input_args())
+                .arg(arg!(--categories <path>).required(false))
+                .arg(fuzzy_arg().requires("categories")),
+        )
+        .subcommand(
+            Command::new("serve-grpc")
+                .about("Parse and summarize statements sent over gRPC (proto/parser.proto)")
+                .arg(arg!(--listen <address>).default_value("127.0.0.1:50051"))
+                .arg(capacity_arg())
+                .arg(arg!(--categories <path>).required(false))
+                .arg(fuzzy_arg().requires("categories")),
+        )
+        .subcommand(
+            Command::new("report")
+                .about("Render the month's spend as an HTML report, or email it with --email")
+                .args(input_args())
+                .arg(arg!(--categories <path>).required(false))
+                .arg(fuzzy_arg().requires("categories"))
+                .arg(arg!(--month <month>).required(false))
+                .arg(arg!(--output <path>).required(false))
+                .arg(arg!(--email).required(false).requires("config"))
+                .arg(arg!(--config <path>).required(false))
+                .arg(
+                    arg!(--locale <locale>)
+                        .value_parser(locale::parse)
+                        .default_value("plain"),
+                ),
+        )
+        .subcommand(
+            Command::new("validate")
+                .about("Check statement details and the balance chain across statements")
+                .args(input_args()),
+        )
+        .subcommand(
+            Command::new("decrypt")
+                .about("Write unencrypted copies of the statements")
+                .args(input_args())
+                .arg(arg!(--"output-dir" <dir>).required(true))
+                .arg(arg!(--force).required(false)),
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Compare the CSV outputs of two runs, or two directories of them")
+                .arg(arg!(<old>))
+                .arg(arg!(<new>)),
+        )
+        .subcommand(
+            Command::new("gen-fixture")
+                .about("Write a synthetic statement with known transactions, for tests")
+                .hide(true)
+                .arg(arg!(--output <path>).required(true))
+                .arg(arg!(--name <name>).default_value("JOHN DOE"))
+                .arg(arg!(--password <password>).default_value(""))
+                .arg(
+                    arg!(--rows <n>)
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("40"),
+                )
+                .arg(arg!(--expected <path>).required(false)),
+        )
+        .subcommand(
+            Command::new("selftest")
+                .about("Parse the fixture statements and compare them with their expected CSVs")
+                .arg(arg!(--fixtures <dir>).default_value("tests/fixtures"))
+                .arg(arg!(--name <name>).default_value("JOHN DOE"))
+                .arg(arg!(--password <password>).default_value("JOHN0101"))
+                .arg(capacity_arg()),
+        )
+        .subcommand(
+            Command::new("password")
+                .about("Manage statement passwords in the OS keyring")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("set")
+                        .about("Store a password for --password keyring:<entry>")
+                        .arg(arg!(<entry>)),
+                ),
+        )
+        .subcommand(
+            Command::new("categorize")
+                .about("Manage category rules")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("learn")
+                        .about("Generate category rules from a labeled CSV")
+                        .arg(arg!(--labeled <path_to_csv>).required(true))
+                        .arg(arg!(--output <path>).required(true))
+                        .arg(arg!(--"description-column" <name>).default_value("Description"))
+                        .arg(arg!(--"category-column" <name>).default_value("Category"))
+                        .arg(
+                            arg!(--"min-support" <rows>)
+                                .value_parser(clap::value_parser!(usize))
+                                .default_value("2"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("check")
+                        .about("Validate a rules file and report unreachable or conflicting rules")
+                        .arg(arg!(--categories <path>).required(true)),
+                )
+                .subcommand(
+                    Command::new("test")
+                        .about("Show which rule categorizes a transaction description")
+                        .arg(arg!(<description>))
+                        .arg(arg!(--categories <path>).required(true))
+                        .arg(fuzzy_arg())
+                        .arg(
+                            arg!(--amount <amount>)
+                                .required(false)
+                                .value_parser(clap::value_parser!(f32)),
+                        ),
+                ),
+        )
+        .get_matches();
+
+    match matches.subcommand() {
+        Some(("review", sub_matches)) => return review(sub_matches),
+        Some(("validate", sub_matches)) => return validate(sub_matches),
+        Some(("query", sub_matches)) => return query(sub_matches),
+        Some(("mcp", sub_matches)) => return mcp(sub_matches),
+        Some(("report", sub_matches)) => return report(sub_matches),
+        Some(("serve-grpc", sub_matches)) => {
+            return grpc::serve(
+                sub_matches.get_one::<String>("listen").unwrap(),
+                load_categories(sub_matches)?,
+                channel_capacity(sub_matches),
+            );
+        }
+        Some(("categorize", sub_matches)) => return categorize(sub_matches),
+        Some(("decrypt", sub_matches)) => return decrypt(sub_matches),
+        Some(("gen-fixture", sub_matches)) => return gen_fixture(sub_matches),
+        Some(("selftest", sub_matches)) => {
+            // fixtures are unencrypted, or encrypted with --password.
+            let passwords = vec![
+                String::new(),
+                sub_matches.get_one::<String>("password").unwrap().clone(),
+            ];
+            let passed = selftest::run(
+                sub_matches.get_one::<String>("fixtures").unwrap(),
+                sub_matches.get_one::<String>("name").unwrap(),
+                &passwords,
+                channel_capacity(sub_matches),
+            )?;
+            if !passed {
+                exit(error::EXIT_VALIDATION);
+            }
+            return Ok(());
+        }
+        Some(("diff", sub_matches)) => {
+            let differ = diff::run(
+                sub_matches.get_one::<String>("old").unwrap(),
+                sub_matches.get_one::<String>("new").unwrap(),
+            )?;
+            // like diff(1)
+            exit(if differ { 1 } else { 0 });
+        }
+        Some(("password", sub_matches)) => {
+            if let Some(("set", sub_matches)) = sub_matches.subcommand() {
+                return password::set(sub_matches.get_one::<String>("entry").unwrap());
+            }
+        }
+        _ => {}
+    }
+
+    if matches.get_flag("dry-run") {
+        return dry_run(&matches);
+    }
+
+    let format = matches.get_one::<String>("format").unwrap().clone();
+    let show_summary = matches.get_flag("summary");
+    let summary_by = matches.get_one::<String>("summary-by").unwrap().clone();
+    let locale = *matches.get_one::<locale::Locale>("locale").unwrap();
+    let interactive = matches.get_flag("interactive");
+    // the category questions are asked while statements are still parsed,
+    // so a password prompt would fight them for the terminal.
+    if interactive && matches.get_one::<String>("wrong-password").unwrap() == "prompt" {
+        return Err(anyhow::anyhow!(
+            "--interactive can't be combined with --wrong-password prompt"
+        ));
+    }
+    let mut heatmap = matches.get_flag("heatmap").then(heatmap::Heatmap::default);
+    let forex_report = matches.get_flag("forex-report");
+    let forecast = matches.get_flag("forecast");
+    let budgets = matches
+        .get_one::<String>("budgets")
+        .map(|path| budgets::load(path))
+        .transpose()?;
+    let milestones: Vec<milestones::Milestone> = matches
+        .get_many::<milestones::Milestone>("milestone")
+        .map(|milestones| milestones.cloned().collect())
+        .unwrap_or_default();
+    let anniversary = matches
+        .get_one::<(u32, u32)>("anniversary")
+        .copied()
+        .unwrap_or((1, 1));
+    let convert_to = matches
+        .get_one::<String>("convert-to")
+        .map(|currency| currency.to_uppercase());
+    if matches.contains_id("rates") && !forex_report && convert_to.is_none() {
+        return Err(anyhow::anyhow!(
+            "--rates needs --forex-report or --convert-to"
+        ));
+    }
+    let rates = matches
+        .get_one::<String>("rates")
+        .map(|path| fx::Rates::load(path))
+        .transpose()?;
+
+    let categories_path = matches.get_one::<String>("categories").cloned();
+    let categories = load_categories(&matches)?;
+
+    let tag_rules = match matches.get_one::<String>("tags") {
+        Some(path) => Some(Categories::load(path)?),
+        None => None,
     };
+    let tag_filter: Vec<String> = matches
+        .get_many::<String>("tag")
+        .map(|tags| tags.cloned().collect())
+        .unwrap_or_default();
 
-    let n: String = match name {
-        Some(s) => s.clone(),
-        None => "".to_string(),
+    let options = output::Options {
+        add_headers: matches.get_flag("addheaders"),
+        categories: categories.is_some(),
+        tags: tag_rules.is_some(),
+        converted: convert_to.is_some(),
+        owner: matches.contains_id("profiles"),
+        excel_safe: matches.get_flag("excel-safe"),
+        columns: matches
+            .get_many::<String>("columns")
+            .map(|columns| columns.cloned().collect()),
+        template: matches.get_one::<String>("template").cloned(),
+        accounts: matches.get_one::<String>("accounts").cloned(),
+        utc: matches.get_flag("utc"),
+        sign_convention: output::SignConvention::new(
+            matches.get_one::<String>("sign-convention").unwrap(),
+        ),
     };
+    if options.excel_safe && format != "csv" {
+        return Err(anyhow::anyhow!("--excel-safe only applies to --format csv"));
+    }
+    if options.columns.is_some() && format != "csv" {
+        return Err(anyhow::anyhow!("--columns only applies to --format csv"));
+    }
+    if options.sign_convention != output::SignConvention::DebitNegative && format != "csv" {
+        return Err(anyhow::anyhow!(
+            "--sign-convention only applies to --format csv"
+        ));
+    }
+    if options.accounts.is_some() && !["gnucash", "tally-xml"].contains(&format.as_str()) {
+        return Err(anyhow::anyhow!(
+            "--accounts only applies to --format gnucash and tally-xml"
+        ));
+    }
 
-    for file in pdf_files {
-        parse(file, n.clone(), pass.clone(), &tx).context("Failed to parse statement")?;
+    let output_path = matches.get_one::<String>("output").cloned();
+    let sink = matches.get_one::<String>("sink").cloned();
+    let sink_config = sinks::Config {
+        url: matches.get_one::<String>("url").cloned(),
+        token: matches.get_one::<String>("token").cloned(),
+        secret: matches.get_one::<String>("secret").cloned(),
+        batch_size: *matches.get_one::<usize>("batch-size").unwrap(),
+        account: matches.get_one::<String>("account").unwrap().clone(),
+        credentials: matches.get_one::<String>("credentials").cloned(),
+        spreadsheet: matches.get_one::<String>("spreadsheet").cloned(),
+        worksheet: matches.get_one::<String>("worksheet").unwrap().clone(),
+        brokers: matches.get_one::<String>("brokers").cloned(),
+        topic: matches.get_one::<String>("topic").unwrap().clone(),
+        schema_registry: matches.get_one::<String>("schema-registry").cloned(),
+        database: matches.get_one::<String>("database").cloned(),
+        group: matches.get_one::<String>("group").cloned(),
+        utc: matches.get_flag("utc"),
+    };
+
+    // with --profiles, every holder's name.
+    let names = match matches.get_one::<String>("profiles") {
+        Some(path) => profiles::load(path)?
+            .into_iter()
+            .map(|profile| profile.name)
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => matches
+            .get_one::<String>("name")
+            .cloned()
+            .unwrap_or_default(),
+    };
+    let redactor = matches
+        .get_flag("redact")
+        .then(|| redact::Redactor::new(&names, matches.get_flag("redact-merchants")));
+    let row_redactor = redactor.clone();
+    let provenance_path = matches.get_one::<String>("provenance").cloned();
+    let refund_window = matches
+        .get_flag("match-refunds")
+        .then(|| *matches.get_one::<i64>("refund-window").unwrap());
+    let link_emi = matches.get_flag("link-emi");
+    let metrics_path = matches.get_one::<String>("metrics-out").cloned();
+    // filled in by the writer, written once the statements are counted.
+    let metrics = metrics_path
+        .is_some()
+        .then(|| Arc::new(Mutex::new(metrics::Metrics::new(categories.clone()))));
+    let row_metrics = metrics.clone();
+    let notify = matches
+        .get_one::<String>("notify")
+        .map(|spec| notify::Target::parse(spec))
+        .transpose()?;
+    let report = notify
+        .is_some()
+        .then(|| Arc::new(Mutex::new(notify::Report::new(categories.clone()))));
+    let row_report = report.clone();
+    let mut dedup = matches
+        .contains_id("profiles")
+        .then(profiles::Dedup::default);
+
+    let (tx, rx) = mpsc::sync_channel::<Transaction>(channel_capacity(&matches));
+
+    let split_by = matches.get_one::<String>("split-by").cloned();
+    let output_dir = matches.get_one::<String>("output-dir").cloned();
+    if let Some(dir) = &output_dir {
+        if !s3::is_s3(dir) {
+            fs::create_dir_all(dir).context(format!("failed to create {}", dir))?;
+        }
     }
 
+    // The writer hands back the destinations uncommitted, so that the output
+    // only replaces the targets once every statement parsed.
+    let writer_thread = thread::spawn(move || -> Result<Vec<destination::Destination>, Error> {
+        let rows = rx
+            .into_iter()
+            // holders' copies of a shared statement.
+            .filter(|transaction| dedup.as_mut().is_none_or(|dedup| dedup.first(transaction)))
+            .map(|mut transaction| {
+                if let (Some(currency), Some(rates)) = (&convert_to, &rates) {
+                    transaction.converted_amount = rates
+                        .rate(currency, transaction.date.date())
+                        .map(|rate| transaction.amount / rate);
+                }
+                if let Some(tag_rules) = &tag_rules {
+                    transaction.tags = tag_rules
+                        .all(&transaction)
+                        .into_iter()
+                        .map(str::to_owned)
+                        .collect();
+                }
+                transaction
+            })
+            // keep rows carrying any of the --tag tags
+            .filter(|transaction| {
+                tag_filter.is_empty() || transaction.tags.iter().any(|t| tag_filter.contains(t))
+            });
+        // refunds and EMI installments can be statements away from their
+        // purchase, so matching them waits for every row.
+        let rows: Box<dyn Iterator<Item = Transaction>> = if refund_window.is_some() || link_emi {
+            let mut rows: Vec<Transaction> = rows.collect();
+            // a reversal to EMI isn't a refund.
+            if link_emi {
+                matching::emi(&mut rows);
+            }
+            if let Some(days) = refund_window {
+                matching::refunds(&mut rows, days);
+            }
+            Box::new(rows.into_iter())
+        } else {
+            Box::new(rows)
+        };
+        let rows: Box<dyn Iterator<Item = Transaction>> = match row_metrics {
+            Some(metrics) => {
+                Box::new(rows.inspect(move |transaction| metrics.lock().unwrap().add(transaction)))
+            }
+            None => rows,
+        };
+        let rows: Box<dyn Iterator<Item = Transaction>> = match row_report {
+            Some(report) => {
+                Box::new(rows.inspect(move |transaction| report.lock().unwrap().add(transaction)))
+            }
+            None => rows,
+        };
+
+        if show_summary {
+            let rows = rows.inspect(|transaction| {
+                if let Some(heatmap) = &mut heatmap {
+                    heatmap.add(transaction);
+                }
+            });
+            summary::run(
+                rows,
+                categories,
+                categories_path.as_deref(),
+                interactive,
+                &summary_by,
+                locale,
+            )?;
+            if let Some(heatmap) = &heatmap {
+                println!();
+                heatmap.print(locale);
+            }
+            return Ok(Vec::new());
+        }
+        if forex_report {
+            forex::run(rows, rates.as_ref(), locale);
+            return Ok(Vec::new());
+        }
+        if forecast {
+            forecast::run(rows, categories.as_ref(), locale)?;
+            return Ok(Vec::new());
+        }
+        if let Some(budgets) = &budgets {
+            budgets::run(rows, categories.as_ref(), budgets, locale)?;
+            return Ok(Vec::new());
+        }
+        if !milestones.is_empty() {
+            milestones::run(rows, &milestones, anniversary, locale);
+            return Ok(Vec::new());
+        }
+
+        // One output per --split-by key ("2025-01", ...), or a single one
+        // under the empty key, opened when its first row comes in.
+        let open =
+            |key: &str| -> Result<(destination::Destination, Box<dyn output::Output>), Error> {
+                let destination = match &output_dir {
+                    Some(dir) => {
+                        let name = format!("{}.{}", key, output::extension(&format));
+                        destination::Destination::new(Some(&format!(
+                            "{}/{}",
+                            dir.trim_end_matches('/'),
+                            name
+                        )))
+                    }
+                    None => destination::Destination::new(output_path.as_deref()),
+                };
+                let out = match &sink {
+                    Some(sink) => sinks::new(sink, &sink_config)?,
+                    None => new_output(&format, destination.writer()?, options.clone())?,
+                };
+                Ok((destination, out))
+            };
+
+        let mut outputs: BTreeMap<String, (destination::Destination, Box<dyn output::Output>)> =
+            BTreeMap::new();
+        // --provenance, one JSON line per row written.
+        let mut provenance = None;
+        let written = (|| -> Result<(), Error> {
+            if split_by.is_none() {
+                outputs.insert(String::new(), open("")?);
+            }
+            if let Some(path) = &provenance_path {
+                let destination = destination::Destination::new(Some(path));
+                let writer = destination.writer()?;
+                provenance = Some((destination, writer));
+            }
+            for mut transaction in rows {
+                if let Some(categories) = &categories {
+                    transaction.category = categories.categorize(&transaction).map(str::to_owned);
+                }
+                // after categorizing, which needs the merchant.
+                if let Some(redactor) = &row_redactor {
+                    redactor.transaction(&mut transaction);
+                }
+                let key = match split_by.as_deref() {
+                    Some("month") => transaction.date.format("%Y-%m").to_string(),
+                    _ => String::new(),
+                };
+                if !outputs.contains_key(&key) {
+                    let opened = open(&key)?;
+                    outputs.insert(key.clone(), opened);
+                }
+                outputs.get_mut(&key).unwrap().1.write(&transaction)?;
+                if let Some((_, writer)) = &mut provenance {
+                    writeln!(writer, "{}", provenance_json(&transaction))?;
+                }
+            }
+            for (_, out) in outputs.values_mut() {
+                out.finish()?;
+            }
+            if let Some((_, writer)) = &mut provenance {
+                writer.flush()?;
+            }
+            Ok(())
+        })();
+
+        let destinations = outputs
+            .into_values()
+            .map(|(destination, _)| destination)
+            .chain(provenance.map(|(destination, _)| destination));
+        match written {
+            Ok(()) => Ok(destinations.collect()),
+            Err(e) => {
+                destinations.for_each(destination::Destination::discard);
+                Err(e)
+            }
+        }
+    });
+
+    let started = Instant::now();
+    let parsed = parse_statements(&matches, &tx);
+
     drop(tx);
 
-    match writer_thread.join() {
-        Ok(Ok(_)) => (),
-        Ok(Err(e)) => return Err(e.into()),
+    let destinations = match writer_thread.join() {
+        Ok(Ok(destinations)) => destinations,
+        Ok(Err(e)) => return Err(e),
         Err(e) => return Err(anyhow::anyhow!("Thread panicked: {:?}", e)),
+    };
+
+    match parsed {
+        Ok((statements, failures)) => {
+            for destination in destinations {
+                destination.commit()?;
+            }
+            let missing = missing_months(&matches, &statements);
+            for month in &missing {
+                eprintln!("warning: no statement for {}", month);
+            }
+            let points_expiry = *matches.get_one::<u32>("points-expiry").unwrap();
+            if show_summary {
+                summary::print_points(&statements, points_expiry, locale);
+            }
+            if let (Some(target), Some(report)) = (&notify, &report) {
+                let alerts: Vec<String> =
+                    summary::expiring_points(&statements, points_expiry, locale)
+                        .into_iter()
+                        .chain(
+                            missing
+                                .iter()
+                                .map(|month| format!("no statement for {}", month)),
+                        )
+                        .chain(
+                            failures
+                                .iter()
+                                .map(|(file, e)| format!("{} failed: {:#}", file, e)),
+                        )
+                        .collect();
+                let (title, message) = report.lock().unwrap().message(&alerts, locale);
+                target.send(
+                    matches
+                        .get_one::<String>("notify-token")
+                        .map(String::as_str),
+                    &title,
+                    &message,
+                )?;
+            }
+            if let (Some(path), Some(metrics)) = (&metrics_path, &metrics) {
+                metrics
+                    .lock()
+                    .unwrap()
+                    .write(path, statements.len(), failures.len())?;
+            }
+            if matches.get_flag("stats") {
+                print_stats(&statements, started.elapsed());
+            }
+            if let Some(dir) = matches.get_one::<String>("meta-dir") {
+                fs::create_dir_all(dir).context(format!("failed to create {}", dir))?;
+                for statement in &statements {
+                    match &redactor {
+                        Some(redactor) => {
+                            let mut statement = statement.clone();
+                            redactor.statement(&mut statement);
+                            statement.write_meta(dir)?;
+                        }
+                        None => statement.write_meta(dir)?,
+                    }
+                }
+            }
+            report_failures(&failures);
+            report_empty(&statements);
+        }
+        Err(e) => {
+            destinations
+                .into_iter()
+                .for_each(destination::Destination::discard);
+            // an unattended run that failed is worth hearing about too.
+            if let Some(target) = &notify {
+                let token = matches.get_one::<String>("notify-token");
+                if let Err(e) = target.send(
+                    token.map(String::as_str),
+                    "Card statements failed",
+                    &format!("{:#}", e),
+                ) {
+                    eprintln!("warning: {:#}", e);
+                }
+            }
+            return Err(e);
+        }
     }
 
     Ok(())
@@ -0,0 +1,178 @@
+// Post-processing passes over all the rows of a run, linking rows that
+// belong together across statements.
+use crate::categories::merchant_key;
+use crate::{Transaction, TransactionType};
+use chrono::Duration;
+
+// Words refund descriptions start with, before the merchant.
+const REFUND_PREFIXES: [&str; 5] = ["REFUND", "REVERSAL", "REV", "RFND", "CREDIT"];
+
+// How much a refund may exceed its purchase by, from rounding.
+const TOLERANCE: f32 = 1.0;
+
+// Descriptions of the credit reversing a purchase converted to EMI.
+const EMI_CONVERSIONS: [&str; 5] = [
+    "EMI CONV",
+    "CONVERTED TO EMI",
+    "CONV TO EMI",
+    "SMARTEMI",
+    "EMI BOOKING",
+];
+
+// Words of EMI installment descriptions around the merchant, as in
+// "EMI PRINCIPAL 3/12 AMAZON PAY INDIA".
+const EMI_WORDS: [&str; 6] = [
+    "EMI",
+    "PRINCIPAL",
+    "INTEREST",
+    "AMOUNT",
+    "INSTALLMENT",
+    "OF",
+];
+
+// Conversions are booked within this many days of the purchase.
+const EMI_CONVERSION_DAYS: i64 = 45;
+
+// The merchant of a refund's description, past the words marking it one:
+// "REFUND-SWIGGY LIMITED" -> "SWIGGY".
+fn refund_merchant(description: &str) -> String {
+    let rest: Vec<&str> = description
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '*')
+        .skip_while(|word| {
+            let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+            word.is_empty() || REFUND_PREFIXES.contains(&word.to_uppercase().as_str())
+        })
+        .collect();
+    merchant_key(&rest.join(" "))
+}
+
+// Link refunds and reversals to the purchase they undo: the same merchant,
+// up to `days` before, for as much or more (partial refunds). The closest
+// amount wins, then the latest purchase. Both rows get the purchase's ID as
+// their `match_id`.
+pub fn refunds(rows: &mut [Transaction], days: i64) {
+    let window = Duration::days(days);
+    let mut refunds: Vec<usize> = (0..rows.len())
+        .filter(|&i| {
+            rows[i].transaction_type() == TransactionType::Refund && rows[i].emi_group.is_none()
+        })
+        .collect();
+    // earlier refunds pick first.
+    refunds.sort_by_key(|&i| rows[i].date);
+
+    for refund in refunds {
+        let merchant = refund_merchant(&rows[refund].tx);
+        if merchant.is_empty() {
+            continue;
+        }
+        let (date, amount) = (rows[refund].date, rows[refund].amount);
+        let purchase = (0..rows.len())
+            .filter(|&i| {
+                let row = &rows[i];
+                row.amount < 0.0
+                    && row.match_id.is_none()
+                    && row.emi_group.is_none()
+                    && row.date <= date
+                    && row.date >= date - window
+                    && -row.amount + TOLERANCE >= amount
+                    && merchant_key(&row.tx) == merchant
+            })
+            .min_by(|&a, &b| {
+                let off = |i: usize| (-rows[i].amount - amount).abs();
+                off(a)
+                    .total_cmp(&off(b))
+                    .then(rows[b].date.cmp(&rows[a].date))
+            });
+        if let Some(purchase) = purchase {
+            let id = rows[purchase].id();
+            rows[purchase].match_id = Some(id.clone());
+            rows[refund].match_id = Some(id);
+        }
+    }
+}
+
+fn emi_conversion(row: &Transaction) -> bool {
+    let tx = row.tx.to_uppercase();
+    row.amount > 0.0 && EMI_CONVERSIONS.iter().any(|c| tx.contains(c))
+}
+
+// A monthly EMI debit: principal, interest, or both.
+pub fn emi_installment(row: &Transaction) -> bool {
+    row.amount < 0.0
+        && !emi_conversion(row)
+        && row
+            .tx
+            .to_uppercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| word == "EMI" || word == "SMARTEMI")
+}
+
+// The merchant of an installment's description, past the EMI words and the
+// "3/12" installment number.
+fn installment_merchant(description: &str) -> String {
+    let rest: Vec<&str> = description
+        .split_whitespace()
+        .skip_while(|word| {
+            let word = word.to_uppercase();
+            EMI_WORDS.contains(&word.as_str())
+                || word
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == '/' || c == '-')
+        })
+        .collect();
+    merchant_key(&rest.join(" "))
+}
+
+// Link purchases converted to EMI with the credit reversing them and their
+// monthly installments: the conversion credit matches the purchase it
+// reverses by amount, up to 45 days after it, and installments match by
+// merchant. All of them get the purchase's ID as their `emi_group`, so
+// totals can skip the purchase and its reversal and count the installments.
+pub fn emi(rows: &mut [Transaction]) {
+    let window = Duration::days(EMI_CONVERSION_DAYS);
+    let mut conversions: Vec<usize> = (0..rows.len())
+        .filter(|&i| emi_conversion(&rows[i]))
+        .collect();
+    conversions.sort_by_key(|&i| rows[i].date);
+
+    // the purchases converted.
+    let mut groups = Vec::new();
+    for conversion in conversions {
+        let (date, amount) = (rows[conversion].date, rows[conversion].amount);
+        let purchase = (0..rows.len())
+            .filter(|&i| {
+                let row = &rows[i];
+                row.amount < 0.0
+                    && row.emi_group.is_none()
+                    && !emi_installment(row)
+                    && row.date <= date
+                    && row.date >= date - window
+                    && (-row.amount - amount).abs() <= TOLERANCE
+            })
+            // the latest purchase before the conversion.
+            .max_by_key(|&i| rows[i].date);
+        if let Some(purchase) = purchase {
+            let id = rows[purchase].id();
+            rows[purchase].emi_group = Some(id.clone());
+            rows[conversion].emi_group = Some(id);
+            groups.push(purchase);
+        }
+    }
+
+    for i in 0..rows.len() {
+        if !emi_installment(&rows[i]) {
+            continue;
+        }
+        let merchant = installment_merchant(&rows[i].tx);
+        let group = groups
+            .iter()
+            .filter(|&&purchase| {
+                rows[purchase].date <= rows[i].date && merchant_key(&rows[purchase].tx) == merchant
+            })
+            // the latest purchase of the merchant.
+            .max_by_key(|&&purchase| rows[purchase].date);
+        if let Some(&purchase) = group {
+            rows[i].emi_group = rows[purchase].emi_group.clone();
+        }
+    }
+}
@@ -0,0 +1,201 @@
+// `mcp`: a Model Context Protocol server over stdio, so LLM assistants can
+// ask about the statements locally instead of being handed CSVs. It speaks
+// JSON-RPC, a message per line, and has two tools:
+//
+//   parse_statement(path, password): the transactions of a statement
+//   spend_summary(from, to): spend per category over the run's statements
+//
+// Logs go to stderr, stdout is the protocol's.
+use crate::categories::Categories;
+use crate::summary;
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+// the protocol versions known, the latest last.
+const VERSIONS: [&str; 3] = ["2024-11-05", "2025-03-26", "2025-06-18"];
+
+// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+fn tools() -> Value {
+    json!([
+        {
+            "name": "parse_statement",
+            "description": "Parse a credit card statement PDF (or a zip or .eml holding statements) and return its transactions. Amounts are negative for debits.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Path of the statement" },
+                    "password": { "type": "string", "description": "Password of the PDF, if it isn't one the server was started with" },
+                },
+                "required": ["path"],
+            },
+        },
+        {
+            "name": "spend_summary",
+            "description": "Spend (purchases net of refunds) per category over the statements the server was started with, between two dates.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "from": { "type": "string", "format": "date", "description": "First day, YYYY-MM-DD; the earliest transaction by default" },
+                    "to": { "type": "string", "format": "date", "description": "Last day, YYYY-MM-DD; the latest transaction by default" },
+                },
+            },
+        },
+    ])
+}
+
+fn date(arguments: &Value, key: &str) -> Result<Option<NaiveDate>, Error> {
+    arguments[key]
+        .as_str()
+        .map(|date| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .context(format!("{} should be YYYY-MM-DD, got {}", key, date))
+        })
+        .transpose()
+}
+
+fn spend_summary(rows: &[Transaction], arguments: &Value) -> Result<Value, Error> {
+    let from = date(arguments, "from")?;
+    let to = date(arguments, "to")?;
+    let rows: Vec<&Transaction> = rows
+        .iter()
+        .filter(|transaction| {
+            let date = transaction.date.date();
+            from.is_none_or(|from| date >= from) && to.is_none_or(|to| date <= to)
+        })
+        .collect();
+
+    let categories = summary::spend_by_category(rows.iter().copied());
+
+    let dates = rows.iter().map(|transaction| transaction.date.date());
+    Ok(json!({
+        "from": from.or_else(|| dates.clone().min()).map(|d| d.to_string()),
+        "to": to.or_else(|| dates.max()).map(|d| d.to_string()),
+        "total": categories.iter().fold(0.0, |total, (_, spend, _)| total + spend),
+        "categories": categories
+            .iter()
+            .map(|(category, spend, n)| json!({
+                "category": category,
+                "spend": spend,
+                "transactions": n,
+            }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+// Serve until stdin closes. `rows` are the statements of the input args,
+// and `parse_statement` parses a file with an extra password to try.
+pub fn serve(
+    mut rows: Vec<Transaction>,
+    categories: Option<&Categories>,
+    mut parse_statement: impl FnMut(&str, Option<&str>) -> Result<Vec<Transaction>, Error>,
+) -> Result<(), Error> {
+    let categorize = |transaction: &mut Transaction| {
+        if let Some(categories) = categories {
+            transaction.category = categories.categorize(transaction).map(str::to_owned);
+        }
+    };
+    rows.iter_mut().for_each(categorize);
+    eprintln!("mcp: serving {} transactions on stdio", rows.len());
+
+    let stdout = io::stdout();
+    for line in io::stdin().lock().lines() {
+        let line = line.context("failed to read stdin")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let message: Value = match serde_json::from_str(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                let response = error(Value::Null, PARSE_ERROR, &e.to_string());
+                writeln!(stdout.lock(), "{}", response)?;
+                continue;
+            }
+        };
+        // notifications, eg. notifications/initialized, aren't answered.
+        let Some(id) = message.get("id").cloned() else {
+            continue;
+        };
+        let params = &message["params"];
+
+        let response = match message["method"].as_str().unwrap_or_default() {
+            "initialize" => {
+                let requested = params["protocolVersion"].as_str().unwrap_or_default();
+                let version = VERSIONS
+                    .into_iter()
+                    .find(|v| *v == requested)
+                    .unwrap_or(VERSIONS[VERSIONS.len() - 1]);
+                result(
+                    id,
+                    json!({
+                        "protocolVersion": version,
+                        "capabilities": { "tools": {} },
+                        "serverInfo": {
+                            "name": env!("CARGO_PKG_NAME"),
+                            "version": env!("CARGO_PKG_VERSION"),
+                        },
+                    }),
+                )
+            }
+            "ping" => result(id, json!({})),
+            "tools/list" => result(id, json!({ "tools": tools() })),
+            "tools/call" => match params["name"].as_str() {
+                Some(name @ ("parse_statement" | "spend_summary")) => {
+                    let arguments = &params["arguments"];
+                    let called = if name == "parse_statement" {
+                        match arguments["path"].as_str() {
+                            Some(path) => parse_statement(path, arguments["password"].as_str())
+                                .map(|mut parsed| {
+                                    parsed.iter_mut().for_each(categorize);
+                                    parsed
+                                        .iter()
+                                        .map(|transaction| transaction.to_json(false))
+                                        .collect()
+                                }),
+                            None => Err(anyhow!("parse_statement needs a path")),
+                        }
+                    } else {
+                        spend_summary(&rows, arguments)
+                    };
+                    // a tool that fails tells the model why, rather than the
+                    // client.
+                    let (text, failed) = match called {
+                        Ok(value) => (value.to_string(), false),
+                        Err(e) => (format!("{:#}", e), true),
+                    };
+                    result(
+                        id,
+                        json!({
+                            "content": [{ "type": "text", "text": text }],
+                            "isError": failed,
+                        }),
+                    )
+                }
+                name => error(
+                    id,
+                    INVALID_PARAMS,
+                    &format!("unknown tool {}", name.unwrap_or_default()),
+                ),
+            },
+            method => error(id, METHOD_NOT_FOUND, &format!("unknown method {}", method)),
+        };
+        let mut out = stdout.lock();
+        writeln!(out, "{}", response)?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+fn result(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
@@ -0,0 +1,93 @@
+// --metrics-out: the run's totals as Prometheus/OpenMetrics gauges, for the
+// node_exporter textfile collector or any scraper reading a file, to chart
+// spend in Grafana. Spend is per category, and per owner with --profiles.
+use crate::categories::Categories;
+use crate::destination::Destination;
+use crate::forecast;
+use crate::summary::UNCATEGORIZED;
+use crate::Transaction;
+use anyhow::Error;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    categories: Option<Categories>,
+    // (owner, category) -> (spend, rows)
+    spend: BTreeMap<(Option<String>, String), (f64, usize)>,
+}
+
+impl Metrics {
+    pub fn new(categories: Option<Categories>) -> Self {
+        Metrics {
+            categories,
+            ..Default::default()
+        }
+    }
+
+    pub fn add(&mut self, transaction: &Transaction) {
+        let category = self
+            .categories
+            .as_ref()
+            .and_then(|categories| categories.categorize(transaction))
+            .unwrap_or(UNCATEGORIZED)
+            .to_owned();
+        let (spend, rows) = self
+            .spend
+            .entry((transaction.owner.clone(), category))
+            .or_default();
+        *spend += forecast::spend(transaction) as f64;
+        *rows += 1;
+    }
+
+    // Write the gauges, with the statements parsed and the files that
+    // failed.
+    pub fn write(&self, path: &str, statements: usize, errors: usize) -> Result<(), Error> {
+        let destination = Destination::new(Some(path));
+        let mut out = destination.writer()?;
+
+        writeln!(out, "# HELP hdfc_cc_spend Purchases net of refunds.")?;
+        writeln!(out, "# TYPE hdfc_cc_spend gauge")?;
+        for ((owner, category), (spend, _)) in &self.spend {
+            writeln!(out, "hdfc_cc_spend{} {:.2}", labels(owner, category), spend)?;
+        }
+        writeln!(out, "# HELP hdfc_cc_transactions Transactions parsed.")?;
+        writeln!(out, "# TYPE hdfc_cc_transactions gauge")?;
+        for ((owner, category), (_, rows)) in &self.spend {
+            writeln!(
+                out,
+                "hdfc_cc_transactions{} {}",
+                labels(owner, category),
+                rows
+            )?;
+        }
+        writeln!(out, "# HELP hdfc_cc_statements Statements parsed.")?;
+        writeln!(out, "# TYPE hdfc_cc_statements gauge")?;
+        writeln!(out, "hdfc_cc_statements {}", statements)?;
+        writeln!(
+            out,
+            "# HELP hdfc_cc_parse_errors Statements that failed to parse."
+        )?;
+        writeln!(out, "# TYPE hdfc_cc_parse_errors gauge")?;
+        writeln!(out, "hdfc_cc_parse_errors {}", errors)?;
+        writeln!(out, "# EOF")?;
+        out.flush()?;
+        drop(out);
+        destination.commit()
+    }
+}
+
+fn labels(owner: &Option<String>, category: &str) -> String {
+    let mut labels = vec![format!("category=\"{}\"", escape(category))];
+    if let Some(owner) = owner {
+        labels.push(format!("owner=\"{}\"", escape(owner)));
+    }
+    format!("{{{}}}", labels.join(","))
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
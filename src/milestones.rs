@@ -0,0 +1,148 @@
+// `--milestone`: progress toward the annual spend milestones of cards like
+// Infinia and Regalia (fee waiver, bonus vouchers), per card year from
+// `--anniversary`, with the date each was crossed or, for the current year,
+// when it will be at the pace so far.
+use crate::forecast;
+use crate::locale::Locale;
+use crate::Transaction;
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct Milestone {
+    pub amount: f32,
+    pub label: String,
+}
+
+// Parses a --milestone value for clap: "400000" or "400000:Fee waiver".
+pub fn parse(s: &str) -> Result<Milestone, String> {
+    let (amount, label) = s.split_once(':').unwrap_or((s, ""));
+    let amount: f32 = amount
+        .trim()
+        .replace(',', "")
+        .parse()
+        .map_err(|_| format!("bad milestone amount {:?}", amount))?;
+    if amount <= 0.0 {
+        return Err(format!("bad milestone amount {:?}", s));
+    }
+    Ok(Milestone {
+        amount,
+        label: label.trim().to_owned(),
+    })
+}
+
+// Parses an --anniversary value for clap, "MM-DD": the month and day card
+// years start on.
+pub fn parse_anniversary(s: &str) -> Result<(u32, u32), String> {
+    // a leap year, so 02-29 passes.
+    let date = NaiveDate::parse_from_str(&format!("2000-{}", s), "%Y-%m-%d")
+        .map_err(|_| format!("bad anniversary {:?}, want MM-DD", s))?;
+    Ok((date.month(), date.day()))
+}
+
+// The card year starting on (month, day) in `year`, Feb 29 falling back to
+// Feb 28 in other years.
+fn anniversary(year: i32, (month, day): (u32, u32)) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .or_else(|| NaiveDate::from_ymd_opt(year, month, day - 1))
+        .unwrap()
+}
+
+// The start of the card year `date` falls in.
+fn year_start(date: NaiveDate, day: (u32, u32)) -> NaiveDate {
+    let start = anniversary(date.year(), day);
+    if start <= date {
+        start
+    } else {
+        anniversary(date.year() - 1, day)
+    }
+}
+
+// Print the spend toward every milestone, per card year the rows cover.
+pub fn run(
+    rows: impl Iterator<Item = Transaction>,
+    milestones: &[Milestone],
+    day: (u32, u32),
+    locale: Locale,
+) {
+    // card year start -> spend per day
+    let mut years: BTreeMap<NaiveDate, BTreeMap<NaiveDate, f32>> = BTreeMap::new();
+    let mut last = None;
+    // spend as forecast counts it: purchases net of refunds, EMI conversions
+    // by their installments.
+    for transaction in rows {
+        let date = transaction.date.date();
+        *years
+            .entry(year_start(date, day))
+            .or_default()
+            .entry(date)
+            .or_default() += forecast::spend(&transaction);
+        last = last.max(Some(date));
+    }
+    let Some(last) = last else {
+        println!("No transactions.");
+        return;
+    };
+
+    let mut milestones = milestones.to_vec();
+    milestones.sort_by(|a, b| a.amount.total_cmp(&b.amount));
+    for (start, days) in &years {
+        let end = anniversary(start.year() + 1, day);
+        let total: f32 = days.values().sum();
+        println!(
+            "Card year {} to {}: {} spent",
+            start,
+            end - Duration::days(1),
+            locale.amount(total)
+        );
+
+        for milestone in &milestones {
+            let name = if milestone.label.is_empty() {
+                locale.amount(milestone.amount)
+            } else {
+                format!("{} ({})", milestone.label, locale.amount(milestone.amount))
+            };
+            let progress = (total / milestone.amount * 100.0).min(100.0);
+            let status = match crossed(days, milestone.amount) {
+                Some(date) => format!("crossed on {}", date),
+                None if last < end && last >= *start => {
+                    let remaining = milestone.amount - total;
+                    // the pace from the start of the year to the last row.
+                    let elapsed = (last - *start).num_days() + 1;
+                    match projected(total, elapsed, remaining) {
+                        Some(days) if last + Duration::days(days) < end => format!(
+                            "{} to go, projected {}",
+                            locale.amount(remaining),
+                            last + Duration::days(days)
+                        ),
+                        _ => format!(
+                            "{} to go, not reached by {} at this pace",
+                            locale.amount(remaining),
+                            end - Duration::days(1)
+                        ),
+                    }
+                }
+                None => format!("missed by {}", locale.amount(milestone.amount - total)),
+            };
+            println!("  {:<32} {:>5.1}%  {}", name, progress, status);
+        }
+    }
+}
+
+// The day the running spend first reached `amount`.
+fn crossed(days: &BTreeMap<NaiveDate, f32>, amount: f32) -> Option<NaiveDate> {
+    let mut total = 0.0;
+    days.iter().find_map(|(date, spend)| {
+        total += spend;
+        (total >= amount).then_some(*date)
+    })
+}
+
+// Days until `remaining` is spent at the pace of `total` over `elapsed` days.
+fn projected(total: f32, elapsed: i64, remaining: f32) -> Option<i64> {
+    if total <= 0.0 {
+        return None;
+    }
+    let per_day = total / elapsed as f32;
+    Some((remaining / per_day).ceil() as i64)
+}
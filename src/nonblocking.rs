@@ -0,0 +1,57 @@
+// `parse_async` and `parse_file_async`: parsing from async code, for services
+// on tokio. The parser itself blocks, so it runs on tokio's blocking pool,
+// its rows coming through a tokio channel. Needs a build with the tokio
+// feature.
+use crate::error::ParseError;
+use crate::statement::Statement;
+use crate::{ParseOptions, StatementIter, Transaction};
+use std::panic;
+use tokio::sync::mpsc::Sender;
+
+// Parse the pdf, sending its transactions to `sender`, and return the
+// statement details, as `parse` does. Drain the channel while it parses (in
+// a task of its own, or `tokio::join!`ed with this): the parser waits for
+// room when it's full.
+pub async fn parse_async(
+    path: String,
+    data: Vec<u8>,
+    name: String,
+    passwords: Vec<String>,
+    options: ParseOptions<'static>,
+    sender: Sender<Transaction>,
+) -> Result<Statement, ParseError> {
+    let parsed = tokio::task::spawn_blocking(move || {
+        let mut rows = StatementIter::new(path, data, name, &passwords, options)?;
+        for row in &mut rows {
+            sender.blocking_send(row?).map_err(|_| ParseError::Closed)?;
+        }
+        Ok(rows.into_statement())
+    })
+    .await;
+    match parsed {
+        Ok(parsed) => parsed,
+        // a panic in the parser is the caller's, as with `parse`.
+        Err(e) => match e.try_into_panic() {
+            Ok(payload) => panic::resume_unwind(payload),
+            // the runtime's shutting down.
+            Err(_) => Err(ParseError::Closed),
+        },
+    }
+}
+
+// `parse_async` of the statement at `path`, read without blocking.
+pub async fn parse_file_async(
+    path: String,
+    name: String,
+    passwords: Vec<String>,
+    options: ParseOptions<'static>,
+    sender: Sender<Transaction>,
+) -> Result<Statement, ParseError> {
+    let data = tokio::fs::read(&path)
+        .await
+        .map_err(|source| ParseError::Io {
+            file: path.clone(),
+            source,
+        })?;
+    parse_async(path, data, name, passwords, options, sender).await
+}
@@ -0,0 +1,156 @@
+// --notify: once the run is done, the latest month's spend and the run's
+// warnings (points expiring, months without a statement, files that failed)
+// sent to a phone through a Telegram bot or an ntfy topic, for unattended
+// runs from cron.
+use crate::categories::Categories;
+use crate::forecast;
+use crate::locale::Locale;
+use crate::summary::UNCATEGORIZED;
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use chrono::{Datelike, NaiveDate};
+use reqwest::blocking::Client;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+const NTFY: &str = "https://ntfy.sh";
+
+pub enum Target {
+    // chat ID or @channel, messaged by the bot of --notify-token.
+    Telegram { chat: String },
+    // topic URL, on ntfy.sh unless the topic is one.
+    Ntfy { url: String },
+}
+
+impl Target {
+    // telegram:<chat> or ntfy:<topic>
+    pub fn parse(spec: &str) -> Result<Self, Error> {
+        match spec.split_once(':') {
+            Some(("telegram", chat)) if !chat.is_empty() => Ok(Target::Telegram {
+                chat: chat.to_owned(),
+            }),
+            Some(("ntfy", topic)) if !topic.is_empty() => Ok(Target::Ntfy {
+                url: if topic.contains("://") {
+                    topic.to_owned()
+                } else {
+                    format!("{}/{}", NTFY, topic)
+                },
+            }),
+            _ => Err(anyhow!(
+                "--notify takes telegram:<chat> or ntfy:<topic>, got {}",
+                spec
+            )),
+        }
+    }
+
+    pub fn send(&self, token: Option<&str>, title: &str, message: &str) -> Result<(), Error> {
+        let client = Client::new();
+        let request = match self {
+            Target::Telegram { chat } => {
+                let token =
+                    token.ok_or_else(|| anyhow!("--notify-token is required for telegram"))?;
+                client
+                    .post(format!("https://api.telegram.org/bot{}/sendMessage", token))
+                    .json(&json!({
+                        "chat_id": chat,
+                        "text": format!("{}\n\n{}", title, message),
+                    }))
+            }
+            Target::Ntfy { url } => {
+                let request = client
+                    .post(url)
+                    .header("Title", title)
+                    .body(message.to_owned());
+                match token {
+                    Some(token) => request.bearer_auth(token),
+                    None => request,
+                }
+            }
+        };
+        request
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .context("failed to send the notification")?;
+        Ok(())
+    }
+}
+
+// Spend per month and category, over the rows as they're written.
+#[derive(Debug, Default)]
+pub struct Report {
+    categories: Option<Categories>,
+    // month -> category -> (spend, rows)
+    months: BTreeMap<NaiveDate, BTreeMap<String, (f32, usize)>>,
+}
+
+impl Report {
+    pub fn new(categories: Option<Categories>) -> Self {
+        Report {
+            categories,
+            ..Default::default()
+        }
+    }
+
+    pub fn add(&mut self, transaction: &Transaction) {
+        let spend = forecast::spend(transaction);
+        if spend == 0.0 {
+            return;
+        }
+        let category = self
+            .categories
+            .as_ref()
+            .and_then(|categories| categories.categorize(transaction))
+            .unwrap_or(UNCATEGORIZED)
+            .to_owned();
+        let month = transaction.date.date().with_day(1).unwrap();
+        let (total, rows) = self
+            .months
+            .entry(month)
+            .or_default()
+            .entry(category)
+            .or_default();
+        *total += spend;
+        *rows += 1;
+    }
+
+    // The title and body of the notification: the latest month's spend per
+    // category, biggest first, against the month before, then `alerts`.
+    pub fn message(&self, alerts: &[String], locale: Locale) -> (String, String) {
+        let mut lines = Vec::new();
+        let mut months = self.months.iter().rev();
+        let title = match months.next() {
+            Some((month, categories)) => {
+                let (total, rows) = categories
+                    .values()
+                    .fold((0.0, 0), |(total, rows), (spend, n)| {
+                        (total + spend, rows + n)
+                    });
+                let mut line = format!("Spent {} over {} transactions", locale.amount(total), rows);
+                if let Some((previous, categories)) = months.next() {
+                    let before: f32 = categories.values().map(|(spend, _)| spend).sum();
+                    line.push_str(&format!(
+                        ", {} in {}",
+                        locale.amount(before),
+                        previous.format("%Y-%m")
+                    ));
+                }
+                lines.push(line);
+                let mut categories: Vec<_> = categories.iter().collect();
+                categories.sort_by(|a, b| b.1 .0.total_cmp(&a.1 .0));
+                for (category, (spend, _)) in categories {
+                    lines.push(format!("{}: {}", category, locale.amount(*spend)));
+                }
+                format!("Card spend for {}", month.format("%Y-%m"))
+            }
+            None => {
+                lines.push("No spend in the statements parsed".to_owned());
+                "Card spend".to_owned()
+            }
+        };
+        if !alerts.is_empty() {
+            lines.push(String::new());
+            lines.extend(alerts.iter().map(|alert| format!("⚠ {}", alert)));
+        }
+        (title, lines.join("\n"))
+    }
+}
@@ -0,0 +1,378 @@
+use crate::categories::merchant_key;
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use std::io::Write;
+
+mod accounts;
+mod actual;
+mod arrow;
+pub mod avro;
+mod camt053;
+mod gnucash;
+mod mt940;
+mod ofx;
+mod qif;
+mod quickbooks;
+mod tally;
+mod ynab;
+mod zohobooks;
+
+// Output formats selectable with --format.
+pub const FORMATS: [&str; 14] = [
+    "csv",
+    "qif",
+    "ofx",
+    "ynab",
+    "actual",
+    "template",
+    "arrow",
+    "avro",
+    "camt053",
+    "mt940",
+    "tally-xml",
+    "zohobooks",
+    "quickbooks",
+    "gnucash",
+];
+
+// File extension for outputs written in `format`.
+pub fn extension(format: &str) -> &'static str {
+    match format {
+        "qif" => "qif",
+        "ofx" => "ofx",
+        "template" => "txt",
+        "arrow" => "arrow",
+        "avro" => "avro",
+        "camt053" => "xml",
+        "mt940" => "sta",
+        "tally-xml" => "xml",
+        _ => "csv",
+    }
+}
+
+// What the writers should emit besides the parsed fields.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub add_headers: bool,
+    // --categories / --tags given, so the rows carry them.
+    pub categories: bool,
+    pub tags: bool,
+    // --profiles given, so the rows carry their owner.
+    pub owner: bool,
+    // --convert-to given, so the rows carry a converted amount.
+    pub converted: bool,
+    // --excel-safe: BOM, unambiguous dates and no formula injection.
+    pub excel_safe: bool,
+    // --columns, in output order. None for the default layout.
+    pub columns: Option<Vec<String>>,
+    // --template file for --format template.
+    pub template: Option<String>,
+    // --accounts file for the double-entry formats.
+    pub accounts: Option<String>,
+    // --utc: timestamps in UTC instead of IST.
+    pub utc: bool,
+    // --sign-convention for the CSV amounts.
+    pub sign_convention: SignConvention,
+}
+
+// --sign-convention: how the CSV tells debits from credits, as importers
+// disagree on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignConvention {
+    // debits negative, credits positive, as parsed.
+    #[default]
+    DebitNegative,
+    // debits positive, credits negative, as the statements print them.
+    DebitPositive,
+    // the amount split into Debit and Credit columns, both positive.
+    SeparateColumns,
+}
+
+pub const SIGN_CONVENTIONS: [&str; 3] = ["debit-negative", "debit-positive", "separate-columns"];
+
+impl SignConvention {
+    pub fn new(name: &str) -> Self {
+        match name {
+            "debit-positive" => SignConvention::DebitPositive,
+            "separate-columns" => SignConvention::SeparateColumns,
+            _ => SignConvention::DebitNegative,
+        }
+    }
+}
+
+// Writes transactions in one output format.
+pub trait Output {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error>;
+
+    // Called once after the last transaction.
+    fn finish(&mut self) -> Result<(), Error>;
+}
+
+pub fn new<W: Write + 'static>(
+    format: &str,
+    out: W,
+    options: Options,
+) -> Result<Box<dyn Output>, Error> {
+    match format {
+        "csv" => Ok(Box::new(Csv::new(out, options)?)),
+        "qif" => Ok(Box::new(qif::Qif::new(out)?)),
+        "ofx" => Ok(Box::new(ofx::Ofx::new(out))),
+        "ynab" => Ok(Box::new(ynab::Ynab::new(out)?)),
+        "actual" => Ok(Box::new(actual::Actual::new(out)?)),
+        "arrow" => Ok(Box::new(arrow::Arrow::new(out, options.utc)?)),
+        "avro" => Ok(Box::new(avro::Avro::new(out)?)),
+        "camt053" => Ok(Box::new(camt053::Camt053::new(out))),
+        "mt940" => Ok(Box::new(mt940::Mt940::new(out))),
+        "tally-xml" => Ok(Box::new(tally::Tally::new(
+            out,
+            accounts::Accounts::load(options.accounts.as_deref())?,
+        )?)),
+        "gnucash" => Ok(Box::new(gnucash::GnuCash::new(
+            out,
+            accounts::Accounts::load(options.accounts.as_deref())?,
+        )?)),
+        "zohobooks" => Ok(Box::new(zohobooks::ZohoBooks::new(out)?)),
+        "quickbooks" => Ok(Box::new(quickbooks::QuickBooks::new(out)?)),
+        _ => Err(anyhow!("unknown output format {}", format)),
+    }
+}
+
+// Columns selectable with --columns.
+pub const COLUMNS: [&str; 21] = [
+    "date",
+    "description",
+    "merchant",
+    "city",
+    "state",
+    "points",
+    "cashback",
+    "rewards_unit",
+    "amount",
+    "converted_amount",
+    "type",
+    "balance",
+    "statement_date",
+    "statement_file",
+    "card",
+    "owner",
+    "category",
+    "tags",
+    "match_id",
+    "emi_group",
+    "id",
+];
+
+// The default output, one CSV row per transaction.
+pub struct Csv<W: Write> {
+    wtr: csv::Writer<W>,
+    columns: Vec<String>,
+    options: Options,
+}
+
+impl<W: Write> Csv<W> {
+    pub fn new(mut out: W, options: Options) -> Result<Self, Error> {
+        if options.excel_safe {
+            // without the BOM Excel reads the file as ANSI and mangles ₹ and friends.
+            out.write_all("\u{feff}".as_bytes())
+                .context("Failed to write BOM")?;
+        }
+        let mut wtr = csv::Writer::from_writer(out);
+
+        let columns = match &options.columns {
+            Some(columns) => columns.clone(),
+            None => {
+                let mut columns = vec!["date", "description", "points", "amount"];
+                if options.converted {
+                    columns.push("converted_amount");
+                }
+                if options.owner {
+                    columns.push("owner");
+                }
+                if options.categories {
+                    columns.push("category");
+                }
+                if options.tags {
+                    columns.push("tags");
+                }
+                columns.into_iter().map(str::to_owned).collect()
+            }
+        };
+        // the amount column becomes the two.
+        let columns = match options.sign_convention {
+            SignConvention::SeparateColumns => columns
+                .into_iter()
+                .flat_map(|column| match column.as_str() {
+                    "amount" => vec!["debit".to_owned(), "credit".to_owned()],
+                    _ => vec![column],
+                })
+                .collect(),
+            _ => columns,
+        };
+
+        if options.add_headers {
+            //  writes the header rows to CSV if user passes --addheaders param
+            let headers: Vec<&str> = columns.iter().map(|c| header(c)).collect();
+            wtr.write_record(&headers)
+                .context("Failed to write headers")?;
+        }
+
+        Ok(Csv {
+            wtr,
+            columns,
+            options,
+        })
+    }
+
+    // The writer, once finished.
+    pub fn into_inner(self) -> Result<W, Error> {
+        self.wtr
+            .into_inner()
+            .map_err(|e| anyhow!("failed to flush CSV: {}", e.error()))
+    }
+
+    fn field(&self, transaction: &Transaction, column: &str) -> String {
+        let excel_safe = self.options.excel_safe;
+        let text = |s: String| if excel_safe { excel_escape(&s) } else { s };
+        match column {
+            "date" if excel_safe => transaction.date.format("%Y-%m-%d %H:%M:%S").to_string(),
+            "date" => transaction.timestamp(self.options.utc),
+            "description" => text(transaction.tx.clone()),
+            "merchant" => text(merchant_key(&transaction.tx)),
+            "city" => transaction.city.unwrap_or_default().to_owned(),
+            "state" => transaction.state.unwrap_or_default().to_owned(),
+            "points" => transaction.points.to_string(),
+            "cashback" => format!("{:.2}", transaction.cashback),
+            "rewards_unit" => transaction.rewards_unit.to_owned(),
+            "amount" => {
+                let amount = match self.options.sign_convention {
+                    SignConvention::DebitPositive => -transaction.amount,
+                    _ => transaction.amount,
+                };
+                if excel_safe {
+                    format!("{:.2}", amount)
+                } else {
+                    amount.to_string()
+                }
+            }
+            "converted_amount" => transaction
+                .converted_amount
+                .map(|amount| match self.options.sign_convention {
+                    SignConvention::DebitPositive => -amount,
+                    _ => amount,
+                })
+                .map(|amount| format!("{:.2}", amount))
+                .unwrap_or_default(),
+            "debit" if transaction.amount < 0.0 => format!("{:.2}", -transaction.amount),
+            "credit" if transaction.amount >= 0.0 => format!("{:.2}", transaction.amount),
+            "balance" => transaction
+                .balance
+                .map(|b| format!("{:.2}", b))
+                .unwrap_or_default(),
+            "statement_date" => transaction
+                .statement_date
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            "statement_file" => text(transaction.statement_file.clone()),
+            "card" => transaction.card.clone().unwrap_or_default(),
+            "owner" => text(transaction.owner.clone().unwrap_or_default()),
+            "category" => text(transaction.category.clone().unwrap_or_default()),
+            "tags" => text(transaction.tags.join(";")),
+            "type" => transaction.transaction_type().name().to_owned(),
+            "match_id" => transaction.match_id.clone().unwrap_or_default(),
+            "emi_group" => transaction.emi_group.clone().unwrap_or_default(),
+            "id" => transaction.id(),
+            _ => String::new(),
+        }
+    }
+}
+
+pub fn header(column: &str) -> &'static str {
+    match column {
+        "date" => "Date",
+        "description" => "Description",
+        "merchant" => "Merchant",
+        "city" => "City",
+        "state" => "State",
+        "points" => "Points",
+        "cashback" => "Cashback",
+        "rewards_unit" => "Rewards Unit",
+        "amount" => "Amount",
+        "converted_amount" => "Converted Amount",
+        "debit" => "Debit",
+        "credit" => "Credit",
+        "balance" => "Balance",
+        "statement_date" => "Statement Date",
+        "statement_file" => "Statement File",
+        "card" => "Card",
+        "owner" => "Owner",
+        "category" => "Category",
+        "tags" => "Tags",
+        "type" => "Type",
+        "match_id" => "Match ID",
+        "emi_group" => "EMI Group",
+        "id" => "ID",
+        _ => "",
+    }
+}
+
+impl<W: Write> Output for Csv<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let record: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| self.field(transaction, column))
+            .collect();
+        self.wtr
+            .write_record(&record)
+            .context("Failed to write row")
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.wtr.flush().context("Error flushing to stdout")
+    }
+}
+
+// Text cells starting with one of these are evaluated as formulas by
+// spreadsheets. Prefixing a quote makes them plain text again.
+fn excel_escape(field: &str) -> String {
+    match field.chars().next() {
+        Some('=' | '+' | '-' | '@' | '\t' | '\r') if field.parse::<f64>().is_err() => {
+            format!("'{}", field)
+        }
+        _ => field.to_owned(),
+    }
+}
+
+// Text for an XML element or attribute: markup characters escaped, and
+// the control characters XML can't carry (PDF text can) dropped.
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{fffe}' | '\u{ffff}' => {}
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Opening and closing balances of a statement of `transactions`: from the
+// running balance of the first row where the rows carry one, otherwise
+// starting at zero, so the closing balance is the net of the rows.
+fn statement_balances(transactions: &[Transaction]) -> (f32, f32) {
+    let opening = transactions
+        .first()
+        .and_then(|first| first.balance.map(|balance| balance - first.amount))
+        .unwrap_or(0.0);
+    let closing = transactions
+        .iter()
+        .fold(opening, |balance, transaction| balance + transaction.amount);
+    (opening, closing)
+}
@@ -0,0 +1,62 @@
+use crate::{Transaction, TransactionType};
+use anyhow::{Context, Error};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+// --accounts: which account each row is booked against, for the
+// double-entry formats (gnucash, tally-xml, where accounts are ledgers).
+// A TOML file, every key optional:
+//
+//   card = "Liabilities:HDFC Credit Card"  # the card's own account
+//   payment = "Assets:HDFC Bank"           # the other side of card payments
+//   default = "Imbalance-INR"              # rows with no account of their own
+//   company = "Acme Traders"               # tally-xml: the company to import into
+//
+//   [accounts]                             # category (from --categories) = account
+//   Food = "Expenses:Dining"
+//   Travel = "Expenses:Travel"
+//
+// The formats have their own defaults for card and default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Accounts {
+    pub company: Option<String>,
+    card: Option<String>,
+    payment: Option<String>,
+    default: Option<String>,
+    #[serde(default)]
+    accounts: HashMap<String, String>,
+}
+
+impl Accounts {
+    // The mapping in `path`, or an empty one, leaving every row to the
+    // defaults.
+    pub fn load(path: Option<&str>) -> Result<Self, Error> {
+        let Some(path) = path else {
+            return Ok(Accounts::default());
+        };
+        let data = fs::read_to_string(path).context(format!("failed to read {}", path))?;
+        toml::from_str(&data).context(format!("failed to parse {}", path))
+    }
+
+    pub fn card<'a>(&'a self, default: &'a str) -> &'a str {
+        self.card.as_deref().unwrap_or(default)
+    }
+
+    // The account on the other side of the card's for `transaction`.
+    pub fn account<'a>(&'a self, transaction: &Transaction, default: &'a str) -> &'a str {
+        if transaction.transaction_type() == TransactionType::Payment {
+            if let Some(payment) = &self.payment {
+                return payment;
+            }
+        }
+        transaction
+            .category
+            .as_ref()
+            .and_then(|category| self.accounts.get(category))
+            .or(self.default.as_ref())
+            .map(String::as_str)
+            .unwrap_or(default)
+    }
+}
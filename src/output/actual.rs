@@ -0,0 +1,45 @@
+use super::Output;
+use crate::Transaction;
+use anyhow::{Context, Error};
+use std::io::Write;
+
+// CSV for Actual Budget's importer: ISO dates, outflows as negative
+// amounts, and an imported_id Actual uses to skip rows it already has.
+pub struct Actual<W: Write> {
+    wtr: csv::Writer<W>,
+}
+
+impl<W: Write> Actual<W> {
+    pub fn new(out: W) -> Result<Self, Error> {
+        let mut wtr = csv::Writer::from_writer(out);
+        wtr.write_record([
+            "date",
+            "payee",
+            "notes",
+            "category",
+            "amount",
+            "imported_id",
+        ])
+        .context("Failed to write headers")?;
+        Ok(Actual { wtr })
+    }
+}
+
+impl<W: Write> Output for Actual<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        self.wtr
+            .write_record([
+                transaction.date.format("%Y-%m-%d").to_string(),
+                transaction.tx.clone(),
+                transaction.tags.join(" "),
+                transaction.category.clone().unwrap_or_default(),
+                format!("{:.2}", transaction.amount),
+                transaction.id(),
+            ])
+            .context("Failed to write row")
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.wtr.flush().context("failed to flush Actual output")
+    }
+}
@@ -0,0 +1,228 @@
+use super::Output;
+use crate::Transaction;
+use anyhow::Error;
+use std::io::Write;
+
+// An Arrow IPC stream with a typed schema, for Polars, pandas and DuckDB to
+// read without parsing: the date a timestamp in IST (or UTC with --utc),
+// amounts floats, the statement date a date, tags a list of strings. Rows are
+// written in batches of BATCH_ROWS. Needs a build with the arrow feature.
+pub struct Arrow<W: Write> {
+    writer: ipc::Writer<W>,
+    batch: Vec<Transaction>,
+}
+
+// rows per record batch.
+const BATCH_ROWS: usize = 8192;
+
+impl<W: Write> Arrow<W> {
+    pub fn new(out: W, utc: bool) -> Result<Self, Error> {
+        Ok(Arrow {
+            writer: ipc::Writer::new(out, utc)?,
+            batch: Vec::new(),
+        })
+    }
+}
+
+impl<W: Write> Output for Arrow<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        self.batch.push(transaction.clone());
+        if self.batch.len() >= BATCH_ROWS {
+            self.writer.write(&self.batch)?;
+            self.batch.clear();
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if !self.batch.is_empty() {
+            self.writer.write(&self.batch)?;
+            self.batch.clear();
+        }
+        self.writer.finish()
+    }
+}
+
+#[cfg(feature = "arrow")]
+mod ipc {
+    use crate::categories::merchant_key;
+    use crate::Transaction;
+    use anyhow::{Context, Error};
+    use arrow_array::builder::{ListBuilder, StringBuilder};
+    use arrow_array::{
+        ArrayRef, Date32Array, Float64Array, Int32Array, RecordBatch, StringArray,
+        TimestampMillisecondArray,
+    };
+    use arrow_ipc::writer::StreamWriter;
+    use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+    use chrono::{NaiveDate, TimeZone};
+    use chrono_tz::Asia::Kolkata;
+    use std::io::Write;
+    use std::sync::Arc;
+
+    pub struct Writer<W: Write> {
+        stream: StreamWriter<W>,
+        schema: SchemaRef,
+    }
+
+    fn schema(utc: bool) -> Schema {
+        let text = |name: &str, nullable: bool| Field::new(name, DataType::Utf8, nullable);
+        let timezone = if utc { "UTC" } else { "Asia/Kolkata" };
+        Schema::new(vec![
+            Field::new(
+                "date",
+                DataType::Timestamp(TimeUnit::Millisecond, Some(timezone.into())),
+                false,
+            ),
+            text("description", false),
+            text("merchant", false),
+            text("city", true),
+            text("state", true),
+            Field::new("points", DataType::Int32, false),
+            Field::new("cashback", DataType::Float64, false),
+            text("rewards_unit", false),
+            Field::new("amount", DataType::Float64, false),
+            Field::new("converted_amount", DataType::Float64, true),
+            text("type", false),
+            Field::new("balance", DataType::Float64, true),
+            Field::new("statement_date", DataType::Date32, true),
+            text("statement_file", false),
+            text("card", true),
+            text("owner", true),
+            text("category", true),
+            Field::new(
+                "tags",
+                DataType::List(Arc::new(Field::new("item", DataType::Utf8, true))),
+                false,
+            ),
+            text("match_id", true),
+            text("emi_group", true),
+            text("id", false),
+        ])
+    }
+
+    impl<W: Write> Writer<W> {
+        pub fn new(out: W, utc: bool) -> Result<Self, Error> {
+            let schema = Arc::new(schema(utc));
+            let stream =
+                StreamWriter::try_new(out, &schema).context("failed to start arrow stream")?;
+            Ok(Writer { stream, schema })
+        }
+
+        pub fn write(&mut self, rows: &[Transaction]) -> Result<(), Error> {
+            let text = |value: fn(&Transaction) -> Option<&str>| -> ArrayRef {
+                Arc::new(rows.iter().map(value).collect::<StringArray>())
+            };
+            let float = |value: fn(&Transaction) -> Option<f32>| -> ArrayRef {
+                Arc::new(
+                    rows.iter()
+                        .map(|t| value(t).map(f64::from))
+                        .collect::<Float64Array>(),
+                )
+            };
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+            let timezone = match self.schema.field(0).data_type() {
+                DataType::Timestamp(_, timezone) => timezone.clone(),
+                _ => None,
+            };
+
+            let mut tags = ListBuilder::new(StringBuilder::new());
+            for transaction in rows {
+                for tag in &transaction.tags {
+                    tags.values().append_value(tag);
+                }
+                tags.append(true);
+            }
+
+            let columns: Vec<ArrayRef> = vec![
+                Arc::new(
+                    TimestampMillisecondArray::from(
+                        rows.iter()
+                            .map(|t| {
+                                Kolkata
+                                    .from_local_datetime(&t.date)
+                                    .unwrap()
+                                    .timestamp_millis()
+                            })
+                            .collect::<Vec<_>>(),
+                    )
+                    .with_timezone_opt(timezone),
+                ),
+                text(|t| Some(t.tx.as_str())),
+                Arc::new(
+                    rows.iter()
+                        .map(|t| Some(merchant_key(&t.tx)))
+                        .collect::<StringArray>(),
+                ),
+                text(|t| t.city),
+                text(|t| t.state),
+                Arc::new(Int32Array::from(
+                    rows.iter().map(|t| t.points).collect::<Vec<_>>(),
+                )),
+                float(|t| Some(t.cashback)),
+                text(|t| Some(t.rewards_unit)),
+                float(|t| Some(t.amount)),
+                float(|t| t.converted_amount),
+                text(|t| Some(t.transaction_type().name())),
+                float(|t| t.balance),
+                Arc::new(
+                    rows.iter()
+                        .map(|t| {
+                            t.statement_date
+                                .map(|date| (date - epoch).num_days() as i32)
+                        })
+                        .collect::<Date32Array>(),
+                ),
+                text(|t| Some(t.statement_file.as_str())),
+                text(|t| t.card.as_deref()),
+                text(|t| t.owner.as_deref()),
+                text(|t| t.category.as_deref()),
+                Arc::new(tags.finish()),
+                text(|t| t.match_id.as_deref()),
+                text(|t| t.emi_group.as_deref()),
+                Arc::new(rows.iter().map(|t| Some(t.id())).collect::<StringArray>()),
+            ];
+            let batch = RecordBatch::try_new(self.schema.clone(), columns)
+                .context("failed to build arrow batch")?;
+            self.stream
+                .write(&batch)
+                .context("failed to write arrow batch")
+        }
+
+        pub fn finish(&mut self) -> Result<(), Error> {
+            self.stream
+                .finish()
+                .context("failed to finish arrow stream")?;
+            self.stream
+                .get_mut()
+                .flush()
+                .context("Error flushing output")
+        }
+    }
+}
+
+#[cfg(not(feature = "arrow"))]
+mod ipc {
+    use crate::Transaction;
+    use anyhow::{anyhow, Error};
+    use std::io::Write;
+    use std::marker::PhantomData;
+
+    pub struct Writer<W: Write>(PhantomData<W>);
+
+    impl<W: Write> Writer<W> {
+        pub fn new(_out: W, _utc: bool) -> Result<Self, Error> {
+            Err(anyhow!(
+                "--format arrow needs a build with the arrow feature (cargo build --features arrow)"
+            ))
+        }
+
+        pub fn write(&mut self, _rows: &[Transaction]) -> Result<(), Error> {
+            unreachable!("Writer::new always fails without the arrow feature")
+        }
+
+        pub fn finish(&mut self) -> Result<(), Error> {
+            unreachable!("Writer::new always fails without the arrow feature")
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use super::Output;
+use crate::categories::merchant_key;
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Asia::Kolkata;
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::time::SystemTime;
+
+// The Avro schema of a transaction, shared by --format avro files and the
+// kafka sink's messages with --schema-registry. Fields are only ever added,
+// with defaults, so readers of older rows keep working.
+pub const SCHEMA: &str = r#"{
+  "type": "record",
+  "name": "Transaction",
+  "namespace": "hdfc_cc_parser.v1",
+  "fields": [
+    {"name": "id", "type": "string"},
+    {"name": "date", "type": {"type": "long", "logicalType": "timestamp-millis"}},
+    {"name": "description", "type": "string"},
+    {"name": "merchant", "type": "string"},
+    {"name": "city", "type": ["null", "string"], "default": null},
+    {"name": "state", "type": ["null", "string"], "default": null},
+    {"name": "points", "type": "int"},
+    {"name": "cashback", "type": "double"},
+    {"name": "rewards_unit", "type": "string"},
+    {"name": "amount", "type": "double"},
+    {"name": "converted_amount", "type": ["null", "double"], "default": null},
+    {"name": "type", "type": "string"},
+    {"name": "balance", "type": ["null", "double"], "default": null},
+    {"name": "statement_date", "type": ["null", {"type": "int", "logicalType": "date"}], "default": null},
+    {"name": "statement_file", "type": "string"},
+    {"name": "card", "type": ["null", "string"], "default": null},
+    {"name": "owner", "type": ["null", "string"], "default": null},
+    {"name": "category", "type": ["null", "string"], "default": null},
+    {"name": "tags", "type": {"type": "array", "items": "string"}},
+    {"name": "match_id", "type": ["null", "string"], "default": null},
+    {"name": "emi_group", "type": ["null", "string"], "default": null}
+  ]
+}"#;
+
+// rows per block of an object container file.
+const BLOCK_ROWS: usize = 1000;
+
+fn long(buf: &mut Vec<u8>, n: i64) {
+    // zig-zag, then a little-endian base-128 varint.
+    let mut n = ((n << 1) ^ (n >> 63)) as u64;
+    while n >= 0x80 {
+        buf.push((n as u8 & 0x7f) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn string(buf: &mut Vec<u8>, s: &str) {
+    long(buf, s.len() as i64);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn double(buf: &mut Vec<u8>, f: f32) {
+    buf.extend_from_slice(&f64::from(f).to_le_bytes());
+}
+
+// A ["null", T] union: the branch, then the value if there's one.
+fn optional<T>(buf: &mut Vec<u8>, value: Option<T>, write: impl FnOnce(&mut Vec<u8>, T)) {
+    match value {
+        Some(value) => {
+            long(buf, 1);
+            write(buf, value);
+        }
+        None => long(buf, 0),
+    }
+}
+
+// Append the Avro binary encoding of `transaction` under SCHEMA to `buf`.
+pub fn encode(buf: &mut Vec<u8>, transaction: &Transaction) {
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    string(buf, &transaction.id());
+    long(
+        buf,
+        Kolkata
+            .from_local_datetime(&transaction.date)
+            .unwrap()
+            .timestamp_millis(),
+    );
+    string(buf, &transaction.tx);
+    string(buf, &merchant_key(&transaction.tx));
+    optional(buf, transaction.city, string);
+    optional(buf, transaction.state, string);
+    long(buf, transaction.points.into());
+    double(buf, transaction.cashback);
+    string(buf, transaction.rewards_unit);
+    double(buf, transaction.amount);
+    optional(buf, transaction.converted_amount, double);
+    string(buf, transaction.transaction_type().name());
+    optional(buf, transaction.balance, double);
+    optional(buf, transaction.statement_date, |buf, date| {
+        long(buf, (date - epoch).num_days())
+    });
+    string(buf, &transaction.statement_file);
+    optional(buf, transaction.card.as_deref(), string);
+    optional(buf, transaction.owner.as_deref(), string);
+    optional(buf, transaction.category.as_deref(), string);
+    if !transaction.tags.is_empty() {
+        long(buf, transaction.tags.len() as i64);
+        transaction.tags.iter().for_each(|tag| string(buf, tag));
+    }
+    long(buf, 0);
+    optional(buf, transaction.match_id.as_deref(), string);
+    optional(buf, transaction.emi_group.as_deref(), string);
+}
+
+// Register SCHEMA under `subject` with a Confluent schema registry, returning
+// its ID. Registering a schema the subject already has returns the existing
+// ID, so this is safe on every run.
+pub fn register(registry: &str, subject: &str) -> Result<u32, Error> {
+    let registered: Value = Client::new()
+        .post(format!(
+            "{}/subjects/{}/versions",
+            registry.trim_end_matches('/'),
+            subject
+        ))
+        .header("Content-Type", "application/vnd.schemaregistry.v1+json")
+        .json(&json!({ "schema": SCHEMA }))
+        .send()
+        .and_then(|resp| resp.error_for_status())
+        .context(format!("failed to register the schema for {}", subject))?
+        .json()
+        .context("failed to read schema registry response")?;
+    registered["id"]
+        .as_u64()
+        .and_then(|id| u32::try_from(id).ok())
+        .ok_or_else(|| anyhow!("schema registry returned no ID: {}", registered))
+}
+
+// An Avro object container file: the schema in the header, then the rows in
+// blocks of BLOCK_ROWS, uncompressed. Readable by pandas (fastavro), Spark,
+// DuckDB and avro-tools.
+pub struct Avro<W: Write> {
+    out: W,
+    sync: [u8; 16],
+    block: Vec<u8>,
+    rows: usize,
+}
+
+impl<W: Write> Avro<W> {
+    pub fn new(mut out: W) -> Result<Self, Error> {
+        // the marker between blocks only needs to be unlikely in the data.
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let mut sync = [0; 16];
+        sync.copy_from_slice(&Sha256::digest(nanos.to_le_bytes())[..16]);
+
+        let mut header = b"Obj\x01".to_vec();
+        long(&mut header, 2);
+        string(&mut header, "avro.schema");
+        string(&mut header, SCHEMA);
+        string(&mut header, "avro.codec");
+        string(&mut header, "null");
+        long(&mut header, 0);
+        header.extend_from_slice(&sync);
+        out.write_all(&header).context("Error writing output")?;
+
+        Ok(Avro {
+            out,
+            sync,
+            block: Vec::new(),
+            rows: 0,
+        })
+    }
+
+    fn flush_block(&mut self) -> Result<(), Error> {
+        let mut head = Vec::new();
+        long(&mut head, self.rows as i64);
+        long(&mut head, self.block.len() as i64);
+        self.out
+            .write_all(&head)
+            .and_then(|_| self.out.write_all(&self.block))
+            .and_then(|_| self.out.write_all(&self.sync))
+            .context("Error writing output")?;
+        self.block.clear();
+        self.rows = 0;
+        Ok(())
+    }
+}
+
+impl<W: Write> Output for Avro<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        encode(&mut self.block, transaction);
+        self.rows += 1;
+        if self.rows >= BLOCK_ROWS {
+            self.flush_block()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if self.rows > 0 {
+            self.flush_block()?;
+        }
+        self.out.flush().context("Error flushing output")
+    }
+}
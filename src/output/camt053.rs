@@ -0,0 +1,131 @@
+use super::{statement_balances, xml_escape, Output};
+use crate::Transaction;
+use anyhow::{Context, Error};
+use chrono::{NaiveDate, NaiveDateTime, Utc};
+use chrono_tz::Asia::Kolkata;
+use std::io::Write;
+
+const ACCOUNT_ID: &str = "HDFC-CC";
+
+// ISO 20022 bank-to-customer statement (camt.053.001.02), one statement
+// spanning all transactions, so rows are buffered and the document is
+// written on finish. Each entry's reference is the transaction ID, for
+// importers that skip entries they already have.
+pub struct Camt053<W: Write> {
+    out: W,
+    transactions: Vec<Transaction>,
+}
+
+impl<W: Write> Camt053<W> {
+    pub fn new(out: W) -> Self {
+        Camt053 {
+            out,
+            transactions: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Output for Camt053<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        self.transactions.push(transaction.clone());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let now = Utc::now().with_timezone(&Kolkata).naive_local();
+        let start = self
+            .transactions
+            .iter()
+            .map(|t| t.date)
+            .min()
+            .unwrap_or(now);
+        let end = self
+            .transactions
+            .iter()
+            .map(|t| t.date)
+            .max()
+            .unwrap_or(now);
+        let (opening, closing) = statement_balances(&self.transactions);
+        // message and statement IDs are limited to 35 characters.
+        let id = format!("HDFCCC-{}", now.format("%Y%m%d%H%M%S"));
+
+        let mut doc = String::new();
+        doc += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+        doc += "<Document xmlns=\"urn:iso:std:iso:20022:tech:xsd:camt.053.001.02\">\n";
+        doc += "<BkToCstmrStmt>\n";
+        doc += &format!(
+            "<GrpHdr><MsgId>{}</MsgId><CreDtTm>{}</CreDtTm></GrpHdr>\n",
+            id,
+            date_time(&now)
+        );
+        doc += "<Stmt>\n";
+        doc += &format!("<Id>{}</Id>\n", id);
+        doc += &format!("<CreDtTm>{}</CreDtTm>\n", date_time(&now));
+        doc += &format!(
+            "<FrToDt><FrDtTm>{}</FrDtTm><ToDtTm>{}</ToDtTm></FrToDt>\n",
+            date_time(&start),
+            date_time(&end)
+        );
+        doc += &format!(
+            "<Acct><Id><Othr><Id>{}</Id></Othr></Id><Ccy>INR</Ccy></Acct>\n",
+            ACCOUNT_ID
+        );
+        doc += &balance("OPBD", opening, start.date());
+        doc += &balance("CLBD", closing, end.date());
+
+        for transaction in &self.transactions {
+            let id = transaction.id();
+            let date = transaction.date.format("%Y-%m-%d");
+            doc += "<Ntry>\n";
+            doc += &format!("<NtryRef>{}</NtryRef>\n", id);
+            doc += &amount(transaction.amount);
+            doc += "<Sts>BOOK</Sts>\n";
+            doc += &format!("<BookgDt><Dt>{}</Dt></BookgDt>\n", date);
+            doc += &format!("<ValDt><Dt>{}</Dt></ValDt>\n", date);
+            doc += &format!("<AcctSvcrRef>{}</AcctSvcrRef>\n", id);
+            doc += "<NtryDtls><TxDtls>\n";
+            doc += &format!("<Refs><AcctSvcrRef>{}</AcctSvcrRef></Refs>\n", id);
+            // unstructured remittance info is limited to 140 characters.
+            let info: String = transaction.tx.chars().take(140).collect();
+            doc += &format!("<RmtInf><Ustrd>{}</Ustrd></RmtInf>\n", xml_escape(&info));
+            doc += "</TxDtls></NtryDtls>\n";
+            doc += &format!(
+                "<AddtlNtryInf>{}</AddtlNtryInf>\n",
+                xml_escape(&transaction.tx)
+            );
+            doc += "</Ntry>\n";
+        }
+
+        doc += "</Stmt>\n";
+        doc += "</BkToCstmrStmt>\n";
+        doc += "</Document>\n";
+
+        self.out
+            .write_all(doc.as_bytes())
+            .context("Failed to write camt.053")?;
+        self.out.flush().context("failed to flush camt.053 output")
+    }
+}
+
+// An amount and its direction: camt amounts are never negative.
+fn amount(amount: f32) -> String {
+    let direction = if amount < 0.0 { "DBIT" } else { "CRDT" };
+    format!(
+        "<Amt Ccy=\"INR\">{:.2}</Amt><CdtDbtInd>{}</CdtDbtInd>\n",
+        amount.abs(),
+        direction
+    )
+}
+
+fn balance(code: &str, value: f32, date: NaiveDate) -> String {
+    format!(
+        "<Bal><Tp><CdOrPrtry><Cd>{}</Cd></CdOrPrtry></Tp>{}<Dt><Dt>{}</Dt></Dt></Bal>\n",
+        code,
+        amount(value).trim_end(),
+        date.format("%Y-%m-%d")
+    )
+}
+
+fn date_time(date: &NaiveDateTime) -> String {
+    format!("{}+05:30", date.format("%Y-%m-%dT%H:%M:%S"))
+}
@@ -0,0 +1,64 @@
+use super::accounts::Accounts;
+use super::Output;
+use crate::Transaction;
+use anyhow::{Context, Error};
+use std::io::Write;
+
+// accounts for rows --accounts doesn't place; Imbalance-INR is where
+// GnuCash itself puts unbalanced splits.
+const CARD: &str = "Liabilities:Credit Card:HDFC";
+const DEFAULT: &str = "Imbalance-INR";
+
+// CSV for GnuCash's transaction importer, a row per transaction with both
+// sides named: Account is the card's, Transfer Account the other side's from
+// --accounts, so every column maps to one of the importer's column types and
+// the transactions come in balanced. Charges are withdrawals from the card
+// account, payments and refunds deposits; the transaction ID goes in Number.
+pub struct GnuCash<W: Write> {
+    wtr: csv::Writer<W>,
+    accounts: Accounts,
+}
+
+impl<W: Write> GnuCash<W> {
+    pub fn new(out: W, accounts: Accounts) -> Result<Self, Error> {
+        let mut wtr = csv::Writer::from_writer(out);
+        wtr.write_record([
+            "Date",
+            "Number",
+            "Description",
+            "Notes",
+            "Account",
+            "Deposit",
+            "Withdrawal",
+            "Transfer Account",
+        ])
+        .context("Failed to write headers")?;
+        Ok(GnuCash { wtr, accounts })
+    }
+}
+
+impl<W: Write> Output for GnuCash<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let (deposit, withdrawal) = if transaction.amount < 0.0 {
+            (String::new(), format!("{:.2}", -transaction.amount))
+        } else {
+            (format!("{:.2}", transaction.amount), String::new())
+        };
+        self.wtr
+            .write_record([
+                transaction.date.format("%Y-%m-%d").to_string(),
+                transaction.id(),
+                transaction.tx.clone(),
+                transaction.tags.join(" "),
+                self.accounts.card(CARD).to_owned(),
+                deposit,
+                withdrawal,
+                self.accounts.account(transaction, DEFAULT).to_owned(),
+            ])
+            .context("Failed to write row")
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.wtr.flush().context("failed to flush GnuCash output")
+    }
+}
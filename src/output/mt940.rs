@@ -0,0 +1,111 @@
+use super::{statement_balances, Output};
+use crate::Transaction;
+use anyhow::{Context, Error};
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Asia::Kolkata;
+use std::io::Write;
+
+const ACCOUNT_ID: &str = "HDFC-CC";
+
+// SWIFT MT940 customer statement, one statement spanning all transactions,
+// so rows are buffered and the message is written on finish. Each :61: line
+// carries the transaction ID as the bank reference, and the description
+// follows in :86:.
+pub struct Mt940<W: Write> {
+    out: W,
+    transactions: Vec<Transaction>,
+}
+
+impl<W: Write> Mt940<W> {
+    pub fn new(out: W) -> Self {
+        Mt940 {
+            out,
+            transactions: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Output for Mt940<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        self.transactions.push(transaction.clone());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let today = Utc::now().with_timezone(&Kolkata).date_naive();
+        let dates = self.transactions.iter().map(|t| t.date.date());
+        let start = dates.clone().min().unwrap_or(today);
+        let end = dates.max().unwrap_or(today);
+        let (opening, closing) = statement_balances(&self.transactions);
+
+        // SWIFT lines end in CRLF.
+        let mut lines = vec![
+            format!(":20:HDFCCC{}", today.format("%y%m%d")),
+            format!(":25:{}", ACCOUNT_ID),
+            ":28C:1/1".to_owned(),
+            format!(":60F:{}", balance(opening, start)),
+        ];
+        for transaction in &self.transactions {
+            let mark = if transaction.amount < 0.0 { "D" } else { "C" };
+            // value date, entry date, mark, amount, a miscellaneous
+            // transaction type, no customer reference, then the bank's.
+            lines.push(format!(
+                ":61:{}{}{}{}NMSCNONREF//{}",
+                transaction.date.format("%y%m%d"),
+                transaction.date.format("%m%d"),
+                mark,
+                amount(transaction.amount),
+                transaction.id()
+            ));
+            // up to 6 lines of 65 characters, all ASCII by now.
+            let mut info = format!(":86:{}", swift_chars(&transaction.tx));
+            for _ in 0..6 {
+                let rest = info.split_off(info.len().min(65));
+                lines.push(info);
+                if rest.is_empty() {
+                    break;
+                }
+                // a line starting with '-' would end the message.
+                info = if rest.starts_with('-') {
+                    format!(" {}", rest)
+                } else {
+                    rest
+                };
+            }
+        }
+        lines.push(format!(":62F:{}", balance(closing, end)));
+        lines.push("-".to_owned());
+
+        let mut message = lines.join("\r\n");
+        message += "\r\n";
+        self.out
+            .write_all(message.as_bytes())
+            .context("Failed to write MT940")?;
+        self.out.flush().context("failed to flush MT940 output")
+    }
+}
+
+// Amounts have a decimal comma and no sign, the mark before them says which.
+fn amount(amount: f32) -> String {
+    format!("{:.2}", amount.abs()).replace('.', ",")
+}
+
+fn balance(value: f32, date: NaiveDate) -> String {
+    let mark = if value < 0.0 { "D" } else { "C" };
+    format!("{}{}INR{}", mark, date.format("%y%m%d"), amount(value))
+}
+
+// The SWIFT character set is letters, digits, space and /-?:().,'+ only:
+// anything else becomes a space, and so does ':', which could start a
+// continuation line of :86: and read as a tag.
+fn swift_chars(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "/-?().,'+ ".contains(c) {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,104 @@
+use super::{xml_escape, Output};
+use crate::Transaction;
+use anyhow::{Context, Error};
+use chrono::Utc;
+use chrono_tz::Asia::Kolkata;
+use std::io::Write;
+
+const ACCOUNT_ID: &str = "HDFC-CC";
+
+// OFX 2 credit card statement. The statement dates span all transactions,
+// so rows are buffered and the document is written on finish.
+pub struct Ofx<W: Write> {
+    out: W,
+    transactions: Vec<Transaction>,
+}
+
+impl<W: Write> Ofx<W> {
+    pub fn new(out: W) -> Self {
+        Ofx {
+            out,
+            transactions: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Output for Ofx<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        self.transactions.push(transaction.clone());
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        // dates are all labelled IST, whatever the machine's zone.
+        let now = Utc::now().with_timezone(&Kolkata).naive_local();
+        let start = self
+            .transactions
+            .iter()
+            .map(|t| t.date)
+            .min()
+            .unwrap_or(now);
+        let end = self
+            .transactions
+            .iter()
+            .map(|t| t.date)
+            .max()
+            .unwrap_or(now);
+
+        let mut doc = String::new();
+        doc += "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n";
+        doc += "<?OFX OFXHEADER=\"200\" VERSION=\"220\" SECURITY=\"NONE\" OLDFILEUID=\"NONE\" NEWFILEUID=\"NONE\"?>\n";
+        doc += "<OFX>\n";
+        doc += "<SIGNONMSGSRSV1><SONRS>\n";
+        doc += "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>\n";
+        doc += &format!("<DTSERVER>{}</DTSERVER>\n", ofx_date(&now));
+        doc += "<LANGUAGE>ENG</LANGUAGE>\n";
+        doc += "</SONRS></SIGNONMSGSRSV1>\n";
+        doc += "<CREDITCARDMSGSRSV1><CCSTMTTRNRS>\n";
+        doc += "<TRNUID>0</TRNUID>\n";
+        doc += "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>\n";
+        doc += "<CCSTMTRS>\n";
+        doc += "<CURDEF>INR</CURDEF>\n";
+        doc += &format!("<CCACCTFROM><ACCTID>{}</ACCTID></CCACCTFROM>\n", ACCOUNT_ID);
+        doc += "<BANKTRANLIST>\n";
+        doc += &format!("<DTSTART>{}</DTSTART>\n", ofx_date(&start));
+        doc += &format!("<DTEND>{}</DTEND>\n", ofx_date(&end));
+
+        for transaction in &self.transactions {
+            let kind = if transaction.amount < 0.0 {
+                "DEBIT"
+            } else {
+                "CREDIT"
+            };
+            doc += "<STMTTRN>\n";
+            doc += &format!("<TRNTYPE>{}</TRNTYPE>\n", kind);
+            doc += &format!("<DTPOSTED>{}</DTPOSTED>\n", ofx_date(&transaction.date));
+            doc += &format!("<TRNAMT>{:.2}</TRNAMT>\n", transaction.amount);
+            doc += &format!("<FITID>{}</FITID>\n", transaction.id());
+            // NAME is limited to 32 characters, the full description goes to MEMO.
+            let name: String = transaction.tx.chars().take(32).collect();
+            doc += &format!("<NAME>{}</NAME>\n", xml_escape(&name));
+            doc += &format!("<MEMO>{}</MEMO>\n", xml_escape(&transaction.tx));
+            doc += "</STMTTRN>\n";
+        }
+
+        doc += "</BANKTRANLIST>\n";
+        // the closing balance isn't known from the transaction rows.
+        doc += &format!(
+            "<LEDGERBAL><BALAMT>0.00</BALAMT><DTASOF>{}</DTASOF></LEDGERBAL>\n",
+            ofx_date(&end)
+        );
+        doc += "</CCSTMTRS>\n";
+        doc += "</CCSTMTTRNRS></CREDITCARDMSGSRSV1>\n";
+        doc += "</OFX>\n";
+
+        self.out
+            .write_all(doc.as_bytes())
+            .context("Failed to write OFX")?;
+        self.out.flush().context("failed to flush OFX output")
+    }
+}
+
+fn ofx_date(date: &chrono::NaiveDateTime) -> String {
+    format!("{}[+5.30:IST]", date.format("%Y%m%d%H%M%S"))
+}
@@ -0,0 +1,55 @@
+use super::Output;
+use crate::Transaction;
+use anyhow::{Context, Error};
+use std::io::Write;
+
+// Quicken Interchange Format, as a credit card account. Each transaction is
+// a record of D(ate), T(otal), P(ayee), M(emo) and L (category) lines ended
+// by ^.
+pub struct Qif<W: Write> {
+    out: W,
+}
+
+impl<W: Write> Qif<W> {
+    pub fn new(mut out: W) -> Result<Self, Error> {
+        writeln!(out, "!Type:CCard").context("Failed to write headers")?;
+        Ok(Qif { out })
+    }
+}
+
+impl<W: Write> Output for Qif<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let mut record = format!(
+            "D{}\nT{:.2}\nP{}\n",
+            transaction.date.format("%m/%d/%Y"),
+            transaction.amount,
+            single_line(&transaction.tx)
+        );
+
+        let mut memo = Vec::new();
+        if transaction.points != 0 {
+            memo.push(format!("points {}", transaction.points));
+        }
+        if !transaction.tags.is_empty() {
+            memo.push(format!("tags {}", transaction.tags.join(",")));
+        }
+        if !memo.is_empty() {
+            record += &format!("M{}\n", memo.join("; "));
+        }
+
+        if let Some(category) = &transaction.category {
+            record += &format!("L{}\n", single_line(category));
+        }
+
+        writeln!(self.out, "{}^", record).context("Failed to write row")
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.out.flush().context("failed to flush QIF output")
+    }
+}
+
+// QIF fields are line based, keep values on one line.
+fn single_line(s: &str) -> String {
+    s.replace(['\r', '\n'], " ")
+}
@@ -0,0 +1,45 @@
+use super::Output;
+use crate::Transaction;
+use anyhow::{Context, Error};
+use std::io::Write;
+
+// CSV for QuickBooks Online's bank upload, in its four-column layout: Date,
+// Description, Credit, Debit, amounts positive in one or the other so the
+// sign can't be read the wrong way round for a card account. Dates are
+// DD/MM/YYYY (pick dd/MM/yyyy when uploading).
+pub struct QuickBooks<W: Write> {
+    wtr: csv::Writer<W>,
+}
+
+impl<W: Write> QuickBooks<W> {
+    pub fn new(out: W) -> Result<Self, Error> {
+        let mut wtr = csv::Writer::from_writer(out);
+        wtr.write_record(["Date", "Description", "Credit", "Debit"])
+            .context("Failed to write headers")?;
+        Ok(QuickBooks { wtr })
+    }
+}
+
+impl<W: Write> Output for QuickBooks<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let (credit, debit) = if transaction.amount < 0.0 {
+            (String::new(), format!("{:.2}", -transaction.amount))
+        } else {
+            (format!("{:.2}", transaction.amount), String::new())
+        };
+        self.wtr
+            .write_record([
+                transaction.date.format("%d/%m/%Y").to_string(),
+                transaction.tx.clone(),
+                credit,
+                debit,
+            ])
+            .context("Failed to write row")
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.wtr
+            .flush()
+            .context("failed to flush QuickBooks output")
+    }
+}
@@ -0,0 +1,96 @@
+use super::accounts::Accounts;
+use super::{xml_escape, Output};
+use crate::Transaction;
+use anyhow::{Context, Error};
+use std::io::Write;
+
+// ledgers for rows --accounts doesn't place.
+const CARD: &str = "HDFC Credit Card";
+const DEFAULT: &str = "Suspense";
+
+// Tally's XML import envelope with a voucher per transaction: a Payment
+// debiting the row's ledger and crediting the card for debits, a Receipt the
+// other way round for credits, against the ledgers of --accounts. The
+// transaction ID is the voucher's reference.
+pub struct Tally<W: Write> {
+    out: W,
+    ledgers: Accounts,
+}
+
+impl<W: Write> Tally<W> {
+    pub fn new(mut out: W, ledgers: Accounts) -> Result<Self, Error> {
+        let mut header = String::new();
+        header += "<ENVELOPE>\n";
+        header += "<HEADER><TALLYREQUEST>Import Data</TALLYREQUEST></HEADER>\n";
+        header += "<BODY><IMPORTDATA>\n";
+        header += "<REQUESTDESC><REPORTNAME>Vouchers</REPORTNAME>";
+        if let Some(company) = &ledgers.company {
+            header += &format!(
+                "<STATICVARIABLES><SVCURRENTCOMPANY>{}</SVCURRENTCOMPANY></STATICVARIABLES>",
+                xml_escape(company)
+            );
+        }
+        header += "</REQUESTDESC>\n";
+        header += "<REQUESTDATA>\n";
+        out.write_all(header.as_bytes())
+            .context("Failed to write Tally XML")?;
+
+        Ok(Tally { out, ledgers })
+    }
+}
+
+// A ledger entry: debits are deemed positive and carry a negative amount in
+// Tally's XML, credits the reverse.
+fn entry(ledger: &str, debit: bool, amount: f32) -> String {
+    let (deemed_positive, amount) = if debit {
+        ("Yes", -amount.abs())
+    } else {
+        ("No", amount.abs())
+    };
+    format!(
+        "<ALLLEDGERENTRIES.LIST><LEDGERNAME>{}</LEDGERNAME><ISDEEMEDPOSITIVE>{}</ISDEEMEDPOSITIVE><AMOUNT>{:.2}</AMOUNT></ALLLEDGERENTRIES.LIST>\n",
+        xml_escape(ledger),
+        deemed_positive,
+        amount
+    )
+}
+
+impl<W: Write> Output for Tally<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let kind = if transaction.amount < 0.0 {
+            "Payment"
+        } else {
+            "Receipt"
+        };
+        let ledger = self.ledgers.account(transaction, DEFAULT);
+        let card = self.ledgers.card(CARD);
+
+        let mut voucher = String::new();
+        voucher += "<TALLYMESSAGE xmlns:UDF=\"TallyUDF\">\n";
+        voucher += &format!("<VOUCHER VCHTYPE=\"{}\" ACTION=\"Create\">\n", kind);
+        voucher += &format!("<DATE>{}</DATE>\n", transaction.date.format("%Y%m%d"));
+        voucher += &format!("<VOUCHERTYPENAME>{}</VOUCHERTYPENAME>\n", kind);
+        voucher += &format!("<REFERENCE>{}</REFERENCE>\n", transaction.id());
+        voucher += &format!("<NARRATION>{}</NARRATION>\n", xml_escape(&transaction.tx));
+        if transaction.amount < 0.0 {
+            voucher += &entry(ledger, true, transaction.amount);
+            voucher += &entry(card, false, transaction.amount);
+        } else {
+            voucher += &entry(card, true, transaction.amount);
+            voucher += &entry(ledger, false, transaction.amount);
+        }
+        voucher += "</VOUCHER>\n";
+        voucher += "</TALLYMESSAGE>\n";
+
+        self.out
+            .write_all(voucher.as_bytes())
+            .context("Failed to write Tally XML")
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.out
+            .write_all(b"</REQUESTDATA>\n</IMPORTDATA></BODY>\n</ENVELOPE>\n")
+            .context("Failed to write Tally XML")?;
+        self.out.flush().context("failed to flush Tally output")
+    }
+}
@@ -0,0 +1,49 @@
+use super::Output;
+use crate::Transaction;
+use anyhow::{Context, Error};
+use std::io::Write;
+
+// CSV in the layout of YNAB's file importer: Date, Payee, Memo, Outflow,
+// Inflow, with positive amounts in either Outflow or Inflow.
+pub struct Ynab<W: Write> {
+    wtr: csv::Writer<W>,
+}
+
+impl<W: Write> Ynab<W> {
+    pub fn new(out: W) -> Result<Self, Error> {
+        let mut wtr = csv::Writer::from_writer(out);
+        wtr.write_record(["Date", "Payee", "Memo", "Outflow", "Inflow"])
+            .context("Failed to write headers")?;
+        Ok(Ynab { wtr })
+    }
+}
+
+impl<W: Write> Output for Ynab<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let (outflow, inflow) = if transaction.amount < 0.0 {
+            (format!("{:.2}", -transaction.amount), String::new())
+        } else {
+            (String::new(), format!("{:.2}", transaction.amount))
+        };
+
+        let mut memo = Vec::new();
+        if let Some(category) = &transaction.category {
+            memo.push(category.clone());
+        }
+        memo.extend(transaction.tags.iter().cloned());
+
+        self.wtr
+            .write_record([
+                transaction.date.format("%m/%d/%Y").to_string(),
+                transaction.tx.clone(),
+                memo.join(", "),
+                outflow,
+                inflow,
+            ])
+            .context("Failed to write row")
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.wtr.flush().context("failed to flush YNAB output")
+    }
+}
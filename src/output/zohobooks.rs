@@ -0,0 +1,55 @@
+use super::Output;
+use crate::categories::merchant_key;
+use crate::Transaction;
+use anyhow::{Context, Error};
+use std::io::Write;
+
+// CSV for Zoho Books' bank statement import: the columns of its template,
+// amounts positive in either Withdrawals or Deposits, dates as DD/MM/YYYY
+// (pick dd/MM/yyyy when importing), and the transaction ID as the reference
+// number.
+pub struct ZohoBooks<W: Write> {
+    wtr: csv::Writer<W>,
+}
+
+impl<W: Write> ZohoBooks<W> {
+    pub fn new(out: W) -> Result<Self, Error> {
+        let mut wtr = csv::Writer::from_writer(out);
+        wtr.write_record([
+            "Date",
+            "Withdrawals",
+            "Deposits",
+            "Payee",
+            "Description",
+            "Reference Number",
+        ])
+        .context("Failed to write headers")?;
+        Ok(ZohoBooks { wtr })
+    }
+}
+
+impl<W: Write> Output for ZohoBooks<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let (withdrawal, deposit) = if transaction.amount < 0.0 {
+            (format!("{:.2}", -transaction.amount), String::new())
+        } else {
+            (String::new(), format!("{:.2}", transaction.amount))
+        };
+        self.wtr
+            .write_record([
+                transaction.date.format("%d/%m/%Y").to_string(),
+                withdrawal,
+                deposit,
+                merchant_key(&transaction.tx),
+                transaction.tx.clone(),
+                transaction.id(),
+            ])
+            .context("Failed to write row")
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.wtr
+            .flush()
+            .context("failed to flush Zoho Books output")
+    }
+}
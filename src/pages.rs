@@ -0,0 +1,35 @@
+// --pages: the pages of each statement to parse, eg. "1-4,7", to look at one
+// problematic page or to skip the promotional annexures at the end.
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pages(Vec<RangeInclusive<usize>>);
+
+impl Pages {
+    // Whether the 1-based `page` is selected.
+    pub fn contains(&self, page: usize) -> bool {
+        self.0.iter().any(|range| range.contains(&page))
+    }
+}
+
+// Parses "1-4,7", "3" or "5-" (page 5 to the end) for clap.
+pub fn parse(s: &str) -> Result<Pages, String> {
+    let page = |n: &str| match n.trim().parse::<usize>() {
+        Ok(0) | Err(_) => Err(format!("invalid page {:?}, pages start at 1", n)),
+        Ok(n) => Ok(n),
+    };
+
+    let mut ranges = Vec::new();
+    for part in s.split(',') {
+        let range = match part.split_once('-') {
+            Some((from, "")) => page(from)?..=usize::MAX,
+            Some((from, to)) => page(from)?..=page(to)?,
+            None => page(part)?..=page(part)?,
+        };
+        if range.is_empty() {
+            return Err(format!("invalid page range {:?}", part));
+        }
+        ranges.push(range);
+    }
+    Ok(Pages(ranges))
+}
@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Error};
+use chrono::NaiveDate;
+use std::fmt::Write;
+
+// Password schemes banks document for their statements, tried in order with
+// --dob when no --password-pattern is given.
+pub const KNOWN_PATTERNS: [&str; 4] = [
+    // HDFC, first 4 letters of the name + DDMM
+    "{NAME:4}{dob:%d%m}",
+    // ICICI, the same in lowercase
+    "{name:4}{dob:%d%m}",
+    // Axis and others, first 4 letters + DDMMYY
+    "{NAME:4}{dob:%d%m%y}",
+    // date of birth alone
+    "{dob:%d%m%Y}",
+];
+
+// Derive a password from a pattern of literal text and placeholders:
+//
+//   {name:N}  first N letters of the name, lowercase
+//   {NAME:N}  first N letters of the name, uppercase
+//   {dob:FMT} date of birth in a chrono format, eg. {dob:%d%m}
+pub fn derive(pattern: &str, name: &str, dob: Option<NaiveDate>) -> Result<String, Error> {
+    let mut password = String::new();
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        password.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .ok_or_else(|| anyhow!("unclosed {{ in password pattern {}", pattern))?
+            + start;
+        let (field, arg) = rest[start + 1..end]
+            .split_once(':')
+            .unwrap_or((&rest[start + 1..end], ""));
+
+        match field {
+            "name" | "NAME" => {
+                let n: usize = arg
+                    .parse()
+                    .map_err(|_| anyhow!("{{{}:N}} needs a letter count, got {:?}", field, arg))?;
+                let letters: String = name.chars().filter(|c| c.is_alphabetic()).take(n).collect();
+                if field == "NAME" {
+                    password.push_str(&letters.to_uppercase());
+                } else {
+                    password.push_str(&letters.to_lowercase());
+                }
+            }
+            "dob" => {
+                let dob = dob.ok_or_else(|| anyhow!("password pattern {} needs --dob", pattern))?;
+                let format = if arg.is_empty() { "%d%m" } else { arg };
+                write!(password, "{}", dob.format(format)).map_err(|_| {
+                    anyhow!(
+                        "invalid date format {} in password pattern {}",
+                        format,
+                        pattern
+                    )
+                })?;
+            }
+            _ => {
+                return Err(anyhow!(
+                    "unknown placeholder {{{}}} in password pattern",
+                    field
+                ))
+            }
+        }
+
+        rest = &rest[end + 1..];
+    }
+    password.push_str(rest);
+
+    Ok(password)
+}
+
+// Ask for the password of `file` on the terminal, None if left empty.
+pub fn prompt(file: &str) -> Result<Option<String>, Error> {
+    let password = rpassword::prompt_password(format!("Password for {} (empty to skip): ", file))?;
+    Ok(Some(password).filter(|p| !p.is_empty()))
+}
+
+// --password keyring:<entry> reads the password stored under `entry` in the
+// OS keyring (Keychain, Credential Manager, Secret Service). Anything else is
+// the password itself.
+pub fn resolve(password: &str) -> Result<String, Error> {
+    match password.strip_prefix("keyring:") {
+        Some(entry) => keyring::get(entry),
+        None => Ok(password.to_owned()),
+    }
+}
+
+// `password set <entry>`: store a password for keyring:<entry>.
+pub fn set(entry: &str) -> Result<(), Error> {
+    keyring::set(entry)
+}
+
+#[cfg(feature = "keyring")]
+mod keyring {
+    use anyhow::{Context, Error};
+    use std::io::{self, BufRead, IsTerminal};
+
+    const SERVICE: &str = "hdfc-cc-parser-rs";
+
+    pub fn get(entry: &str) -> Result<String, Error> {
+        ::keyring::Entry::new(SERVICE, entry)
+            .and_then(|e| e.get_password())
+            .context(format!("failed to read keyring entry {}", entry))
+    }
+
+    pub fn set(entry: &str) -> Result<(), Error> {
+        // prompt without echo on a terminal, otherwise take the first line
+        // of stdin so it can be piped in.
+        let password = if io::stdin().is_terminal() {
+            rpassword::prompt_password(format!("Password for {}: ", entry))?
+        } else {
+            let mut line = String::new();
+            io::stdin().lock().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_owned()
+        };
+        ::keyring::Entry::new(SERVICE, entry)
+            .and_then(|e| e.set_password(&password))
+            .context(format!("failed to store keyring entry {}", entry))
+    }
+}
+
+#[cfg(not(feature = "keyring"))]
+mod keyring {
+    use anyhow::{anyhow, Error};
+
+    pub fn get(entry: &str) -> Result<String, Error> {
+        Err(anyhow!(
+            "keyring:{} needs a build with --features keyring",
+            entry
+        ))
+    }
+
+    pub fn set(_entry: &str) -> Result<(), Error> {
+        Err(anyhow!(
+            "password set needs a build with --features keyring"
+        ))
+    }
+}
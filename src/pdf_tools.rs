@@ -0,0 +1,442 @@
+// Text as drawn on a page, decoded with the page's fonts. Taking the bytes
+// of text operators as UTF-8 only works for plain Latin fonts; statements
+// with embedded or subset fonts need their ToUnicode CMaps or encodings.
+use pdf::content::{Matrix, Op, TextDrawAdjusted};
+use pdf::encoding::BaseEncoding;
+use pdf::error::PdfError;
+use pdf::font::{Font, FontData, ToUnicodeMap};
+use pdf::object::{Page, PlainRef, Resolve};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// What the parsers need of a page's operators: text objects and the text
+// drawn in them, in drawing order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextOp {
+    BeginText,
+    EndText,
+    Text(String),
+}
+
+// How a font's character codes turn into text.
+enum Decoder {
+    // a ToUnicode CMap, over codes `width` bytes wide.
+    ToUnicode {
+        map: ToUnicodeMap,
+        width: usize,
+    },
+    // one byte codes, through the font's encoding and its differences.
+    Simple(Vec<Option<String>>),
+    // two byte codes of a UCS-2/UTF-16 CMap, which are Unicode already.
+    Utf16,
+    // two byte CIDs, to glyphs through the CIDToGIDMap (identity when
+    // absent), and to text through the embedded font's own cmap.
+    Cid {
+        cid_to_gid: Option<Vec<u16>>,
+        glyphs: HashMap<u16, char>,
+    },
+    // fonts the page doesn't describe: UTF-8, or else Latin-1.
+    Raw,
+}
+
+pub struct FontInfo {
+    decoder: Decoder,
+}
+
+impl FontInfo {
+    pub fn new(font: &Font, resolve: &impl Resolve) -> Self {
+        let decoder = match font.to_unicode(resolve) {
+            Some(Ok(map)) => Decoder::ToUnicode {
+                map,
+                width: if font.is_cid() { 2 } else { 1 },
+            },
+            _ if font.is_cid() => cid_decoder(font, resolve),
+            _ => Decoder::Simple(encoding_table(font)),
+        };
+        FontInfo { decoder }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> String {
+        match &self.decoder {
+            Decoder::ToUnicode { map, width } => bytes
+                .chunks(*width)
+                .filter_map(|code| map.get(code.iter().fold(0, |c, &b| c << 8 | b as u16)))
+                .collect(),
+            Decoder::Simple(table) => bytes
+                .iter()
+                .filter_map(|&b| table[b as usize].as_deref())
+                .collect(),
+            Decoder::Utf16 => {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            Decoder::Cid { cid_to_gid, glyphs } => bytes
+                .chunks_exact(2)
+                .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                .map(|cid| match cid_to_gid {
+                    Some(map) => map.get(cid as usize).copied().unwrap_or(cid),
+                    None => cid,
+                })
+                .filter_map(|gid| glyphs.get(&gid))
+                .collect(),
+            Decoder::Raw => raw(bytes),
+        }
+    }
+}
+
+// The decoders of the fonts a page's resources name.
+pub struct FontCache {
+    fonts: HashMap<String, Rc<FontInfo>>,
+}
+
+// The decoders of the fonts seen so far in a document, by object reference.
+// Statements name the same few fonts on every page, and reading their
+// ToUnicode CMaps once per document instead of once per page adds up.
+#[derive(Default)]
+pub struct DocumentFonts {
+    fonts: HashMap<PlainRef, Rc<FontInfo>>,
+}
+
+impl FontCache {
+    pub fn new(page: &Page, resolve: &impl Resolve) -> Self {
+        Self::cached(page, resolve, &mut DocumentFonts::default())
+    }
+
+    // Like `new`, taking the decoders of fonts `document` has seen on earlier
+    // pages from it, and adding the new ones. Fonts defined inline in the
+    // page's resources have no reference and are read every time.
+    pub fn cached(page: &Page, resolve: &impl Resolve, document: &mut DocumentFonts) -> Self {
+        let resources = match page.resources() {
+            Ok(resources) => resources,
+            Err(_) => {
+                return FontCache {
+                    fonts: HashMap::new(),
+                }
+            }
+        };
+        let fonts = resources
+            .fonts
+            .iter()
+            .map(|(name, font)| {
+                let info = match font.as_ref() {
+                    Some(reference) => document
+                        .fonts
+                        .entry(reference)
+                        .or_insert_with(|| Rc::new(FontInfo::new(font, resolve)))
+                        .clone(),
+                    None => Rc::new(FontInfo::new(font, resolve)),
+                };
+                (name.as_str().to_owned(), info)
+            })
+            .collect();
+        FontCache { fonts }
+    }
+
+    fn decode(&self, font: Option<&str>, bytes: &[u8]) -> String {
+        match font.and_then(|name| self.fonts.get(name)) {
+            Some(info) => info.decode(bytes),
+            None => raw(bytes),
+        }
+    }
+}
+
+// A run of text as drawn on a page: the point it starts at, in the page's
+// user space (points up and right from the bottom left corner), and the size
+// it's drawn at. Runs drawn one after another without moving in between
+// share their start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextRun {
+    pub text: String,
+    pub x: f32,
+    pub y: f32,
+    pub font_size: f32,
+}
+
+// The text operators of a page, with the text they draw decoded in the font
+// selected at that point.
+pub fn ops_with_text_state(ops: &[Op], fonts: &FontCache) -> Vec<TextOp> {
+    let mut text_ops = Vec::new();
+    walk(ops, fonts, |op, _| text_ops.push(op));
+    text_ops
+}
+
+// Every run of text drawn on `page`, in drawing order, decoded with the
+// page's fonts. For building layout heuristics of one's own on documents
+// other than statements.
+pub fn extract_positioned_text(
+    page: &Page,
+    resolve: &impl Resolve,
+) -> Result<Vec<TextRun>, PdfError> {
+    let ops = match &page.contents {
+        Some(content) => content.operations(resolve)?,
+        None => Vec::new(),
+    };
+    let fonts = FontCache::new(page, resolve);
+
+    let mut runs = Vec::new();
+    walk(&ops, &fonts, |op, state| {
+        if let TextOp::Text(text) = op {
+            let m = mul(state.tm, state.ctm);
+            runs.push(TextRun {
+                text,
+                x: m[4],
+                y: m[5],
+                font_size: state.size * m[2].hypot(m[3]),
+            });
+        }
+    });
+    Ok(runs)
+}
+
+// A transformation matrix [a b c d e f], as the PDF spec writes them.
+type Transform = [f32; 6];
+
+const IDENTITY: Transform = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+// `a`, then `b`.
+fn mul(a: Transform, b: Transform) -> Transform {
+    [
+        a[0] * b[0] + a[1] * b[2],
+        a[0] * b[1] + a[1] * b[3],
+        a[2] * b[0] + a[3] * b[2],
+        a[2] * b[1] + a[3] * b[3],
+        a[4] * b[0] + a[5] * b[2] + b[4],
+        a[4] * b[1] + a[5] * b[3] + b[5],
+    ]
+}
+
+fn translate(x: f32, y: f32) -> Transform {
+    [1.0, 0.0, 0.0, 1.0, x, y]
+}
+
+fn transform(m: &Matrix) -> Transform {
+    [m.a, m.b, m.c, m.d, m.e, m.f]
+}
+
+// The parts of the graphics state deciding how and where text is drawn.
+#[derive(Clone)]
+struct TextState<'a> {
+    font: Option<&'a str>,
+    size: f32,
+    leading: f32,
+    // current transformation matrix.
+    ctm: Transform,
+    // text matrix, and the text line matrix Td and T* move from.
+    tm: Transform,
+    line: Transform,
+}
+
+impl Default for TextState<'_> {
+    fn default() -> Self {
+        TextState {
+            font: None,
+            size: 0.0,
+            leading: 0.0,
+            ctm: IDENTITY,
+            tm: IDENTITY,
+            line: IDENTITY,
+        }
+    }
+}
+
+// Walks a page's operators, handing each text operator (with the text it
+// draws decoded) and the state it's drawn in to `emit`.
+fn walk<'a>(ops: &'a [Op], fonts: &FontCache, mut emit: impl FnMut(TextOp, &TextState<'a>)) {
+    let mut state = TextState::default();
+    // q/Q save and restore the graphics state, the font among it.
+    let mut saved = Vec::new();
+
+    for op in ops {
+        match op {
+            Op::Save => saved.push(state.clone()),
+            Op::Restore => state = saved.pop().unwrap_or_default(),
+            Op::Transform { matrix } => state.ctm = mul(transform(matrix), state.ctm),
+            Op::TextFont { name, size } => {
+                state.font = Some(name.as_str());
+                state.size = *size;
+            }
+            Op::Leading { leading } => state.leading = *leading,
+            Op::BeginText => {
+                state.tm = IDENTITY;
+                state.line = IDENTITY;
+                emit(TextOp::BeginText, &state);
+            }
+            Op::EndText => emit(TextOp::EndText, &state),
+            Op::SetTextMatrix { matrix } => {
+                state.tm = transform(matrix);
+                state.line = state.tm;
+            }
+            Op::MoveTextPosition { translation } => {
+                state.line = mul(translate(translation.x, translation.y), state.line);
+                state.tm = state.line;
+            }
+            Op::TextNewline => {
+                state.line = mul(translate(0.0, -state.leading), state.line);
+                state.tm = state.line;
+            }
+            Op::TextDraw { text } => {
+                let text = fonts.decode(state.font, text.as_bytes());
+                emit(TextOp::Text(text), &state);
+            }
+            Op::TextDrawAdjusted { array } => {
+                let text = adjusted_text(array, state.font, fonts);
+                emit(TextOp::Text(text), &state);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Kerned text (TJ): the strings of the array joined, with a space where the
+// adjustment moves as far as a word gap. Adjustments are in thousandths of
+// the font size, negative moving right.
+fn adjusted_text(array: &[TextDrawAdjusted], font: Option<&str>, fonts: &FontCache) -> String {
+    let mut text = String::new();
+    for part in array {
+        match part {
+            TextDrawAdjusted::Text(s) => text.push_str(&fonts.decode(font, s.as_bytes())),
+            TextDrawAdjusted::Spacing(gap) if *gap < -WORD_GAP && !text.ends_with(' ') => {
+                text.push(' ')
+            }
+            TextDrawAdjusted::Spacing(_) => {}
+        }
+    }
+    text
+}
+
+// Adjustment taken as a space between words rather than kerning.
+const WORD_GAP: f32 = 200.0;
+
+// Decoder of a CID font without a ToUnicode map. Newer statement templates
+// embed TrueType subsets this way, leaving the font's cmap as the only way
+// back from glyphs to text.
+fn cid_decoder(font: &Font, resolve: &impl Resolve) -> Decoder {
+    if let Some(BaseEncoding::Other(name)) = font.encoding().map(|e| &e.base) {
+        if name.as_str().contains("UCS2") || name.as_str().contains("UTF16") {
+            return Decoder::Utf16;
+        }
+    }
+
+    let descendant = match &font.data {
+        FontData::Type0(type0) => type0.descendant_fonts.first(),
+        _ => None,
+    };
+    let cid_to_gid = match descendant.map(|font| &font.data) {
+        Some(FontData::CIDFontType2(_, Some(map))) => Some(map.clone()),
+        _ => None,
+    };
+    let glyphs = match font.embedded_data(resolve) {
+        Some(Ok(data)) => glyph_chars(&data),
+        _ => HashMap::new(),
+    };
+
+    if glyphs.is_empty() {
+        return Decoder::Raw;
+    }
+    Decoder::Cid { cid_to_gid, glyphs }
+}
+
+// Characters of an embedded TrueType font's glyphs, from its Unicode cmap
+// subtables. Bare CFF programs have no cmap and give nothing.
+fn glyph_chars(data: &[u8]) -> HashMap<u16, char> {
+    let mut glyphs = HashMap::new();
+    let face = match ttf_parser::Face::parse(data, 0) {
+        Ok(face) => face,
+        Err(_) => return glyphs,
+    };
+    let subtables = face
+        .tables()
+        .cmap
+        .into_iter()
+        .flat_map(|cmap| cmap.subtables);
+    for subtable in subtables.filter(|subtable| subtable.is_unicode()) {
+        subtable.codepoints(|code| {
+            if let (Some(gid), Some(c)) = (subtable.glyph_index(code), char::from_u32(code)) {
+                glyphs.entry(gid.0).or_insert(c);
+            }
+        });
+    }
+    glyphs
+}
+
+fn raw(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_owned(),
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+// Text of each one byte code of a simple font: its base encoding, with the
+// glyph names of the encoding's differences on top.
+fn encoding_table(font: &Font) -> Vec<Option<String>> {
+    let encoding = font.encoding();
+    let win_ansi = matches!(
+        encoding.map(|e| &e.base),
+        Some(BaseEncoding::WinAnsiEncoding)
+    );
+
+    let mut table: Vec<Option<String>> = (0..=255u8)
+        .map(|b| match b {
+            0x80..=0x9f if win_ansi => WIN_ANSI[b as usize - 0x80].map(String::from),
+            0x20..=0x7e | 0xa0..=0xff => Some((b as char).to_string()),
+            _ => None,
+        })
+        .collect();
+
+    if let Some(encoding) = encoding {
+        for (&code, name) in &encoding.differences {
+            if let Some(slot) = table.get_mut(code as usize) {
+                *slot = glyph_text(name.as_str());
+            }
+        }
+    }
+    table
+}
+
+// Text of a glyph name: a standard one ("rupee", "bullet"), or uniXXXX.
+fn glyph_text(name: &str) -> Option<String> {
+    if let Some(text) = pdf_encoding::glyphname_to_unicode(name) {
+        return Some(text.to_owned());
+    }
+    let hex = name.strip_prefix("uni")?;
+    char::from_u32(u32::from_str_radix(hex.get(..4)?, 16).ok()?).map(String::from)
+}
+
+// WinAnsiEncoding where it differs from Latin-1, 0x80 to 0x9f.
+const WIN_ANSI: [Option<char>; 32] = [
+    Some('€'),
+    None,
+    Some('‚'),
+    Some('ƒ'),
+    Some('„'),
+    Some('…'),
+    Some('†'),
+    Some('‡'),
+    Some('ˆ'),
+    Some('‰'),
+    Some('Š'),
+    Some('‹'),
+    Some('Œ'),
+    None,
+    Some('Ž'),
+    None,
+    None,
+    Some('‘'),
+    Some('’'),
+    Some('“'),
+    Some('”'),
+    Some('•'),
+    Some('–'),
+    Some('—'),
+    Some('˜'),
+    Some('™'),
+    Some('š'),
+    Some('›'),
+    Some('œ'),
+    None,
+    Some('ž'),
+    Some('Ÿ'),
+];
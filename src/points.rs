@@ -0,0 +1,96 @@
+// Reward points across statements, from their points summaries: earned,
+// redeemed and expired, and the batches still unspent, to warn before a
+// large one expires. Points are spent oldest first, as HDFC redeems and
+// expires them.
+use crate::statement::Statement;
+use chrono::{Months, NaiveDate};
+
+// Points unspent of what a statement earned.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch {
+    pub earned_on: NaiveDate,
+    pub points: i32,
+}
+
+#[derive(Debug, Default)]
+pub struct Ledger {
+    pub earned: i32,
+    pub redeemed: i32,
+    // drops in the balance the statements don't account for.
+    pub expired: i32,
+    // the last statement's closing balance.
+    pub balance: i32,
+    // the last statement's date.
+    pub as_of: Option<NaiveDate>,
+    // oldest first.
+    pub batches: Vec<Batch>,
+}
+
+impl Ledger {
+    // The ledger of the statements with a points summary and a date. The
+    // first one's opening balance counts as earned on its date, so expiries
+    // of those points may come earlier than shown.
+    pub fn new(statements: &[Statement]) -> Ledger {
+        let mut statements: Vec<&Statement> = statements
+            .iter()
+            .filter(|s| s.statement_date.is_some() && s.points_closing.is_some())
+            .collect();
+        statements.sort_by_key(|s| s.statement_date);
+
+        let mut ledger = Ledger::default();
+        for (i, statement) in statements.iter().enumerate() {
+            let date = statement.statement_date.unwrap();
+            let opening = statement.points_opening.unwrap_or_default();
+            let earned = statement.points_earned.unwrap_or_default();
+            let redeemed = statement.points_redeemed.unwrap_or_default();
+            let closing = statement.points_closing.unwrap_or_default();
+            if i == 0 {
+                ledger.add(date, opening);
+            }
+            ledger.add(date, earned);
+            ledger.earned += earned;
+            ledger.redeemed += redeemed;
+            let expired = (opening + earned - redeemed - closing).max(0);
+            ledger.expired += expired;
+            ledger.spend(redeemed + expired);
+            ledger.balance = closing;
+            ledger.as_of = Some(date);
+        }
+        ledger
+    }
+
+    fn add(&mut self, date: NaiveDate, points: i32) {
+        if points > 0 {
+            self.batches.push(Batch {
+                earned_on: date,
+                points,
+            });
+        }
+    }
+
+    fn spend(&mut self, mut points: i32) {
+        while points > 0 {
+            let Some(batch) = self.batches.first_mut() else {
+                return;
+            };
+            let spent = points.min(batch.points);
+            batch.points -= spent;
+            points -= spent;
+            if batch.points == 0 {
+                self.batches.remove(0);
+            }
+        }
+    }
+
+    // The unspent batches expiring on or before `by`, `months` after they
+    // were earned, with their expiry dates.
+    pub fn expiring(&self, months: u32, by: NaiveDate) -> Vec<(NaiveDate, &Batch)> {
+        self.batches
+            .iter()
+            .filter_map(|batch| {
+                let expiry = batch.earned_on.checked_add_months(Months::new(months))?;
+                (expiry <= by).then_some((expiry, batch))
+            })
+            .collect()
+    }
+}
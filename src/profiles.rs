@@ -0,0 +1,77 @@
+// --profiles: the statements of several holders in one run, eg. a
+// household's cards, each with its own name and passwords, merged into one
+// output with an owner column. A TOML file of [[profile]] tables:
+//
+//   [[profile]]
+//   owner = "John"          # the owner column, the name by default
+//   name = "JOHN DOE"
+//   dir = "statements/john" # or file = "..."
+//   passwords = ["JOHN0101"]
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub owner: Option<String>,
+    pub name: String,
+    pub dir: Option<String>,
+    pub file: Option<String>,
+    // tried before the run's --password and friends, derived for `name`.
+    #[serde(default)]
+    pub passwords: Vec<String>,
+}
+
+impl Profile {
+    pub fn owner(&self) -> &str {
+        self.owner.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfilesFile {
+    profile: Vec<Profile>,
+}
+
+pub fn load(path: &str) -> Result<Vec<Profile>, Error> {
+    let data = fs::read_to_string(path).context(format!("failed to read {}", path))?;
+    let file: ProfilesFile = toml::from_str(&data).context(format!("failed to parse {}", path))?;
+    for (i, profile) in file.profile.iter().enumerate() {
+        if profile.dir.is_some() == profile.file.is_some() {
+            return Err(anyhow!(
+                "{}: profile {} ({}) needs one of dir or file",
+                path,
+                i + 1,
+                profile.owner()
+            ));
+        }
+    }
+    if file.profile.is_empty() {
+        return Err(anyhow!("{}: no profiles", path));
+    }
+    Ok(file.profile)
+}
+
+// Rows of a statement parsed for more than one profile, as when holders
+// share a statement, are kept once, from the first profile's copy. Rows are
+// known by their statement's card and date and their ID, which tells
+// identical rows of a statement apart.
+#[derive(Debug, Default)]
+pub struct Dedup {
+    seen: HashSet<(String, NaiveDate, String)>,
+}
+
+impl Dedup {
+    // Whether the row is the first of its kind.
+    pub fn first(&mut self, transaction: &Transaction) -> bool {
+        let (Some(card), Some(date)) = (&transaction.card, transaction.statement_date) else {
+            return true;
+        };
+        self.seen.insert((card.clone(), date, transaction.id()))
+    }
+}
@@ -0,0 +1,206 @@
+// `query --sql`: SQL over the parsed rows, loaded into an in-memory DuckDB
+// table `transactions`, printed as a table or CSV. Needs a build with the sql
+// feature.
+use crate::categories::Categories;
+use crate::Transaction;
+use anyhow::Error;
+use std::io::{self, Write};
+
+// A result: column names, and rows of values as text, None for NULL.
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+// Run `sql` over `rows`, categorized with `categories`.
+pub fn run(
+    rows: Vec<Transaction>,
+    categories: Option<&Categories>,
+    sql: &str,
+) -> Result<Table, Error> {
+    let rows = rows.into_iter().map(|mut transaction| {
+        if let Some(categories) = categories {
+            transaction.category = categories.categorize(&transaction).map(str::to_owned);
+        }
+        transaction
+    });
+    engine::run(rows, sql)
+}
+
+// Print the result as aligned columns, numbers right aligned, or as CSV.
+pub fn print(table: &Table, format: &str) -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if format == "csv" {
+        let mut wtr = csv::Writer::from_writer(out);
+        wtr.write_record(&table.columns)?;
+        for row in &table.rows {
+            wtr.write_record(row.iter().map(|v| v.as_deref().unwrap_or_default()))?;
+        }
+        wtr.flush()?;
+        return Ok(());
+    }
+
+    let cell = |v: &Option<String>| v.clone().unwrap_or_else(|| "NULL".to_owned());
+    let widths: Vec<usize> = table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            table
+                .rows
+                .iter()
+                .map(|row| cell(&row[i]).chars().count())
+                .chain([column.chars().count()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let line = |cells: Vec<String>| -> String {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| {
+                if cell.parse::<f64>().is_ok() {
+                    format!("{:>width$}", cell, width = width)
+                } else {
+                    format!("{:<width$}", cell, width = width)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_owned()
+    };
+    writeln!(out, "{}", line(table.columns.clone()))?;
+    writeln!(
+        out,
+        "{}",
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<_>>()
+            .join("  ")
+    )?;
+    for row in &table.rows {
+        writeln!(out, "{}", line(row.iter().map(cell).collect()))?;
+    }
+    writeln!(out, "({} rows)", table.rows.len())?;
+    Ok(())
+}
+
+#[cfg(feature = "sql")]
+mod engine {
+    use super::Table;
+    use crate::categories::merchant_key;
+    use crate::Transaction;
+    use anyhow::{Context, Error};
+    use duckdb::{params, Connection};
+
+    // The columns of the transactions table, and their SQL types.
+    const COLUMNS: [(&str, &str); 21] = [
+        ("date", "TIMESTAMP"),
+        ("description", "VARCHAR"),
+        ("merchant", "VARCHAR"),
+        ("city", "VARCHAR"),
+        ("state", "VARCHAR"),
+        ("category", "VARCHAR"),
+        ("tags", "VARCHAR[]"),
+        ("type", "VARCHAR"),
+        ("points", "INTEGER"),
+        ("cashback", "DOUBLE"),
+        ("rewards_unit", "VARCHAR"),
+        ("amount", "DOUBLE"),
+        ("balance", "DOUBLE"),
+        ("statement_date", "DATE"),
+        ("statement_file", "VARCHAR"),
+        ("card", "VARCHAR"),
+        ("owner", "VARCHAR"),
+        ("match_id", "VARCHAR"),
+        ("emi_group", "VARCHAR"),
+        ("converted_amount", "DOUBLE"),
+        ("id", "VARCHAR"),
+    ];
+
+    pub fn run(rows: impl Iterator<Item = Transaction>, sql: &str) -> Result<Table, Error> {
+        let conn = Connection::open_in_memory()?;
+        let columns: Vec<String> = COLUMNS
+            .iter()
+            .map(|(name, ty)| format!("\"{}\" {}", name, ty))
+            .collect();
+        conn.execute_batch(&format!(
+            "CREATE TABLE transactions ({}); BEGIN;",
+            columns.join(", ")
+        ))?;
+
+        // dates go in as text, cast by DuckDB, and tags joined by commas.
+        let mut insert = conn.prepare(
+            "INSERT INTO transactions VALUES (CAST(? AS TIMESTAMP), ?, ?, ?, ?, ?, \
+             list_filter(string_split(?, ','), t -> t <> ''), ?, ?, ?, ?, ?, ?, \
+             CAST(? AS DATE), ?, ?, ?, ?, ?, ?, ?)",
+        )?;
+        for transaction in rows {
+            insert.execute(params![
+                transaction.date.format("%Y-%m-%d %H:%M:%S").to_string(),
+                transaction.tx,
+                merchant_key(&transaction.tx),
+                transaction.city,
+                transaction.state,
+                transaction.category,
+                transaction.tags.join(","),
+                transaction.transaction_type().name(),
+                transaction.points,
+                transaction.cashback as f64,
+                transaction.rewards_unit,
+                transaction.amount as f64,
+                transaction.balance.map(f64::from),
+                transaction
+                    .statement_date
+                    .map(|d| d.format("%Y-%m-%d").to_string()),
+                transaction.statement_file,
+                transaction.card,
+                transaction.owner,
+                transaction.match_id,
+                transaction.emi_group,
+                transaction.converted_amount.map(f64::from),
+                transaction.id(),
+            ])?;
+        }
+        conn.execute_batch("COMMIT;")?;
+
+        // every column as text, so any result prints.
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT COLUMNS(*)::VARCHAR FROM ({})",
+                sql.trim().trim_end_matches(';')
+            ))
+            .context("invalid query")?;
+        let mut result = stmt.query([])?;
+        let columns = result
+            .as_ref()
+            .map(|stmt| stmt.column_names())
+            .unwrap_or_default();
+        let mut rows = Vec::new();
+        while let Some(row) = result.next()? {
+            rows.push(
+                (0..columns.len())
+                    .map(|i| row.get::<_, Option<String>>(i))
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+        Ok(Table { columns, rows })
+    }
+}
+
+#[cfg(not(feature = "sql"))]
+mod engine {
+    use super::Table;
+    use crate::Transaction;
+    use anyhow::{anyhow, Error};
+
+    pub fn run(_rows: impl Iterator<Item = Transaction>, _sql: &str) -> Result<Table, Error> {
+        Err(anyhow!(
+            "query needs a build with the sql feature (cargo build --features sql)"
+        ))
+    }
+}
@@ -0,0 +1,81 @@
+// --redact: output that's safe to share, eg. in a bug report. Card and
+// account numbers keep only their last four digits, the cardholder's name is
+// masked wherever it shows up (NEFT and UPI descriptions carry it), and with
+// --redact-merchants descriptions are replaced by a hash of their merchant.
+use crate::categories::merchant_key;
+use crate::statement::Statement;
+use crate::Transaction;
+use regex::{Captures, Regex};
+use sha2::{Digest, Sha256};
+
+const MASK: &str = "XXXX";
+
+#[derive(Clone)]
+pub struct Redactor {
+    // runs of 12 to 19 digits (or masking Xs), in groups or not.
+    numbers: Regex,
+    // the words of --name.
+    name: Option<Regex>,
+    merchants: bool,
+}
+
+impl Redactor {
+    pub fn new(name: &str, merchants: bool) -> Self {
+        let words: Vec<String> = name
+            .split_whitespace()
+            .filter(|word| word.len() > 2)
+            .map(regex::escape)
+            .collect();
+        Redactor {
+            numbers: Regex::new(r"[0-9X*](?:[ -]?[0-9X*]){11,18}").unwrap(),
+            name: (!words.is_empty())
+                .then(|| Regex::new(&format!(r"(?i)\b(?:{})\b", words.join("|"))).unwrap()),
+            merchants,
+        }
+    }
+
+    fn text(&self, text: &str) -> String {
+        // card and account numbers end in digits, masked or not.
+        let text = self.numbers.replace_all(text, |c: &Captures| {
+            let run = &c[0];
+            if run.chars().rev().take(4).all(|c| c.is_ascii_digit()) {
+                number(run)
+            } else {
+                run.to_owned()
+            }
+        });
+        match &self.name {
+            Some(name) => name.replace_all(&text, MASK).to_string(),
+            None => text.to_string(),
+        }
+    }
+
+    pub fn transaction(&self, transaction: &mut Transaction) {
+        transaction.tx = if self.merchants {
+            let digest = Sha256::digest(merchant_key(&transaction.tx));
+            let hash: String = digest[..4].iter().map(|b| format!("{:02x}", b)).collect();
+            format!("MERCHANT {}", hash)
+        } else {
+            self.text(&transaction.tx)
+        };
+        transaction.statement_file = self.text(&transaction.statement_file);
+        transaction.card = transaction.card.as_deref().map(number);
+        transaction.owner = transaction.owner.as_deref().map(|owner| self.text(owner));
+        if let Some(provenance) = &mut transaction.provenance {
+            provenance.texts = provenance.texts.iter().map(|t| self.text(t)).collect();
+        }
+    }
+
+    pub fn statement(&self, statement: &mut Statement) {
+        statement.file = self.text(&statement.file);
+        statement.card_number = statement.card_number.as_deref().map(number);
+        statement.account_number = statement.account_number.as_deref().map(number);
+    }
+}
+
+// All but the last four digits of a card or account number.
+fn number(number: &str) -> String {
+    let digits: Vec<char> = number.chars().filter(char::is_ascii_alphanumeric).collect();
+    let last: String = digits[digits.len().saturating_sub(4)..].iter().collect();
+    format!("{} {} {} {}", MASK, MASK, MASK, last)
+}
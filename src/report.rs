@@ -0,0 +1,288 @@
+// `report`: a month's card spend as an HTML page, per category against the
+// month before, with the top merchants and largest debits. With --email it's
+// sent over SMTP, per the [smtp] table of --config, so household members who
+// never run the CLI still see the numbers:
+//
+//   [smtp]
+//   host = "smtp.gmail.com"
+//   port = 587                        # STARTTLS, the default; 465 for TLS
+//   username = "me@gmail.com"
+//   password = "keyring:smtp"         # or the password itself
+//   from = "Card reports <me@gmail.com>"
+//   to = ["spouse@example.com", "me@gmail.com"]
+use crate::categories::merchant_key;
+use crate::forecast;
+use crate::locale::Locale;
+use crate::summary;
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use chrono::{Datelike, Months, NaiveDate};
+use serde::Serialize;
+use std::collections::HashMap;
+
+// merchants and debits listed.
+const TOP: usize = 10;
+
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{ title }}</title>
+</head>
+<body style="font-family: sans-serif; max-width: 640px; margin: auto; color: #222">
+<h2>{{ title }}</h2>
+<p><strong>{{ total }}</strong> spent over {{ rows }} transactions{% if previous %}, {{ previous }} in {{ previous_month }}{% endif %}.</p>
+{% if categories %}
+<h3>By category</h3>
+<table style="border-collapse: collapse; width: 100%">
+<tr><th align="left">Category</th><th align="right">Spent</th><th align="right">{{ previous_month }}</th><th align="right">Transactions</th></tr>
+{% for c in categories -%}
+<tr style="border-top: 1px solid #ddd"><td>{{ c.name }}</td><td align="right">{{ c.spend }}</td><td align="right">{{ c.previous }}</td><td align="right">{{ c.rows }}</td></tr>
+{% endfor -%}
+</table>
+{% endif %}
+{% if merchants %}
+<h3>Top merchants</h3>
+<table style="border-collapse: collapse; width: 100%">
+{% for m in merchants -%}
+<tr style="border-top: 1px solid #ddd"><td>{{ m.name }}</td><td align="right">{{ m.spend }}</td></tr>
+{% endfor -%}
+</table>
+{% endif %}
+{% if largest %}
+<h3>Largest debits</h3>
+<table style="border-collapse: collapse; width: 100%">
+{% for t in largest -%}
+<tr style="border-top: 1px solid #ddd"><td>{{ t.date }}</td><td>{{ t.description }}</td><td align="right">{{ t.amount }}</td></tr>
+{% endfor -%}
+</table>
+{% endif %}
+</body>
+</html>
+"#;
+
+#[derive(Serialize)]
+struct Line {
+    name: String,
+    spend: String,
+    previous: String,
+    rows: usize,
+}
+
+#[derive(Serialize)]
+struct Merchant {
+    name: String,
+    spend: String,
+}
+
+#[derive(Serialize)]
+struct Debit {
+    date: String,
+    description: String,
+    amount: String,
+}
+
+fn first_day(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap()
+}
+
+// The subject and HTML of the report on `month`, the latest month of the
+// rows by default, the rows categorized already.
+pub fn render(
+    rows: &[Transaction],
+    month: Option<NaiveDate>,
+    locale: Locale,
+) -> Result<(String, String), Error> {
+    let month = month
+        .or_else(|| rows.iter().map(|t| t.date.date()).max())
+        .map(first_day)
+        .ok_or_else(|| anyhow!("no transactions to report on"))?;
+    let before = month - Months::new(1);
+    let of = |month: NaiveDate| {
+        rows.iter()
+            .filter(move |t| first_day(t.date.date()) == month && forecast::spend(t) != 0.0)
+    };
+
+    let categories = summary::spend_by_category(of(month));
+    let previous: HashMap<String, f32> = summary::spend_by_category(of(before))
+        .into_iter()
+        .map(|(category, spend, _)| (category, spend))
+        .collect();
+    let total = categories
+        .iter()
+        .fold(0.0, |total, (_, spend, _)| total + spend);
+    let rows_in_month: usize = categories.iter().map(|(_, _, n)| n).sum();
+
+    let mut merchants: HashMap<String, f32> = HashMap::new();
+    for transaction in of(month) {
+        *merchants.entry(merchant_key(&transaction.tx)).or_default() +=
+            forecast::spend(transaction);
+    }
+    let mut merchants: Vec<_> = merchants
+        .into_iter()
+        .filter(|(merchant, spend)| !merchant.is_empty() && *spend > 0.0)
+        .collect();
+    merchants.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut largest: Vec<_> = of(month).filter(|t| t.amount < 0.0).collect();
+    largest.sort_by(|a, b| a.amount.total_cmp(&b.amount));
+
+    let title = format!("Card spend for {}", month.format("%B %Y"));
+    let mut context = tera::Context::new();
+    context.insert("title", &title);
+    context.insert("total", &locale.amount(total));
+    context.insert("rows", &rows_in_month);
+    context.insert("previous_month", &before.format("%B").to_string());
+    context.insert(
+        "previous",
+        &(!previous.is_empty())
+            .then(|| locale.amount(previous.values().fold(0.0, |total, spend| total + spend))),
+    );
+    context.insert(
+        "categories",
+        &categories
+            .iter()
+            .map(|(category, spend, n)| Line {
+                name: category.clone(),
+                spend: locale.amount(*spend),
+                previous: previous
+                    .get(category)
+                    .map(|spend| locale.amount(*spend))
+                    .unwrap_or_default(),
+                rows: *n,
+            })
+            .collect::<Vec<_>>(),
+    );
+    context.insert(
+        "merchants",
+        &merchants
+            .iter()
+            .take(TOP)
+            .map(|(merchant, spend)| Merchant {
+                name: merchant.clone(),
+                spend: locale.amount(*spend),
+            })
+            .collect::<Vec<_>>(),
+    );
+    context.insert(
+        "largest",
+        &largest
+            .iter()
+            .take(TOP)
+            .map(|t| Debit {
+                date: t.date.format("%Y-%m-%d").to_string(),
+                description: t.tx.clone(),
+                amount: locale.amount(-t.amount),
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    let html = tera::Tera::one_off(TEMPLATE, &context, true).context("failed to render report")?;
+    Ok((title, html))
+}
+
+pub use mailer::Smtp;
+
+#[cfg(feature = "email")]
+mod mailer {
+    use crate::password;
+    use anyhow::{anyhow, Context, Error};
+    use lettre::message::header::ContentType;
+    use lettre::transport::smtp::authentication::Credentials;
+    use lettre::{Message, SmtpTransport, Transport};
+    use serde::Deserialize;
+    use std::fs;
+
+    // STARTTLS by default, implicit TLS on SMTPS_PORT.
+    const PORT: u16 = 587;
+    const SMTPS_PORT: u16 = 465;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    pub struct Smtp {
+        host: String,
+        port: Option<u16>,
+        username: Option<String>,
+        password: Option<String>,
+        from: String,
+        to: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct ConfigFile {
+        smtp: Option<Smtp>,
+    }
+
+    impl Smtp {
+        // The [smtp] table of the --config file at `path`.
+        pub fn load(path: &str) -> Result<Self, Error> {
+            let data = fs::read_to_string(path).context(format!("failed to read {}", path))?;
+            let file: ConfigFile =
+                toml::from_str(&data).context(format!("failed to parse {}", path))?;
+            let smtp = file
+                .smtp
+                .ok_or_else(|| anyhow!("{}: --email needs an [smtp] table", path))?;
+            if smtp.to.is_empty() {
+                return Err(anyhow!("{}: [smtp] needs at least one address in to", path));
+            }
+            Ok(smtp)
+        }
+
+        pub fn send(&self, subject: &str, html: &str) -> Result<(), Error> {
+            let mut message = Message::builder()
+                .from(
+                    self.from
+                        .parse()
+                        .context(format!("invalid from address {}", self.from))?,
+                )
+                .subject(subject)
+                .header(ContentType::TEXT_HTML);
+            for to in &self.to {
+                message = message.to(to.parse().context(format!("invalid to address {}", to))?);
+            }
+            let message = message
+                .body(html.to_owned())
+                .context("failed to build the email")?;
+
+            let port = self.port.unwrap_or(PORT);
+            let mut transport = if port == SMTPS_PORT {
+                SmtpTransport::relay(&self.host)
+            } else {
+                SmtpTransport::starttls_relay(&self.host)
+            }
+            .context(format!("failed to set up SMTP to {}", self.host))?
+            .port(port);
+            if let Some(username) = &self.username {
+                let password = match &self.password {
+                    Some(password) => password::resolve(password)?,
+                    None => String::new(),
+                };
+                transport = transport.credentials(Credentials::new(username.clone(), password));
+            }
+            transport
+                .build()
+                .send(&message)
+                .context(format!("failed to send the report through {}", self.host))?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(feature = "email"))]
+mod mailer {
+    use anyhow::{anyhow, Error};
+
+    pub struct Smtp;
+
+    impl Smtp {
+        pub fn load(_path: &str) -> Result<Self, Error> {
+            Err(anyhow!(
+                "report --email needs a build with the email feature (cargo build --features email)"
+            ))
+        }
+
+        pub fn send(&self, _subject: &str, _html: &str) -> Result<(), Error> {
+            unreachable!("Smtp::load always fails without the email feature")
+        }
+    }
+}
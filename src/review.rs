@@ -0,0 +1,217 @@
+use crate::categories::{merchant_key, Categories};
+use crate::Transaction;
+use anyhow::{Context, Error};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+
+const HELP: &str = "j/k: move  n: next uncategorized  enter: categorize  w: save rules  q: quit";
+
+enum Mode {
+    Normal,
+    // category being typed for the selected row.
+    Editing(String),
+}
+
+struct Review {
+    transactions: Vec<Transaction>,
+    categories: Categories,
+    path: String,
+    state: TableState,
+    mode: Mode,
+    status: String,
+    // unsaved rules?
+    dirty: bool,
+    confirm_quit: bool,
+}
+
+// Open the review TUI over the parsed transactions. New rules learned while
+// categorizing are written back to the categories file at `path`.
+pub fn run(
+    transactions: Vec<Transaction>,
+    categories: Categories,
+    path: &str,
+) -> Result<(), Error> {
+    let mut review = Review {
+        transactions,
+        categories,
+        path: path.to_owned(),
+        state: TableState::default(),
+        mode: Mode::Normal,
+        status: HELP.to_owned(),
+        dirty: false,
+        confirm_quit: false,
+    };
+    if !review.transactions.is_empty() {
+        review.state.select(Some(0));
+    }
+
+    let mut terminal = ratatui::init();
+    let result = review.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+impl Review {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Error> {
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .context("failed to draw review screen")?;
+
+            let key = match event::read().context("failed to read terminal event")? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+
+            match &mut self.mode {
+                Mode::Normal => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => {
+                        // quit on the second press if there are unsaved rules.
+                        if !self.dirty || self.confirm_quit {
+                            return Ok(());
+                        }
+                        self.confirm_quit = true;
+                        self.status = "unsaved rules, press q again to discard".to_owned();
+                    }
+                    KeyCode::Char('j') | KeyCode::Down => self.move_by(1),
+                    KeyCode::Char('k') | KeyCode::Up => self.move_by(-1),
+                    KeyCode::PageDown => self.move_by(20),
+                    KeyCode::PageUp => self.move_by(-20),
+                    KeyCode::Char('g') | KeyCode::Home => self.move_by(isize::MIN),
+                    KeyCode::Char('G') | KeyCode::End => self.move_by(isize::MAX),
+                    KeyCode::Char('n') => self.next_uncategorized(),
+                    KeyCode::Enter | KeyCode::Char('c') => match self.selected() {
+                        Some(tx) if self.categories.categorize(tx).is_none() => {
+                            self.mode = Mode::Editing(String::new())
+                        }
+                        Some(_) => self.status = "row is already categorized".to_owned(),
+                        None => {}
+                    },
+                    KeyCode::Char('w') => {
+                        self.categories.save(&self.path)?;
+                        self.dirty = false;
+                        self.status = format!("saved rules to {}", self.path);
+                    }
+                    _ => {}
+                },
+
+                Mode::Editing(input) => match key.code {
+                    KeyCode::Char(c) => input.push(c),
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Esc => {
+                        self.mode = Mode::Normal;
+                        self.status = HELP.to_owned();
+                    }
+                    KeyCode::Enter => {
+                        let category = input.trim().to_owned();
+                        self.mode = Mode::Normal;
+                        self.assign(&category);
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+
+    fn selected(&self) -> Option<&Transaction> {
+        self.state.selected().and_then(|i| self.transactions.get(i))
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.transactions.is_empty() {
+            return;
+        }
+        let last = self.transactions.len() - 1;
+        let current = self.state.selected().unwrap_or(0);
+        let next = current.saturating_add_signed(delta).min(last);
+        self.state.select(Some(next));
+    }
+
+    fn next_uncategorized(&mut self) {
+        let start = self.state.selected().map_or(0, |i| i + 1);
+        let found = (start..self.transactions.len())
+            .chain(0..start)
+            .find(|&i| self.categories.categorize(&self.transactions[i]).is_none());
+
+        match found {
+            Some(i) => self.state.select(Some(i)),
+            None => self.status = "all rows are categorized".to_owned(),
+        }
+    }
+
+    // Learn a rule for the selected row's merchant. Every other row of the
+    // same merchant picks up the category as well.
+    fn assign(&mut self, category: &str) {
+        let pattern = match self.selected() {
+            Some(tx) => merchant_key(&tx.tx),
+            None => return,
+        };
+        if category.is_empty() || pattern.is_empty() {
+            self.status = HELP.to_owned();
+            return;
+        }
+
+        self.categories.learn(category, &pattern);
+        self.dirty = true;
+        self.confirm_quit = false;
+        self.status = format!("{} -> {} (w to save)", pattern, category);
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [table_area, status_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+
+        let rows = self.transactions.iter().map(|tx| {
+            let category = match self.categories.categorize(tx) {
+                Some(c) => Cell::from(c.to_owned()),
+                None => Cell::from("-").style(Style::default().fg(Color::Yellow)),
+            };
+            Row::new(vec![
+                Cell::from(tx.date.to_string()),
+                Cell::from(tx.tx.clone()),
+                Cell::from(format!("{:.2}", tx.amount)),
+                category,
+            ])
+        });
+
+        let uncategorized = self
+            .transactions
+            .iter()
+            .filter(|tx| self.categories.categorize(tx).is_none())
+            .count();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(19),
+                Constraint::Fill(1),
+                Constraint::Length(12),
+                Constraint::Length(16),
+            ],
+        )
+        .header(
+            Row::new(vec!["Date", "Description", "Amount", "Category"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            " {} transactions, {} uncategorized ",
+            self.transactions.len(),
+            uncategorized
+        )))
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        frame.render_stateful_widget(table, table_area, &mut self.state);
+
+        let status = match &self.mode {
+            Mode::Normal => Paragraph::new(self.status.as_str()),
+            Mode::Editing(input) => Paragraph::new(format!("category: {}", input))
+                .style(Style::default().fg(Color::Cyan)),
+        };
+        frame.render_widget(status, status_area);
+    }
+}
@@ -0,0 +1,141 @@
+// s3://bucket/key input and output, available with the `s3` feature.
+// Credentials and region come from the usual AWS environment/config files.
+use anyhow::{anyhow, Error};
+
+pub fn is_s3(path: &str) -> bool {
+    path.starts_with("s3://")
+}
+
+// Split s3://bucket/key into (bucket, key).
+fn split(url: &str) -> Result<(&str, &str), Error> {
+    let rest = url
+        .strip_prefix("s3://")
+        .ok_or_else(|| anyhow!("not an s3:// url: {}", url))?;
+    match rest.split_once('/') {
+        Some((bucket, key)) if !bucket.is_empty() => Ok((bucket, key)),
+        None if !rest.is_empty() => Ok((rest, "")),
+        _ => Err(anyhow!("missing bucket in {}", url)),
+    }
+}
+
+#[cfg(feature = "s3")]
+mod client {
+    use anyhow::{Context, Error};
+    use aws_sdk_s3::primitives::ByteStream;
+    use aws_sdk_s3::Client;
+    use tokio::runtime::Runtime;
+
+    pub fn connect() -> Result<(Runtime, Client), Error> {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("failed to start s3 runtime")?;
+        let config = rt.block_on(aws_config::load_defaults(
+            aws_config::BehaviorVersion::latest(),
+        ));
+        Ok((rt, Client::new(&config)))
+    }
+
+    pub fn list(bucket: &str, prefix: &str) -> Result<Vec<String>, Error> {
+        let (rt, client) = connect()?;
+        rt.block_on(async {
+            let mut keys = Vec::new();
+            let mut token = None;
+            loop {
+                let resp = client
+                    .list_objects_v2()
+                    .bucket(bucket)
+                    .prefix(prefix)
+                    .set_continuation_token(token)
+                    .send()
+                    .await
+                    .context(format!("failed to list s3://{}/{}", bucket, prefix))?;
+                keys.extend(
+                    resp.contents()
+                        .iter()
+                        .filter_map(|object| object.key().map(str::to_owned)),
+                );
+                match resp.next_continuation_token() {
+                    Some(next) if resp.is_truncated() == Some(true) => {
+                        token = Some(next.to_owned())
+                    }
+                    _ => return Ok(keys),
+                }
+            }
+        })
+    }
+
+    pub fn get(bucket: &str, key: &str) -> Result<Vec<u8>, Error> {
+        let (rt, client) = connect()?;
+        rt.block_on(async {
+            let object = client
+                .get_object()
+                .bucket(bucket)
+                .key(key)
+                .send()
+                .await
+                .context(format!("failed to get s3://{}/{}", bucket, key))?;
+            let data = object
+                .body
+                .collect()
+                .await
+                .context(format!("failed to read s3://{}/{}", bucket, key))?;
+            Ok(data.into_bytes().to_vec())
+        })
+    }
+
+    pub fn put(bucket: &str, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        let (rt, client) = connect()?;
+        rt.block_on(async {
+            client
+                .put_object()
+                .bucket(bucket)
+                .key(key)
+                .body(ByteStream::from(data))
+                .send()
+                .await
+                .context(format!("failed to put s3://{}/{}", bucket, key))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(not(feature = "s3"))]
+mod client {
+    use anyhow::{anyhow, Error};
+
+    fn unsupported() -> Error {
+        anyhow!("s3:// paths need a build with the s3 feature (cargo build --features s3)")
+    }
+
+    pub fn list(_bucket: &str, _prefix: &str) -> Result<Vec<String>, Error> {
+        Err(unsupported())
+    }
+
+    pub fn get(_bucket: &str, _key: &str) -> Result<Vec<u8>, Error> {
+        Err(unsupported())
+    }
+
+    pub fn put(_bucket: &str, _key: &str, _data: Vec<u8>) -> Result<(), Error> {
+        Err(unsupported())
+    }
+}
+
+// s3:// urls of the objects under a prefix.
+pub fn list(url: &str) -> Result<Vec<String>, Error> {
+    let (bucket, prefix) = split(url)?;
+    Ok(client::list(bucket, prefix)?
+        .into_iter()
+        .map(|key| format!("s3://{}/{}", bucket, key))
+        .collect())
+}
+
+pub fn get(url: &str) -> Result<Vec<u8>, Error> {
+    let (bucket, key) = split(url)?;
+    client::get(bucket, key)
+}
+
+pub fn put(url: &str, data: Vec<u8>) -> Result<(), Error> {
+    let (bucket, key) = split(url)?;
+    client::put(bucket, key, data)
+}
@@ -0,0 +1,93 @@
+// `selftest`: parse the fixture statements of a directory (synthetic ones,
+// see `gen-fixture`) and compare their rows with the expected CSV next to
+// each, so a build can be checked end to end without real statements.
+use crate::diff;
+use crate::output::{Csv, Options, Output};
+use crate::{parse, ParseOptions, Transaction};
+use anyhow::{anyhow, Context, Error};
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+
+// Check every fixture, printing a line per fixture and the differing
+// fields of those that don't match. Returns whether all of them did.
+// `capacity` rows are buffered between the parser and the check.
+pub fn run(dir: &str, name: &str, passwords: &[String], capacity: usize) -> Result<bool, Error> {
+    let mut pdfs: Vec<PathBuf> = fs::read_dir(dir)
+        .context(format!("failed to read {}", dir))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pdf"))
+        .collect();
+    if pdfs.is_empty() {
+        return Err(anyhow!("no fixture statements in {}", dir));
+    }
+    pdfs.sort();
+
+    let mut passed = true;
+    for pdf in &pdfs {
+        let file = pdf.to_string_lossy().to_string();
+        let expected_path = pdf.with_extension("csv");
+        let expected = File::open(&expected_path)
+            .context(format!("failed to open {}", expected_path.display()))?;
+        let expected = diff::read_rows(expected, &expected_path.to_string_lossy())?;
+
+        let rows = match parse_rows(&file, name, passwords, capacity) {
+            Ok(rows) => rows,
+            Err(e) => {
+                println!("{}: {:#}", file, e);
+                passed = false;
+                continue;
+            }
+        };
+
+        // through the CSV writer, as a run would write them.
+        let mut csv = Csv::new(
+            Vec::new(),
+            Options {
+                add_headers: true,
+                ..Default::default()
+            },
+        )?;
+        for row in &rows {
+            csv.write(row)?;
+        }
+        csv.finish()?;
+        let actual = diff::read_rows(csv.into_inner()?.as_slice(), &file)?;
+
+        let diff = diff::diff(&expected, &actual);
+        if diff.is_empty() {
+            println!("{}: ok, {} rows", file, rows.len());
+        } else {
+            diff.print(&format!("{}: ", file));
+            passed = false;
+        }
+    }
+    Ok(passed)
+}
+
+fn parse_rows(
+    file: &str,
+    name: &str,
+    passwords: &[String],
+    capacity: usize,
+) -> Result<Vec<Transaction>, Error> {
+    let data = fs::read(file).context(format!("failed to read {}", file))?;
+    let (tx, rx) = mpsc::sync_channel(capacity);
+    let collector = thread::spawn(move || rx.into_iter().collect::<Vec<Transaction>>());
+    let parsed = parse(
+        file.to_owned(),
+        data,
+        name.to_owned(),
+        passwords,
+        ParseOptions::default(),
+        &tx,
+    );
+    drop(tx);
+    let rows = collector
+        .join()
+        .map_err(|e| anyhow!("Thread panicked: {:?}", e))?;
+    parsed?;
+    Ok(rows)
+}
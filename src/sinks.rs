@@ -0,0 +1,75 @@
+use crate::output::Output;
+use anyhow::{anyhow, Error};
+
+mod firefly;
+mod gsheets;
+mod kafka;
+mod notion;
+mod splitwise;
+mod webhook;
+
+// Sinks selectable with --sink, pushing transactions somewhere other than
+// stdout.
+pub const SINKS: [&str; 6] = [
+    "firefly",
+    "gsheets",
+    "kafka",
+    "notion",
+    "splitwise",
+    "webhook",
+];
+
+// Sink settings from the command line.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub url: Option<String>,
+    pub token: Option<String>,
+    // HMAC key for signing webhook payloads.
+    pub secret: Option<String>,
+    // transactions per request, for sinks that batch.
+    pub batch_size: usize,
+    // account the transactions are booked against, where the sink needs one.
+    pub account: String,
+    // Google service account key file, spreadsheet ID and worksheet name.
+    pub credentials: Option<String>,
+    pub spreadsheet: Option<String>,
+    pub worksheet: String,
+    // Kafka bootstrap servers, host:port[,host:port...], and topic.
+    pub brokers: Option<String>,
+    pub topic: String,
+    // Confluent schema registry, for Avro Kafka messages.
+    pub schema_registry: Option<String>,
+    // Notion database ID.
+    pub database: Option<String>,
+    // Splitwise group ID.
+    pub group: Option<String>,
+    // --utc: timestamps in UTC instead of IST.
+    pub utc: bool,
+}
+
+impl Config {
+    fn url(&self, sink: &str) -> Result<String, Error> {
+        self.url
+            .as_ref()
+            .map(|url| url.trim_end_matches('/').to_owned())
+            .ok_or_else(|| anyhow!("--url is required for the {} sink", sink))
+    }
+
+    fn token(&self, sink: &str) -> Result<String, Error> {
+        self.token
+            .clone()
+            .ok_or_else(|| anyhow!("--token is required for the {} sink", sink))
+    }
+}
+
+pub fn new(sink: &str, config: &Config) -> Result<Box<dyn Output>, Error> {
+    match sink {
+        "firefly" => Ok(Box::new(firefly::Firefly::new(config)?)),
+        "gsheets" => Ok(Box::new(gsheets::GSheets::new(config)?)),
+        "kafka" => Ok(Box::new(kafka::Kafka::new(config)?)),
+        "notion" => Ok(Box::new(notion::Notion::new(config)?)),
+        "splitwise" => Ok(Box::new(splitwise::Splitwise::new(config)?)),
+        "webhook" => Ok(Box::new(webhook::Webhook::new(config)?)),
+        _ => Err(anyhow!("unknown sink {}", sink)),
+    }
+}
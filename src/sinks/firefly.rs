@@ -0,0 +1,118 @@
+use super::Config;
+use crate::output::Output;
+use crate::Transaction;
+use anyhow::{Context, Error};
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+
+// Pushes transactions to a Firefly III instance through its REST API. The
+// transaction ID is stored as external_id, and rows already present with
+// that external_id are skipped, so re-runs don't create duplicates. So are
+// rows Firefly III turns down as duplicates of one it already has.
+pub struct Firefly {
+    client: Client,
+    url: String,
+    token: String,
+    account: String,
+    created: usize,
+    skipped: usize,
+    utc: bool,
+}
+
+impl Firefly {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        Ok(Firefly {
+            client: Client::new(),
+            url: config.url("firefly")?,
+            token: config.token("firefly")?,
+            account: config.account.clone(),
+            created: 0,
+            skipped: 0,
+            utc: config.utc,
+        })
+    }
+
+    fn exists(&self, id: &str) -> Result<bool, Error> {
+        let found: Value = self
+            .client
+            .get(format!("{}/api/v1/search/transactions", self.url))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.api+json")
+            .query(&[("query", format!("external_id_is:\"{}\"", id))])
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .context("failed to search Firefly III transactions")?
+            .json()
+            .context("failed to read Firefly III search response")?;
+
+        Ok(found["data"]
+            .as_array()
+            .is_some_and(|data| !data.is_empty()))
+    }
+}
+
+impl Output for Firefly {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let id = transaction.id();
+        if self.exists(&id)? {
+            self.skipped += 1;
+            return Ok(());
+        }
+
+        // debits are withdrawals from the card account to the merchant,
+        // credits deposits from the merchant into it.
+        let (kind, source, destination) = if transaction.amount < 0.0 {
+            ("withdrawal", self.account.as_str(), transaction.tx.as_str())
+        } else {
+            ("deposit", transaction.tx.as_str(), self.account.as_str())
+        };
+
+        let body = json!({
+            "error_if_duplicate_hash": true,
+            "transactions": [{
+                "type": kind,
+                "date": transaction.timestamp(self.utc),
+                "amount": format!("{:.2}", transaction.amount.abs()),
+                "description": transaction.tx,
+                "source_name": source,
+                "destination_name": destination,
+                "category_name": transaction.category,
+                "tags": transaction.tags,
+                "external_id": id,
+            }],
+        });
+
+        let context = || format!("failed to create Firefly III transaction {}", id);
+        let resp = self
+            .client
+            .post(format!("{}/api/v1/transactions", self.url))
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.api+json")
+            .json(&body)
+            .send()
+            .with_context(context)?;
+        // rows pushed before external_id was stored, or by hand, aren't found
+        // by the search but still trip the duplicate hash check.
+        if resp.status() == StatusCode::UNPROCESSABLE_ENTITY {
+            let text = resp.text().with_context(context)?;
+            if text.contains("Duplicate of transaction") {
+                self.skipped += 1;
+                return Ok(());
+            }
+            return Err(Error::msg(text)).with_context(context);
+        }
+        resp.error_for_status().with_context(context)?;
+
+        self.created += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        eprintln!(
+            "firefly: {} transactions created, {} already present",
+            self.created, self.skipped
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+use super::Config;
+use crate::output::Output;
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use chrono::{NaiveDate, NaiveDateTime};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use reqwest::blocking::Client;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+
+// A date as Sheets stores it: days since 1899-12-30, the time of day a
+// fraction of one.
+fn serial(date: NaiveDateTime) -> f64 {
+    let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+    (date - epoch).num_seconds() as f64 / 86400.0
+}
+
+// The rows of an append's updated range, eg. "'Sheet1'!A10:G12", zero based
+// and the last excluded: (9, 12).
+fn appended_rows(range: &str) -> Option<(u64, u64)> {
+    let cells = range.rsplit_once('!')?.1;
+    let (first, last) = cells.split_once(':').unwrap_or((cells, cells));
+    let row = |cell: &str| {
+        cell.trim_start_matches(|c: char| c.is_ascii_alphabetic())
+            .parse::<u64>()
+            .ok()
+    };
+    Some((row(first)?.checked_sub(1)?, row(last)?))
+}
+
+// The fields used from a Google service account key file.
+#[derive(Debug, Deserialize)]
+struct ServiceAccount {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Claims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+}
+
+// Appends transactions as rows to a worksheet of a Google Sheet, through the
+// Sheets API with a service account. The sheet has to be shared with the
+// service account's email. Rows are sent in one request on finish, those
+// whose ID is already in the sheet skipped, and their dates formatted.
+pub struct GSheets {
+    client: Client,
+    account: ServiceAccount,
+    spreadsheet: String,
+    worksheet: String,
+    rows: Vec<Value>,
+}
+
+impl GSheets {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        let credentials = config
+            .credentials
+            .as_ref()
+            .ok_or_else(|| anyhow!("--credentials is required for the gsheets sink"))?;
+        let data = fs::read_to_string(credentials).context(format!(
+            "failed to read service account file {}",
+            credentials
+        ))?;
+        let account = serde_json::from_str(&data).context(format!(
+            "failed to parse service account file {}",
+            credentials
+        ))?;
+
+        Ok(GSheets {
+            client: Client::new(),
+            account,
+            spreadsheet: config
+                .spreadsheet
+                .clone()
+                .ok_or_else(|| anyhow!("--spreadsheet is required for the gsheets sink"))?,
+            worksheet: config.worksheet.clone(),
+            rows: Vec::new(),
+        })
+    }
+
+    // Exchange a signed JWT for an OAuth access token.
+    fn access_token(&self) -> Result<String, Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("system clock is before 1970")?
+            .as_secs();
+        let claims = Claims {
+            iss: &self.account.client_email,
+            scope: SCOPE,
+            aud: &self.account.token_uri,
+            iat: now,
+            exp: now + 3600,
+        };
+        let key = EncodingKey::from_rsa_pem(self.account.private_key.as_bytes())
+            .context("invalid service account private key")?;
+        let jwt = jsonwebtoken::encode(&Header::new(Algorithm::RS256), &claims, &key)
+            .context("failed to sign service account token")?;
+
+        let resp: Value = self
+            .client
+            .post(&self.account.token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", jwt.as_str()),
+            ])
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .context("failed to get Google access token")?
+            .json()
+            .context("failed to read Google token response")?;
+
+        resp["access_token"]
+            .as_str()
+            .map(str::to_owned)
+            .ok_or_else(|| anyhow!("no access_token in Google token response"))
+    }
+
+    // The spreadsheet's API url, its ID followed by `method` (eg.
+    // ":batchUpdate") and then `segments`.
+    fn url(&self, method: &str, segments: &[&str]) -> Result<Url, Error> {
+        let mut url = Url::parse("https://sheets.googleapis.com/v4/spreadsheets")
+            .context("invalid Sheets API url")?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow!("invalid Sheets API url"))?
+            .push(&format!("{}{}", self.spreadsheet, method))
+            .extend(segments);
+        Ok(url)
+    }
+
+    // `cells` of the worksheet in A1 notation.
+    fn range(&self, cells: &str) -> String {
+        format!("'{}'!{}", self.worksheet.replace('\'', "''"), cells)
+    }
+
+    // Give column A of rows `first` to `last` (zero based, `last` excluded)
+    // a date-time number format. The worksheet's looked up by its title
+    // for its ID, which is what formatting requests take.
+    fn format_dates(&self, token: &str, first: u64, last: u64) -> Result<(), Error> {
+        let mut url = self.url("", &[])?;
+        url.query_pairs_mut()
+            .append_pair("fields", "sheets.properties(sheetId,title)");
+        let spreadsheet: Value = self
+            .client
+            .get(url)
+            .bearer_auth(token)
+            .send()
+            .and_then(|resp| resp.error_for_status())?
+            .json()?;
+        let sheet = spreadsheet["sheets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|sheet| &sheet["properties"])
+            .find(|properties| properties["title"] == self.worksheet.as_str())
+            .and_then(|properties| properties["sheetId"].as_u64())
+            .ok_or_else(|| anyhow!("no worksheet {}", self.worksheet))?;
+
+        self.client
+            .post(self.url(":batchUpdate", &[])?)
+            .bearer_auth(token)
+            .json(&json!({
+                "requests": [{
+                    "repeatCell": {
+                        "range": {
+                            "sheetId": sheet,
+                            "startRowIndex": first,
+                            "endRowIndex": last,
+                            "startColumnIndex": 0,
+                            "endColumnIndex": 1,
+                        },
+                        "cell": {
+                            "userEnteredFormat": {
+                                "numberFormat": {
+                                    "type": "DATE_TIME",
+                                    "pattern": "yyyy-mm-dd hh:mm",
+                                },
+                            },
+                        },
+                        "fields": "userEnteredFormat.numberFormat",
+                    },
+                }],
+            }))
+            .send()
+            .and_then(|resp| resp.error_for_status())?;
+        Ok(())
+    }
+}
+
+impl Output for GSheets {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        self.rows.push(json!([
+            serial(transaction.date),
+            transaction.tx,
+            transaction.points,
+            transaction.amount,
+            transaction.category.clone().unwrap_or_default(),
+            transaction.tags.join(";"),
+            transaction.id(),
+        ]));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let token = self.access_token()?;
+
+        // rows already in the sheet, by their ID in column G, are skipped so
+        // re-runs don't append them again.
+        let found: Value = self
+            .client
+            .get(self.url("", &["values", &self.range("G:G")])?)
+            .bearer_auth(&token)
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .context("failed to read the Google Sheet's IDs")?
+            .json()
+            .context("failed to read Google Sheet values")?;
+        let present: HashSet<&str> = found["values"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|row| row[0].as_str())
+            .collect();
+        let rows: Vec<&Value> = self
+            .rows
+            .iter()
+            .filter(|row| !row[6].as_str().is_some_and(|id| present.contains(id)))
+            .collect();
+        let skipped = self.rows.len() - rows.len();
+        if rows.is_empty() {
+            eprintln!("gsheets: {} rows already present", skipped);
+            return Ok(());
+        }
+
+        let mut url = self.url("", &["values", &format!("{}:append", self.range("A1"))])?;
+        // RAW, as a description starting with "=" would be a formula
+        // otherwise.
+        url.query_pairs_mut()
+            .append_pair("valueInputOption", "RAW")
+            .append_pair("insertDataOption", "INSERT_ROWS");
+        let appended: Value = self
+            .client
+            .post(url)
+            .bearer_auth(&token)
+            .json(&json!({ "values": rows }))
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .context("failed to append rows to Google Sheet")?
+            .json()
+            .context("failed to read Google Sheet append response")?;
+
+        // the dates are serial numbers, shown as dates only once formatted.
+        let updated = appended["updates"]["updatedRange"].as_str().unwrap_or("");
+        if let Some((first, last)) = appended_rows(updated) {
+            self.format_dates(&token, first, last)
+                .context("failed to format the appended dates")?;
+        }
+
+        eprintln!(
+            "gsheets: appended {} rows to {}, {} already present",
+            rows.len(),
+            self.worksheet,
+            skipped
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+use super::Config;
+use crate::output::{avro, Output};
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+
+// Publishes each transaction as a JSON message to a Kafka topic, keyed by its
+// ID so a compacted topic keeps one message per transaction. With
+// --schema-registry the messages are Avro instead, in the schema of
+// --format avro registered as <topic>-value, framed the way Confluent's
+// deserializers expect. Needs a build with the kafka feature.
+pub struct Kafka {
+    producer: client::Producer,
+    topic: String,
+    // ID of the registered Avro schema, with --schema-registry.
+    schema: Option<u32>,
+    utc: bool,
+}
+
+impl Kafka {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        let brokers = config
+            .brokers
+            .as_ref()
+            .ok_or_else(|| anyhow!("--brokers is required for the kafka sink"))?;
+        let producer = client::Producer::new(brokers)?;
+        let schema = config
+            .schema_registry
+            .as_ref()
+            .map(|registry| avro::register(registry, &format!("{}-value", config.topic)))
+            .transpose()?;
+        Ok(Kafka {
+            producer,
+            topic: config.topic.clone(),
+            schema,
+            utc: config.utc,
+        })
+    }
+}
+
+impl Output for Kafka {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let payload = match self.schema {
+            // a zero magic byte and the schema ID, big-endian, then the row.
+            Some(id) => {
+                let mut payload = vec![0];
+                payload.extend_from_slice(&id.to_be_bytes());
+                avro::encode(&mut payload, transaction);
+                payload
+            }
+            None => serde_json::to_vec(&transaction.to_json(self.utc))
+                .context("failed to serialize transaction")?,
+        };
+        self.producer
+            .send(&self.topic, &transaction.id(), &payload)
+            .context(format!("failed to publish to {}", self.topic))
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.producer
+            .flush()
+            .context(format!("failed to publish to {}", self.topic))
+    }
+}
+
+#[cfg(feature = "kafka")]
+mod client {
+    use anyhow::{anyhow, Error};
+    use rdkafka::config::ClientConfig;
+    use rdkafka::error::{KafkaError, RDKafkaErrorCode};
+    use rdkafka::producer::{
+        BaseProducer, BaseRecord, DeliveryResult, Producer as _, ProducerContext,
+    };
+    use rdkafka::ClientContext;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    // how long to wait for the messages still queued at the end.
+    const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+    // Keeps the first delivery failure, reported on the next send or flush.
+    #[derive(Default)]
+    struct Deliveries {
+        failure: Mutex<Option<KafkaError>>,
+    }
+
+    impl ClientContext for Deliveries {}
+
+    impl ProducerContext for Deliveries {
+        type DeliveryOpaque = ();
+
+        fn delivery(&self, result: &DeliveryResult<'_>, _: ()) {
+            if let Err((e, _)) = result {
+                self.failure.lock().unwrap().get_or_insert(e.clone());
+            }
+        }
+    }
+
+    pub struct Producer(BaseProducer<Deliveries>);
+
+    impl Producer {
+        pub fn new(brokers: &str) -> Result<Self, Error> {
+            let producer = ClientConfig::new()
+                .set("bootstrap.servers", brokers)
+                .create_with_context(Deliveries::default())?;
+            Ok(Producer(producer))
+        }
+
+        fn failure(&self) -> Result<(), Error> {
+            match self.0.context().failure.lock().unwrap().take() {
+                Some(e) => Err(anyhow!(e)),
+                None => Ok(()),
+            }
+        }
+
+        pub fn send(&self, topic: &str, key: &str, payload: &[u8]) -> Result<(), Error> {
+            let mut record = BaseRecord::to(topic).key(key).payload(payload);
+            // a full queue waits for deliveries to make room.
+            loop {
+                match self.0.send(record) {
+                    Ok(()) => break,
+                    Err((KafkaError::MessageProduction(RDKafkaErrorCode::QueueFull), r)) => {
+                        record = r;
+                        self.0.poll(Duration::from_millis(100));
+                    }
+                    Err((e, _)) => return Err(e.into()),
+                }
+            }
+            self.0.poll(Duration::ZERO);
+            self.failure()
+        }
+
+        pub fn flush(&self) -> Result<(), Error> {
+            self.0.flush(FLUSH_TIMEOUT)?;
+            self.failure()
+        }
+    }
+}
+
+#[cfg(not(feature = "kafka"))]
+mod client {
+    use anyhow::{anyhow, Error};
+
+    fn unsupported() -> Error {
+        anyhow!(
+            "the kafka sink needs a build with the kafka feature (cargo build --features kafka)"
+        )
+    }
+
+    pub struct Producer;
+
+    impl Producer {
+        pub fn new(_brokers: &str) -> Result<Self, Error> {
+            Err(unsupported())
+        }
+
+        pub fn send(&self, _topic: &str, _key: &str, _payload: &[u8]) -> Result<(), Error> {
+            Err(unsupported())
+        }
+
+        pub fn flush(&self) -> Result<(), Error> {
+            Err(unsupported())
+        }
+    }
+}
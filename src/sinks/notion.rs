@@ -0,0 +1,117 @@
+use super::Config;
+use crate::categories::merchant_key;
+use crate::output::Output;
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use reqwest::blocking::{Client, RequestBuilder};
+use serde_json::{json, Value};
+
+const API: &str = "https://api.notion.com/v1";
+const VERSION: &str = "2022-06-28";
+
+// Creates a page per transaction in a Notion database, through an
+// integration's token. The database needs the properties Name (title), Date
+// (date), Merchant (text), Amount (number), Category (select) and ID (text);
+// a page with the transaction's ID is updated instead, so re-runs don't
+// create duplicates and pick up recategorized rows.
+pub struct Notion {
+    client: Client,
+    token: String,
+    database: String,
+    created: usize,
+    updated: usize,
+    utc: bool,
+}
+
+impl Notion {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        Ok(Notion {
+            client: Client::new(),
+            token: config.token("notion")?,
+            database: config
+                .database
+                .clone()
+                .ok_or_else(|| anyhow!("--database is required for the notion sink"))?,
+            created: 0,
+            updated: 0,
+            utc: config.utc,
+        })
+    }
+
+    fn request(&self, request: RequestBuilder, body: &Value) -> Result<Value, Error> {
+        request
+            .bearer_auth(&self.token)
+            .header("Notion-Version", VERSION)
+            .json(body)
+            .send()
+            .and_then(|resp| resp.error_for_status())?
+            .json()
+            .context("failed to read Notion response")
+    }
+
+    // The page of the transaction with `id`, if there's one.
+    fn find(&self, id: &str) -> Result<Option<String>, Error> {
+        let found = self
+            .request(
+                self.client
+                    .post(format!("{}/databases/{}/query", API, self.database)),
+                &json!({
+                    "filter": { "property": "ID", "rich_text": { "equals": id } },
+                    "page_size": 1,
+                }),
+            )
+            .context("failed to query the Notion database")?;
+        Ok(found["results"][0]["id"].as_str().map(str::to_owned))
+    }
+}
+
+fn text(content: &str) -> Value {
+    json!([{ "text": { "content": content } }])
+}
+
+impl Output for Notion {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let id = transaction.id();
+        let properties = json!({
+            "Name": { "title": text(&transaction.tx) },
+            "Date": { "date": { "start": transaction.timestamp(self.utc) } },
+            "Merchant": { "rich_text": text(&merchant_key(&transaction.tx)) },
+            "Amount": { "number": transaction.amount },
+            "Category": {
+                "select": transaction.category.as_ref().map(|name| json!({ "name": name })),
+            },
+            "ID": { "rich_text": text(&id) },
+        });
+
+        match self.find(&id)? {
+            Some(page) => {
+                self.request(
+                    self.client.patch(format!("{}/pages/{}", API, page)),
+                    &json!({ "properties": properties }),
+                )
+                .context(format!("failed to update Notion page for {}", id))?;
+                self.updated += 1;
+            }
+            None => {
+                self.request(
+                    self.client.post(format!("{}/pages", API)),
+                    &json!({
+                        "parent": { "database_id": self.database },
+                        "properties": properties,
+                    }),
+                )
+                .context(format!("failed to create Notion page for {}", id))?;
+                self.created += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        eprintln!(
+            "notion: {} pages created, {} updated",
+            self.created, self.updated
+        );
+        Ok(())
+    }
+}
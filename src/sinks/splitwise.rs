@@ -0,0 +1,123 @@
+use super::Config;
+use crate::output::Output;
+use crate::Transaction;
+use anyhow::{anyhow, Context, Error};
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+const API: &str = "https://secure.splitwise.com/api/v3.0";
+
+// Creates a Splitwise expense, split equally, in a group for every debit;
+// credits aren't expenses and are left out. Narrow the rows down to the
+// shared ones with --tag. The transaction ID goes in the expense's notes, and
+// rows whose ID is in the notes of an expense of the group already are
+// skipped, so re-runs don't create duplicates.
+pub struct Splitwise {
+    client: Client,
+    token: String,
+    group: String,
+    // IDs of the transactions in the group's expenses.
+    existing: HashSet<String>,
+    created: usize,
+    skipped: usize,
+}
+
+fn note(id: &str) -> String {
+    format!("hdfc-cc-parser id: {}", id)
+}
+
+impl Splitwise {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        let mut splitwise = Splitwise {
+            client: Client::new(),
+            token: config.token("splitwise")?,
+            group: config
+                .group
+                .clone()
+                .ok_or_else(|| anyhow!("--group is required for the splitwise sink"))?,
+            existing: HashSet::new(),
+            created: 0,
+            skipped: 0,
+        };
+        splitwise.existing = splitwise.existing()?;
+        Ok(splitwise)
+    }
+
+    fn existing(&self) -> Result<HashSet<String>, Error> {
+        let found: Value = self
+            .client
+            .get(format!("{}/get_expenses", API))
+            .bearer_auth(&self.token)
+            .query(&[("group_id", self.group.as_str()), ("limit", "0")])
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .context("failed to list Splitwise expenses")?
+            .json()
+            .context("failed to read Splitwise expenses")?;
+
+        let prefix = note("");
+        Ok(found["expenses"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|expense| expense["deleted_at"].is_null())
+            .filter_map(|expense| expense["details"].as_str())
+            .flat_map(|details| details.lines())
+            .filter_map(|line| line.strip_prefix(&prefix))
+            .map(str::to_owned)
+            .collect())
+    }
+}
+
+impl Output for Splitwise {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        let id = transaction.id();
+        if transaction.amount >= 0.0 || self.existing.contains(&id) {
+            self.skipped += 1;
+            return Ok(());
+        }
+
+        let created: Value = self
+            .client
+            .post(format!("{}/create_expense", API))
+            .bearer_auth(&self.token)
+            .json(&json!({
+                "cost": format!("{:.2}", -transaction.amount),
+                "description": transaction.tx,
+                "details": note(&id),
+                "date": transaction.date.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                "currency_code": "INR",
+                "group_id": self.group,
+                "split_equally": true,
+            }))
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .context(format!("failed to create Splitwise expense for {}", id))?
+            .json()
+            .context("failed to read Splitwise response")?;
+        // validation errors come back with a 200.
+        if let Some(errors) = created["errors"]
+            .as_object()
+            .filter(|errors| !errors.is_empty())
+        {
+            return Err(anyhow!(
+                "failed to create Splitwise expense for {}: {}",
+                id,
+                Value::Object(errors.clone())
+            ));
+        }
+
+        self.existing.insert(id);
+        self.created += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        eprintln!(
+            "splitwise: {} expenses created, {} skipped (credits or already present)",
+            self.created, self.skipped
+        );
+        Ok(())
+    }
+}
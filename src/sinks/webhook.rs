@@ -0,0 +1,80 @@
+use super::Config;
+use crate::output::Output;
+use crate::Transaction;
+use anyhow::{Context, Error};
+use hmac::{Hmac, Mac};
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+// POSTs batches of transactions as JSON to a URL, optionally signed with an
+// HMAC-SHA256 of the body in the X-Signature header.
+pub struct Webhook {
+    client: Client,
+    url: String,
+    secret: Option<String>,
+    batch_size: usize,
+    batch: Vec<Value>,
+    utc: bool,
+}
+
+impl Webhook {
+    pub fn new(config: &Config) -> Result<Self, Error> {
+        Ok(Webhook {
+            client: Client::new(),
+            url: config.url("webhook")?,
+            secret: config.secret.clone(),
+            batch_size: config.batch_size.max(1),
+            batch: Vec::new(),
+            utc: config.utc,
+        })
+    }
+
+    fn send(&mut self) -> Result<(), Error> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+
+        let body = serde_json::to_vec(&json!({ "transactions": self.batch }))
+            .context("failed to serialize webhook payload")?;
+
+        let mut req = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/json");
+        if let Some(secret) = &self.secret {
+            let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+                .context("invalid webhook secret")?;
+            mac.update(&body);
+            let signature: String = mac
+                .finalize()
+                .into_bytes()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            req = req.header("X-Signature", format!("sha256={}", signature));
+        }
+
+        req.body(body)
+            .send()
+            .and_then(|resp| resp.error_for_status())
+            .context(format!("failed to post transactions to {}", self.url))?;
+
+        self.batch.clear();
+        Ok(())
+    }
+}
+
+impl Output for Webhook {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        self.batch.push(transaction.to_json(self.utc));
+        if self.batch.len() >= self.batch_size {
+            self.send()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.send()
+    }
+}
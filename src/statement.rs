@@ -0,0 +1,418 @@
+use crate::{location, Transaction};
+use anyhow::{Context, Error};
+use chrono::NaiveDate;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use unicode_normalization::UnicodeNormalization;
+
+// Labels of the "Account Summary" table, in the order its values follow.
+const ACCOUNT_SUMMARY: [&str; 5] = [
+    "Opening Balance",
+    "Payment/ Credits",
+    "Purchase/ Debits",
+    "Finance Charges",
+    "Total Dues",
+];
+
+// Labels of the reward points summary, in the order its values follow.
+const POINTS_SUMMARY: [&str; 4] = [
+    "Opening Balance",
+    "Feature + Bonus Reward Points Earned",
+    "Disbursed",
+    "Closing Balance",
+];
+
+// Amounts in the "Past Dues" block, by how long they're overdue.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct PastDues {
+    pub current: f32,
+    pub days_30: f32,
+    pub days_60: f32,
+    pub days_90_plus: f32,
+}
+
+impl PastDues {
+    // Anything beyond the current cycle is a balance rolling over.
+    pub fn overdue(&self) -> f32 {
+        self.days_30 + self.days_60 + self.days_90_plus
+    }
+}
+
+// Statement level details read from a PDF, besides the transactions.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Statement {
+    pub file: String,
+    // name of the detected statement layout. These three are written to
+    // --cache-dir by name, and looked up again when read back.
+    #[serde(skip)]
+    pub bank: &'static str,
+    #[serde(skip)]
+    pub layout: &'static str,
+    // what the statement calls its rewards, see `Layout::rewards_unit`.
+    #[serde(skip)]
+    pub rewards_unit: &'static str,
+    // how well the layout's markers matched, out of the best possible (see
+    // `Layout::score`), None when no layout's did and it's the fallback.
+    #[serde(default)]
+    pub layout_score: Option<(usize, usize)>,
+    // which of several candidate passwords opened it, counting from 1.
+    #[serde(skip)]
+    pub password: Option<usize>,
+    pub pages: usize,
+    pub card_number: Option<String>,
+    // bank account statements: the account number. Their opening and closing
+    // balances go in `opening_balance`/`total_dues`, debits in `purchases` and
+    // credits in `payments`.
+    pub account_number: Option<String>,
+    pub statement_date: Option<NaiveDate>,
+    pub due_date: Option<NaiveDate>,
+    pub period: Option<(NaiveDate, NaiveDate)>,
+
+    // account summary, as printed on the statement.
+    pub opening_balance: Option<f32>,
+    pub payments: Option<f32>,
+    pub purchases: Option<f32>,
+    pub finance_charges: Option<f32>,
+    pub total_dues: Option<f32>,
+    pub minimum_due: Option<f32>,
+
+    // "Past Dues" aging: current, 30, 60 and 90+ days overdue.
+    pub past_dues: Option<PastDues>,
+
+    // reward points summary, as printed on the statement.
+    pub points_opening: Option<i32>,
+    pub points_earned: Option<i32>,
+    pub points_redeemed: Option<i32>,
+    pub points_closing: Option<i32>,
+
+    // totals of the parsed rows.
+    pub count: usize,
+    pub debits: f32,
+    pub credits: f32,
+    pub points: i32,
+    pub cashback: f32,
+
+    pub warnings: Vec<String>,
+}
+
+impl Statement {
+    pub fn new(file: &str) -> Self {
+        Statement {
+            file: file.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    // Account for a parsed row in the totals, and mark it as coming from
+    // this statement. Every row passes through here, so its description is
+    // cleaned up here too.
+    pub fn add(&mut self, transaction: &mut Transaction) {
+        transaction.tx = clean_text(&transaction.tx);
+        (transaction.city, transaction.state) = location::locate(&transaction.tx).unzip();
+        transaction.statement_file = self.file.clone();
+        transaction.statement_date = self.statement_date;
+        transaction.card = self.card_number.clone();
+        if transaction.amount < 0.0 {
+            self.debits += transaction.amount;
+        } else {
+            self.credits += transaction.amount;
+        }
+        self.points += transaction.points;
+        self.cashback += transaction.cashback;
+        self.count += 1;
+    }
+
+    // Last day of the statement period, or the statement date without one.
+    pub fn period_end(&self) -> Option<NaiveDate> {
+        self.period.map(|(_, to)| to).or(self.statement_date)
+    }
+
+    // Pick the statement details out of the text drawn on its pages, in
+    // drawing order. Fields that aren't found stay None.
+    pub fn read_details(&mut self, texts: &[String]) {
+        let joined = texts.join("\n");
+
+        let card = Regex::new(
+            r"Card No\s*:?\s*([0-9X]{4}\s?[0-9X]{2}XX\s?XXXX\s?\d{4}|[0-9X ]{12,19}\d{4})",
+        )
+        .unwrap();
+        self.card_number = card
+            .captures(&joined)
+            .map(|c| c[1].split_whitespace().collect::<Vec<_>>().join(" "));
+
+        self.statement_date = date_after(&joined, "Statement Date");
+        self.due_date = date_after(&joined, "Payment Due Date");
+
+        let period = Regex::new(
+            r"(?:Billing|Statement) Period\s*:?\s*(\d{2}/\d{2}/\d{4}|\d{1,2} \w{3},? \d{4})\s*(?:-|to)\s*(\d{2}/\d{2}/\d{4}|\d{1,2} \w{3},? \d{4})",
+        )
+        .unwrap();
+        self.period = period
+            .captures(&joined)
+            .and_then(|c| Some((parse_date(&c[1])?, parse_date(&c[2])?)));
+
+        if let Some(values) = table_values(texts, &ACCOUNT_SUMMARY, parse_amount) {
+            self.opening_balance = Some(values[0]);
+            self.payments = Some(values[1]);
+            self.purchases = Some(values[2]);
+            self.finance_charges = Some(values[3]);
+            self.total_dues = Some(values[4]);
+        }
+        self.minimum_due = amount_after(texts, "Minimum Amount Due");
+
+        self.past_dues = past_dues(texts);
+        if let Some(dues) = &self.past_dues {
+            if dues.overdue() > 0.0 {
+                self.warnings.push(format!(
+                    "{:.2} past due beyond the current cycle",
+                    dues.overdue()
+                ));
+            }
+        }
+
+        if let Some(values) = table_values(texts, &POINTS_SUMMARY, parse_points) {
+            self.points_opening = Some(values[0]);
+            self.points_earned = Some(values[1]);
+            self.points_redeemed = Some(values[2]);
+            self.points_closing = Some(values[3]);
+        }
+
+        self.warn_missing();
+    }
+
+    // Warn about the details every statement should have but weren't found.
+    pub fn warn_missing(&mut self) {
+        for (field, missing) in [
+            ("card number", self.card_number.is_none()),
+            ("statement date", self.statement_date.is_none()),
+            ("account summary", self.total_dues.is_none()),
+        ] {
+            if missing {
+                self.warnings.push(format!("{} not found", field));
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let date = |d: Option<NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string());
+        json!({
+            "file": self.file,
+            "bank": self.bank,
+            "layout": self.layout,
+            "rewards_unit": self.rewards_unit,
+            "pages": self.pages,
+            "card_number": self.card_number,
+            "account_number": self.account_number,
+            "statement_date": date(self.statement_date),
+            "due_date": date(self.due_date),
+            "period": self.period.map(|(from, to)| json!({
+                "from": date(Some(from)),
+                "to": date(Some(to)),
+            })),
+            "account_summary": {
+                "opening_balance": self.opening_balance,
+                "payments": self.payments,
+                "purchases": self.purchases,
+                "finance_charges": self.finance_charges,
+                "total_dues": self.total_dues,
+                "minimum_due": self.minimum_due,
+            },
+            "past_dues": self.past_dues.map(|dues| json!({
+                "current": dues.current,
+                "30_days": dues.days_30,
+                "60_days": dues.days_60,
+                "90_plus_days": dues.days_90_plus,
+            })),
+            "points_summary": {
+                "opening": self.points_opening,
+                "earned": self.points_earned,
+                "redeemed": self.points_redeemed,
+                "closing": self.points_closing,
+            },
+            "totals": {
+                "transactions": self.count,
+                "debits": self.debits,
+                "credits": self.credits,
+                "points": self.points,
+                "cashback": self.cashback,
+            },
+            "warnings": self.warnings,
+        })
+    }
+
+    // Write the details to `<dir>/<stem>.meta.json`, where stem is the
+    // statement's file name without the extension.
+    pub fn write_meta(&self, dir: &str) -> Result<(), Error> {
+        let stem = Path::new(&self.file)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy();
+        let path = Path::new(dir).join(format!("{}.meta.json", stem));
+
+        let data = serde_json::to_string_pretty(&self.to_json())?;
+        fs::write(&path, data).context(format!("failed to write {}", path.display()))
+    }
+}
+
+pub fn date_after(text: &str, label: &str) -> Option<NaiveDate> {
+    let re = Regex::new(&format!(
+        r"{}\s*:?\s*(\d{{2}}/\d{{2}}/\d{{4}}|\d{{1,2}} \w{{3}},? \d{{4}})",
+        regex::escape(label)
+    ))
+    .unwrap();
+    re.captures(text).and_then(|c| parse_date(&c[1]))
+}
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    ["%d/%m/%Y", "%d %b, %Y", "%d %b %Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(s.trim(), format).ok())
+}
+
+// "1,23,456.78", "1,23,456.78 Cr" or "₹ 2,499.00 Dr", credits negative.
+pub fn parse_amount(s: &str) -> Option<f32> {
+    let s = s.trim();
+    let (s, credit) = match strip_mark(s, &["Cr", "CR"]) {
+        Some(s) => (s, true),
+        None => (strip_mark(s, &["Dr", "DR"]).unwrap_or(s), false),
+    };
+    let s = strip_currency(s);
+    let s = s.strip_prefix('+').unwrap_or(s);
+    if !s.contains('.') {
+        return None;
+    }
+    let amount = without_separators(s).parse::<f32>().ok()?;
+    Some(if credit { -amount } else { amount })
+}
+
+// `s` without a trailing credit or debit mark, as in "2,499.00 Cr" or
+// "2,499.00 (Cr)".
+fn strip_mark<'a>(s: &'a str, marks: &[&str]) -> Option<&'a str> {
+    marks.iter().find_map(|mark| {
+        let s = match s.strip_suffix(')') {
+            Some(s) => s.strip_suffix(mark)?.strip_suffix('(')?,
+            None => s.strip_suffix(mark)?,
+        };
+        Some(s.trim_end())
+    })
+}
+
+// The rupee sign in its spellings: "₹", "Rs.", "INR", and "`", which the
+// Rupee Foradian font some statements use draws as one.
+fn strip_currency(s: &str) -> &str {
+    ["₹", "Rs.", "Rs", "INR", "`"]
+        .iter()
+        .find_map(|sign| s.strip_prefix(sign))
+        .unwrap_or(s)
+        .trim_start()
+}
+
+// Digits without grouping: commas, and the (narrow) no-break and thin
+// spaces some statements group with instead, as well as a space after a
+// sign as in "- 25".
+fn without_separators(s: &str) -> String {
+    s.chars()
+        .filter(|c| !matches!(c, ',' | ' ' | '\u{a0}' | '\u{202f}' | '\u{2009}'))
+        .collect()
+}
+
+// Characters PDFs leave in text that aren't part of it: soft hyphens,
+// bullets, zero width spaces and joiners, byte order marks and the
+// replacement character of failed font decoding.
+const STRAY: [char; 9] = [
+    '\u{ad}', '\u{2022}', '\u{25cf}', '\u{25aa}', '\u{200b}', '\u{200c}', '\u{200d}', '\u{feff}',
+    '\u{fffd}',
+];
+
+// NFKC normalized text without stray characters and control characters,
+// with runs of whitespace collapsed into one space.
+pub fn clean_text(s: &str) -> String {
+    let cleaned: String = s
+        .nfkc()
+        .filter(|c| !STRAY.contains(c) && (!c.is_control() || c.is_whitespace()))
+        .collect();
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// "1,234", "- 25" or "+25".
+pub fn parse_points(s: &str) -> Option<i32> {
+    without_separators(s.trim()).parse::<i32>().ok()
+}
+
+// The "Past Dues" block: a row of bucket labels ("Current Dues", "30 Days",
+// "0-30 Days", ...) followed by their amounts. Buckets are told apart by the
+// day count the label goes up to, anything past 60 days adds up into the
+// last one.
+fn past_dues(texts: &[String]) -> Option<PastDues> {
+    let start = texts.iter().position(|t| t.starts_with("Past Dues"))? + 1;
+    let labels: Vec<&String> = texts[start..]
+        .iter()
+        .take_while(|t| parse_amount(t).is_none())
+        .take(8)
+        .collect();
+    let values: Vec<f32> = texts[start + labels.len()..]
+        .iter()
+        .take(labels.len() * 2)
+        .filter_map(|t| parse_amount(t))
+        .take(labels.len())
+        .collect();
+    if labels.is_empty() || values.len() != labels.len() {
+        return None;
+    }
+
+    let mut dues = PastDues::default();
+    for (label, amount) in labels.iter().zip(values) {
+        // last number, so "31-60 Days" lands with "60 Days" and "0-30 Days"
+        // isn't current.
+        let days: u32 = label
+            .split(|c: char| !c.is_ascii_digit())
+            .rfind(|n| !n.is_empty())
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(0);
+        match days {
+            0 => dues.current += amount,
+            1..=30 => dues.days_30 += amount,
+            31..=60 => dues.days_60 += amount,
+            _ => dues.days_90_plus += amount,
+        }
+    }
+    Some(dues)
+}
+
+// The first amount among the few texts following `label`.
+pub fn amount_after(texts: &[String], label: &str) -> Option<f32> {
+    let i = texts.iter().position(|t| t.starts_with(label))?;
+    texts[i + 1..].iter().take(4).find_map(|t| parse_amount(t))
+}
+
+// Values of a summary table: the labels are drawn as a header row, followed
+// by one value per label in the same order.
+pub fn table_values<T>(
+    texts: &[String],
+    labels: &[&str],
+    parse: fn(&str) -> Option<T>,
+) -> Option<Vec<T>> {
+    let start = texts
+        .windows(2)
+        .position(|w| w[0].starts_with(labels[0]) && w[1].starts_with(labels[1]))?;
+    let last = start
+        + texts[start..]
+            .iter()
+            .position(|t| t.starts_with(labels[labels.len() - 1]))?;
+
+    // allow for a few stray texts (currency symbols, "=" signs) in between.
+    let values: Vec<T> = texts[last + 1..]
+        .iter()
+        .take(labels.len() * 2)
+        .filter_map(|t| parse(t))
+        .take(labels.len())
+        .collect();
+    if values.len() == labels.len() {
+        Some(values)
+    } else {
+        None
+    }
+}
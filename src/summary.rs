@@ -0,0 +1,358 @@
+use crate::categories::{ask_category, merchant_key, Categories};
+use crate::forecast;
+use crate::locale::Locale;
+use crate::matching;
+use crate::points::Ledger;
+use crate::statement::Statement;
+use crate::{Transaction, TransactionType};
+use anyhow::Error;
+use chrono::{Duration, NaiveDate};
+use std::collections::{BTreeMap, HashSet};
+
+// the category of rows no rule matched.
+pub const UNCATEGORIZED: &str = "Uncategorized";
+// rows without a known city (with --summary-by city/state) or card.
+const UNKNOWN: &str = "Unknown";
+// expiring points are warned about this many days ahead of the last
+// statement, in batches of at least this many.
+const EXPIRY_WARNING_DAYS: i64 = 90;
+const EXPIRY_WARNING_POINTS: i32 = 1000;
+
+// Running totals for the summary report.
+#[derive(Debug, Default)]
+pub struct Summary {
+    // category (or city/state with --summary-by) -> (row count, net amount)
+    pub categories: BTreeMap<String, (usize, f32)>,
+    pub debits: f32,
+    pub credits: f32,
+    // the credits by type.
+    pub payments: f32,
+    pub refunds: f32,
+    pub cashback_credits: f32,
+    pub points: i32,
+    // rewards unit -> cashback earned, eg. "NeuCoins" -> 120.0
+    pub cashback: BTreeMap<&'static str, f32>,
+    pub count: usize,
+    // card, after the owner with --profiles -> (row count, debits, credits),
+    // for runs over several cards.
+    pub cards: BTreeMap<String, (usize, f32, f32)>,
+
+    // for the statistics: debit sizes, positive, the merchants debited,
+    // debits per day, and the first and last day of the rows.
+    pub sizes: Sizes,
+    pub merchants: HashSet<String>,
+    pub days: BTreeMap<NaiveDate, f32>,
+    pub period: Option<(NaiveDate, NaiveDate)>,
+}
+
+impl Summary {
+    pub fn add(&mut self, transaction: &Transaction, category: Option<&str>) {
+        // a purchase converted to EMI and its reversal cancel out; its
+        // installments count instead.
+        if transaction.emi_group.is_some() && !matching::emi_installment(transaction) {
+            return;
+        }
+        let entry = self
+            .categories
+            .entry(category.unwrap_or(UNCATEGORIZED).to_owned())
+            .or_default();
+        entry.0 += 1;
+        entry.1 += transaction.amount;
+
+        if transaction.amount < 0.0 {
+            self.debits += transaction.amount;
+            self.sizes.add(-transaction.amount);
+            let merchant = merchant_key(&transaction.tx);
+            if !merchant.is_empty() {
+                self.merchants.insert(merchant);
+            }
+            *self.days.entry(transaction.date.date()).or_default() -= transaction.amount;
+        } else {
+            self.credits += transaction.amount;
+            match transaction.transaction_type() {
+                TransactionType::Payment => self.payments += transaction.amount,
+                TransactionType::Cashback => self.cashback_credits += transaction.amount,
+                _ => self.refunds += transaction.amount,
+            }
+        }
+        self.points += transaction.points;
+        if transaction.cashback != 0.0 {
+            *self.cashback.entry(transaction.rewards_unit).or_default() += transaction.cashback;
+        }
+        self.count += 1;
+
+        let card = transaction.card.as_deref().unwrap_or(UNKNOWN);
+        let card = match &transaction.owner {
+            Some(owner) => format!("{} {}", owner, card),
+            None => card.to_owned(),
+        };
+        let card = self.cards.entry(card).or_default();
+        card.0 += 1;
+        if transaction.amount < 0.0 {
+            card.1 += transaction.amount;
+        } else {
+            card.2 += transaction.amount;
+        }
+
+        let date = transaction.date.date();
+        self.period = Some(match self.period {
+            Some((first, last)) => (first.min(date), last.max(date)),
+            None => (date, date),
+        });
+    }
+
+    // `label` heads the per category totals, amounts are written for `locale`.
+    pub fn print(&self, label: &str, locale: Locale) {
+        if !self.categories.is_empty() {
+            println!("{:<24} {:>6} {:>14}", label, "Count", "Amount");
+            for (category, (count, amount)) in &self.categories {
+                println!(
+                    "{:<24} {:>6} {:>14}",
+                    category,
+                    count,
+                    locale.amount(*amount)
+                );
+            }
+            println!();
+        }
+
+        println!("Transactions: {}", self.count);
+        println!("Debits:       {}", locale.amount(self.debits));
+        println!("Credits:      {}", locale.amount(self.credits));
+        for (label, amount) in [
+            ("Payments", self.payments),
+            ("Refunds", self.refunds),
+            ("Cashback", self.cashback_credits),
+        ] {
+            println!("  {:<11} {}", format!("{}:", label), locale.amount(amount));
+        }
+        println!("Points:       {}", locale.number(self.points as i64));
+        for (unit, cashback) in &self.cashback {
+            println!("{:<13} {}", format!("{}:", unit), locale.amount(*cashback));
+        }
+        self.print_cards(locale);
+        self.print_statistics(locale);
+    }
+
+    // The totals per card, when there's more than one.
+    fn print_cards(&self, locale: Locale) {
+        if self.cards.len() < 2 {
+            return;
+        }
+        println!();
+        println!(
+            "{:<24} {:>6} {:>14} {:>14}",
+            "Card", "Count", "Debits", "Credits"
+        );
+        for (card, (count, debits, credits)) in &self.cards {
+            println!(
+                "{:<24} {:>6} {:>14} {:>14}",
+                card,
+                count,
+                locale.amount(*debits),
+                locale.amount(*credits)
+            );
+        }
+    }
+
+    // Debit sizes and daily spend.
+    fn print_statistics(&self, locale: Locale) {
+        let Some((first, last)) = self.period else {
+            return;
+        };
+        let n = self.sizes.count;
+        if n == 0 {
+            return;
+        }
+        let median = self.sizes.quantile(0.5);
+        let p95 = self.sizes.quantile(0.95);
+        let spent = -self.debits;
+        let days = (last - first).num_days() + 1;
+
+        println!();
+        println!("Statistics, {} to {}:", first, last);
+        println!("  Average debit:     {}", locale.amount(spent / n as f32));
+        println!("  Median debit:      {}", locale.amount(median));
+        println!("  95th percentile:   {}", locale.amount(p95));
+        println!(
+            "  Merchants:         {}",
+            locale.number(self.merchants.len() as i64)
+        );
+        if let Some((day, amount)) = self.days.iter().max_by(|a, b| a.1.total_cmp(b.1)) {
+            println!("  Largest day:       {} on {}", locale.amount(*amount), day);
+        }
+        println!(
+            "  Average daily:     {}",
+            locale.amount(spent / days as f32)
+        );
+    }
+}
+
+// Debit sizes as a histogram of buckets 1% wide, for their median and 95th
+// percentile: a few thousand buckets at most span paise to crores, however
+// many rows there are, and the quantiles read off them are within half a
+// percent.
+#[derive(Debug, Default)]
+pub struct Sizes {
+    pub count: usize,
+    buckets: BTreeMap<i32, usize>,
+}
+
+const BUCKET_WIDTH: f32 = 1.01;
+
+impl Sizes {
+    pub fn add(&mut self, size: f32) {
+        let bucket = (size.ln() / BUCKET_WIDTH.ln()).floor();
+        // NaN and zero sizes go to the lowest bucket.
+        let bucket = if bucket.is_finite() {
+            bucket as i32
+        } else {
+            i32::MIN
+        };
+        *self.buckets.entry(bucket).or_default() += 1;
+        self.count += 1;
+    }
+
+    // The nearest rank `q` quantile, as the middle of its bucket.
+    pub fn quantile(&self, q: f32) -> f32 {
+        let rank = ((self.count as f32 * q).ceil() as usize).max(1);
+        let mut seen = 0;
+        for (bucket, count) in &self.buckets {
+            seen += count;
+            if seen >= rank {
+                return BUCKET_WIDTH.powf(*bucket as f32 + 0.5);
+            }
+        }
+        0.0
+    }
+}
+
+// Summarize the received rows, totalled per category, or per city or state
+// by `by`, amounts written for `locale`. With `interactive`, uncategorized
+// merchants are prompted for on the terminal and the answers appended to the
+// categories file.
+pub fn run(
+    rows: impl Iterator<Item = Transaction>,
+    mut categories: Option<Categories>,
+    categories_path: Option<&str>,
+    interactive: bool,
+    by: &str,
+    locale: Locale,
+) -> Result<(), Error> {
+    if by != "category" {
+        let mut summary = Summary::default();
+        for transaction in rows {
+            let place = match by {
+                "city" => transaction.city,
+                _ => transaction.state,
+            };
+            summary.add(&transaction, Some(place.unwrap_or(UNKNOWN)));
+        }
+        summary.print(if by == "city" { "City" } else { "State" }, locale);
+        return Ok(());
+    }
+
+    let mut summary = Summary::default();
+    // merchants the user chose not to categorize, so they're asked only once.
+    let mut skipped = HashSet::new();
+
+    for transaction in rows {
+        let categories = match &mut categories {
+            Some(categories) => categories,
+            None => {
+                summary.add(&transaction, None);
+                continue;
+            }
+        };
+
+        if interactive && categories.categorize(&transaction).is_none() {
+            let merchant = merchant_key(&transaction.tx);
+            if !merchant.is_empty() && !skipped.contains(&merchant) {
+                match ask_category(&transaction.tx, &merchant)? {
+                    Some(category) => {
+                        categories.learn(&category, &merchant);
+                        if let Some(path) = categories_path {
+                            categories.save(path)?;
+                        }
+                    }
+                    None => {
+                        skipped.insert(merchant);
+                    }
+                }
+            }
+        }
+
+        summary.add(&transaction, categories.categorize(&transaction));
+    }
+
+    summary.print("Category", locale);
+    Ok(())
+}
+
+// The points earned, redeemed and expired over the statements' points
+// summaries, warning about large batches expiring within 90 days of the last
+// statement, `months` after they were earned.
+pub fn print_points(statements: &[Statement], months: u32, locale: Locale) {
+    let ledger = Ledger::new(statements);
+    let Some(as_of) = ledger.as_of else {
+        return;
+    };
+    println!("Points earned:   {}", locale.number(ledger.earned as i64));
+    println!("Points redeemed: {}", locale.number(ledger.redeemed as i64));
+    println!("Points expired:  {}", locale.number(ledger.expired as i64));
+    println!(
+        "Points balance:  {} as of {}",
+        locale.number(ledger.balance as i64),
+        as_of
+    );
+    for warning in expiring_points(statements, months, locale) {
+        eprintln!("warning: {}", warning);
+    }
+}
+
+// Spend (purchases net of refunds) and rows per category of categorized
+// rows, biggest first, for the mcp and serve-grpc summaries.
+pub fn spend_by_category<'a>(
+    rows: impl IntoIterator<Item = &'a Transaction>,
+) -> Vec<(String, f32, usize)> {
+    let mut categories: BTreeMap<&str, (f32, usize)> = BTreeMap::new();
+    for transaction in rows {
+        let spend = forecast::spend(transaction);
+        if spend == 0.0 {
+            continue;
+        }
+        let (total, n) = categories
+            .entry(transaction.category.as_deref().unwrap_or(UNCATEGORIZED))
+            .or_default();
+        *total += spend;
+        *n += 1;
+    }
+    let mut categories: Vec<_> = categories
+        .into_iter()
+        .map(|(category, (spend, n))| (category.to_owned(), spend, n))
+        .collect();
+    categories.sort_by(|a, b| b.1.total_cmp(&a.1));
+    categories
+}
+
+// Warnings of the batches of points worth a look expiring soon after the
+// last statement.
+pub fn expiring_points(statements: &[Statement], months: u32, locale: Locale) -> Vec<String> {
+    let ledger = Ledger::new(statements);
+    let Some(as_of) = ledger.as_of else {
+        return Vec::new();
+    };
+    ledger
+        .expiring(months, as_of + Duration::days(EXPIRY_WARNING_DAYS))
+        .into_iter()
+        .filter(|(_, batch)| batch.points >= EXPIRY_WARNING_POINTS)
+        .map(|(expiry, batch)| {
+            format!(
+                "{} points earned by {} expire on {}",
+                locale.number(batch.points as i64),
+                batch.earned_on,
+                expiry
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,81 @@
+use crate::output::Output;
+use crate::summary::Summary;
+use crate::Transaction;
+use anyhow::{Context as _, Error};
+use serde_json::json;
+use std::fs;
+use std::io::Write;
+use tera::{Context, Tera};
+
+// Free-form text rendered from a Tera template. The template sees every row
+// at once, so it can loop over `transactions` and use the `summary` totals;
+// rows are buffered and the template rendered on finish.
+pub struct Template<W: Write> {
+    out: W,
+    tera: Tera,
+    transactions: Vec<serde_json::Value>,
+    summary: Summary,
+    utc: bool,
+}
+
+const NAME: &str = "template";
+
+impl<W: Write> Template<W> {
+    pub fn new(out: W, path: &str, utc: bool) -> Result<Self, Error> {
+        let source = fs::read_to_string(path).context(format!("failed to read {}", path))?;
+        let mut tera = Tera::default();
+        tera.add_raw_template(NAME, &source)
+            .context(format!("failed to parse template {}", path))?;
+
+        Ok(Template {
+            out,
+            tera,
+            transactions: Vec::new(),
+            summary: Summary::default(),
+            utc,
+        })
+    }
+}
+
+impl<W: Write> Output for Template<W> {
+    fn write(&mut self, transaction: &Transaction) -> Result<(), Error> {
+        self.summary
+            .add(transaction, transaction.category.as_deref());
+        self.transactions.push(transaction.to_json(self.utc));
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        let categories: Vec<_> = self
+            .summary
+            .categories
+            .iter()
+            .map(|(category, (count, amount))| {
+                json!({"category": category, "count": count, "amount": amount})
+            })
+            .collect();
+
+        let mut context = Context::new();
+        context.insert("transactions", &self.transactions);
+        context.insert(
+            "summary",
+            &json!({
+                "count": self.summary.count,
+                "debits": self.summary.debits,
+                "credits": self.summary.credits,
+                "points": self.summary.points,
+                "cashback": self.summary.cashback,
+                "categories": categories,
+            }),
+        );
+
+        let rendered = self
+            .tera
+            .render(NAME, &context)
+            .context("failed to render template")?;
+        self.out
+            .write_all(rendered.as_bytes())
+            .context("Failed to write template output")?;
+        self.out.flush().context("failed to flush template output")
+    }
+}
@@ -0,0 +1,65 @@
+use crate::statement::Statement;
+use chrono::{Datelike, NaiveDate};
+use std::collections::BTreeSet;
+
+// Balances may be off by rounding in the printed summary.
+const TOLERANCE: f32 = 0.01;
+
+// Cross-check a set of statements of one card and report problems, one line
+// each. Statements are chained by statement date: each one's opening balance
+// should be the previous one's closing balance (total dues), otherwise a
+// statement is missing in between or one of the two didn't parse fully.
+pub fn check(statements: &[Statement]) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for statement in statements {
+        for warning in &statement.warnings {
+            problems.push(format!("{}: {}", statement.file, warning));
+        }
+    }
+
+    let mut dated: Vec<&Statement> = statements
+        .iter()
+        .filter(|s| s.statement_date.is_some())
+        .collect();
+    dated.sort_by_key(|s| s.statement_date);
+
+    for pair in dated.windows(2) {
+        let (previous, statement) = (pair[0], pair[1]);
+        let (closing, opening) = match (previous.total_dues, statement.opening_balance) {
+            (Some(closing), Some(opening)) => (closing, opening),
+            _ => continue,
+        };
+        if (closing - opening).abs() > TOLERANCE {
+            problems.push(format!(
+                "{}: opening balance {:.2} doesn't match the closing balance {:.2} of {}, missing statement or parse gap in between",
+                statement.file, opening, closing, previous.file
+            ));
+        }
+    }
+
+    problems
+}
+
+// Months ("2024-03") between the first and last of `dates` that have none
+// of them. Statements are monthly, so each one is a month's missing PDF.
+pub fn missing_months(dates: &[NaiveDate]) -> Vec<String> {
+    let months: BTreeSet<(i32, u32)> = dates.iter().map(|d| (d.year(), d.month())).collect();
+    let (Some(&first), Some(&last)) = (months.first(), months.last()) else {
+        return Vec::new();
+    };
+
+    let mut missing = Vec::new();
+    let (mut year, mut month) = first;
+    while (year, month) < last {
+        if !months.contains(&(year, month)) {
+            missing.push(format!("{}-{:02}", year, month));
+        }
+        (year, month) = if month == 12 {
+            (year + 1, 1)
+        } else {
+            (year, month + 1)
+        };
+    }
+    missing
+}
@@ -0,0 +1,144 @@
+// The exit codes of the binary, one per way a run can go wrong, over
+// synthetic statements (see `fixture`).
+use hdfc_cc_parser_rs::error;
+use hdfc_cc_parser_rs::fixture::Fixture;
+use std::fs;
+use std::process::Command;
+
+const NAME: &str = "JOHN DOE";
+const PASSWORD: &str = "JOHN0101";
+
+// Write `data` to a file of the test's own in the temp dir, returning its
+// path.
+fn write(name: &str, data: &[u8]) -> String {
+    let path = std::env::temp_dir().join(format!("cli-{}-{}", std::process::id(), name));
+    fs::write(&path, data).unwrap();
+    path.to_str().unwrap().to_owned()
+}
+
+fn exit_code(args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_hdfc-cc-parser-rs"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+#[test]
+fn runs_exit_with_the_code_of_what_went_wrong() {
+    let statement = write(
+        "statement.pdf",
+        &Fixture::sample(NAME, 5).pdf(PASSWORD).unwrap(),
+    );
+    let empty = write(
+        "empty.pdf",
+        &Fixture::sample(NAME, 0).pdf(PASSWORD).unwrap(),
+    );
+    let corrupt = write("corrupt.pdf", b"not a statement");
+    let missing = std::env::temp_dir().join(format!("cli-{}-missing.pdf", std::process::id()));
+    let missing = missing.to_str().unwrap();
+
+    let run = |file: &str, password: &str| {
+        exit_code(&["--file", file, "--name", NAME, "--password", password])
+    };
+    assert_eq!(run(&statement, PASSWORD), 0);
+    assert_eq!(run(&corrupt, PASSWORD), error::EXIT_ERROR);
+    assert_eq!(exit_code(&["--no-such-flag"]), 2);
+    assert_eq!(run(&statement, "WRONG"), error::EXIT_PASSWORD);
+    // a run without any rows is told apart from a success, a quiet month
+    // among others isn't.
+    assert_eq!(run(&empty, PASSWORD), error::EXIT_NO_TRANSACTIONS);
+    let dir = std::env::temp_dir().join(format!("cli-{}-dir", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::copy(&statement, dir.join("statement.pdf")).unwrap();
+    fs::copy(&empty, dir.join("empty.pdf")).unwrap();
+    let dir = dir.to_str().unwrap();
+    assert_eq!(
+        exit_code(&["--dir", dir, "--name", NAME, "--password", PASSWORD]),
+        0
+    );
+    fs::remove_dir_all(dir).unwrap();
+    assert_eq!(
+        exit_code(&[
+            "validate",
+            "--file",
+            &empty,
+            "--name",
+            NAME,
+            "--password",
+            PASSWORD
+        ]),
+        error::EXIT_VALIDATION
+    );
+    let rules = write("rules.json", br#"{"Food": []}"#);
+    assert_eq!(
+        exit_code(&["categorize", "check", "--categories", &rules]),
+        error::EXIT_VALIDATION
+    );
+    assert_eq!(run(missing, PASSWORD), error::EXIT_IO);
+
+    for file in [statement, empty, corrupt, rules] {
+        fs::remove_file(file).unwrap();
+    }
+}
+
+#[test]
+fn cached_statements_are_read_from_where_they_are_now() {
+    let cache = std::env::temp_dir().join(format!("cli-{}-cache", std::process::id()));
+    let cache = cache.to_str().unwrap();
+    let before = write(
+        "before.pdf",
+        &Fixture::sample(NAME, 3).pdf(PASSWORD).unwrap(),
+    );
+    let after = before.replace("before.pdf", "after.pdf");
+
+    let files = |file: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_hdfc-cc-parser-rs"))
+            .args(["--file", file, "--name", NAME, "--password", PASSWORD])
+            .args(["--cache-dir", cache, "--columns", "statement_file"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+    assert_eq!(files(&before), format!("{before}\n").repeat(3));
+    // the second run is served from the cache.
+    fs::rename(&before, &after).unwrap();
+    assert_eq!(files(&after), format!("{after}\n").repeat(3));
+
+    fs::remove_file(after).unwrap();
+    fs::remove_dir_all(cache).unwrap();
+}
+
+#[test]
+fn decrypted_copies_overwrite_nothing_unasked() {
+    let statement = write(
+        "decrypt.pdf",
+        &Fixture::sample(NAME, 1).pdf(PASSWORD).unwrap(),
+    );
+    let dir = std::env::temp_dir().join(format!("cli-{}-decrypted", std::process::id()));
+    let dir = dir.to_str().unwrap();
+    let inputs = std::path::Path::new(&statement).parent().unwrap();
+    let original = fs::read(&statement).unwrap();
+
+    let decrypt = |dir: &str, force: bool| {
+        let mut args = vec!["decrypt", "--file", statement.as_str(), "--name", NAME];
+        args.extend(["--password", PASSWORD, "--output-dir", dir]);
+        if force {
+            args.push("--force");
+        }
+        exit_code(&args)
+    };
+    assert_eq!(decrypt(dir, false), 0);
+    // a copy from before.
+    assert_eq!(decrypt(dir, false), error::EXIT_ERROR);
+    assert_eq!(decrypt(dir, true), 0);
+    // the statement itself.
+    assert_eq!(decrypt(inputs.to_str().unwrap(), true), error::EXIT_ERROR);
+    assert!(fs::read(&statement).unwrap() == original);
+
+    fs::remove_file(&statement).unwrap();
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,369 @@
+// End to end: synthetic statements (see `fixture`) through parse, checked
+// against the details and transactions they were generated with.
+use hdfc_cc_parser_rs::error::ParseError;
+use hdfc_cc_parser_rs::fixture::Fixture;
+use hdfc_cc_parser_rs::layout;
+use hdfc_cc_parser_rs::statement::Statement;
+use hdfc_cc_parser_rs::{
+    parse, parse_visit, parse_with, ParseOptions, StatementIter, Transaction, Visitor,
+};
+use std::fs;
+use std::sync::mpsc;
+
+const NAME: &str = "JOHN DOE";
+const PASSWORD: &str = "JOHN0101";
+
+fn parse_pdf(
+    pdf: Vec<u8>,
+    passwords: &[&str],
+) -> Result<(Statement, Vec<Transaction>), ParseError> {
+    parse_pdf_with(pdf, passwords, ParseOptions::default())
+}
+
+fn parse_pdf_with(
+    pdf: Vec<u8>,
+    passwords: &[&str],
+    options: ParseOptions,
+) -> Result<(Statement, Vec<Transaction>), ParseError> {
+    // roomy enough for every row, which are only read after the parse.
+    let (tx, rx) = mpsc::sync_channel(1000);
+    let passwords: Vec<String> = passwords.iter().map(|p| p.to_string()).collect();
+    let statement = parse(
+        "fixture.pdf".to_owned(),
+        pdf,
+        NAME.to_owned(),
+        &passwords,
+        options,
+        &tx,
+    )?;
+    drop(tx);
+    Ok((statement, rx.into_iter().collect()))
+}
+
+#[test]
+fn rows_match_the_fixture() {
+    let fixture = Fixture::sample(NAME, 40);
+    let (_, rows) = parse_pdf(fixture.pdf(PASSWORD).unwrap(), &[PASSWORD]).unwrap();
+
+    assert_eq!(rows.len(), fixture.transactions.len());
+    for (row, expected) in rows.iter().zip(&fixture.transactions) {
+        assert_eq!(row.date, expected.date);
+        assert_eq!(row.tx, expected.tx);
+        assert_eq!(row.points, expected.points, "{}", expected.tx);
+        assert_eq!(row.amount, expected.amount, "{}", expected.tx);
+        assert_eq!(row.statement_date, Some(fixture.statement_date));
+    }
+}
+
+#[test]
+fn details_match_the_fixture() {
+    let fixture = Fixture::sample(NAME, 40);
+    let (statement, _) = parse_pdf(fixture.pdf(PASSWORD).unwrap(), &[PASSWORD]).unwrap();
+
+    assert_eq!(statement.bank, "hdfc");
+    assert_eq!(statement.layout, "hdfc");
+    assert_eq!(statement.pages, 2);
+    assert_eq!(
+        statement.card_number.as_deref(),
+        Some("4386 24XX XXXX 1234")
+    );
+    assert_eq!(statement.statement_date, Some(fixture.statement_date));
+    assert_eq!(statement.due_date, Some(fixture.due_date));
+    assert_eq!(statement.period, Some(fixture.period));
+    assert_eq!(statement.opening_balance, Some(fixture.opening_balance));
+    assert_eq!(statement.total_dues, Some(fixture.total_dues()));
+    assert_eq!(
+        statement.points_closing,
+        Some(fixture.points_opening + fixture.points_earned())
+    );
+    assert_eq!(statement.count, fixture.transactions.len());
+    assert!(statement.warnings.is_empty(), "{:?}", statement.warnings);
+}
+
+#[test]
+fn co_branded_statements_say_what_they_earn() {
+    let fixture = Fixture::sample(NAME, 30).in_layout(&layout::TATA_NEU);
+    let (statement, rows) = parse_pdf(fixture.pdf(PASSWORD).unwrap(), &[PASSWORD]).unwrap();
+
+    assert_eq!(statement.layout, "tata-neu");
+    assert_eq!(statement.rewards_unit, "NeuCoins");
+    assert_eq!(rows.len(), fixture.transactions.len());
+    for (row, expected) in rows.iter().zip(&fixture.transactions) {
+        assert_eq!(row.tx, expected.tx);
+        assert_eq!(row.amount, expected.amount, "{}", expected.tx);
+        assert_eq!(row.cashback, expected.cashback, "{}", expected.tx);
+        assert_eq!(row.rewards_unit, "NeuCoins");
+    }
+}
+
+#[test]
+fn unencrypted_fixture_needs_no_password() {
+    let fixture = Fixture::sample(NAME, 5);
+    let (_, rows) = parse_pdf(fixture.pdf("").unwrap(), &[""]).unwrap();
+    assert_eq!(rows.len(), 5);
+}
+
+#[test]
+fn password_failures() {
+    let fixture = Fixture::sample(NAME, 5);
+    let pdf = fixture.pdf(PASSWORD).unwrap();
+
+    assert!(matches!(
+        parse_pdf(pdf.clone(), &[""]),
+        Err(ParseError::Encrypted { .. })
+    ));
+    assert!(matches!(
+        parse_pdf(pdf.clone(), &["WRONG"]),
+        Err(ParseError::WrongPassword { .. })
+    ));
+    // the right one among several still opens it, and says which it was.
+    let (statement, _) = parse_pdf(pdf.clone(), &["WRONG", PASSWORD]).unwrap();
+    assert_eq!(statement.password, Some(2));
+    let (statement, _) = parse_pdf(pdf, &[PASSWORD]).unwrap();
+    assert_eq!(statement.password, None);
+}
+
+#[test]
+fn identical_rows_get_ids_of_their_own() {
+    let mut fixture = Fixture::sample(NAME, 10);
+    // the same purchase twice on a day.
+    let purchase = fixture.transactions[2].clone();
+    fixture.transactions.insert(3, purchase);
+    let (_, rows) = parse_pdf(fixture.pdf(PASSWORD).unwrap(), &[PASSWORD]).unwrap();
+
+    assert_eq!(
+        (rows[2].tx.as_str(), rows[2].amount),
+        (rows[3].tx.as_str(), rows[3].amount)
+    );
+    assert_eq!((rows[2].occurrence, rows[3].occurrence), (0, 1));
+    assert_ne!(rows[2].id(), rows[3].id());
+    // the first keeps the ID of its fields alone.
+    let fields = Transaction {
+        date: rows[2].date,
+        tx: rows[2].tx.clone(),
+        amount: rows[2].amount,
+        points: rows[2].points,
+        ..Default::default()
+    };
+    assert_eq!(rows[2].id(), fields.id());
+}
+
+#[test]
+fn rows_told_apart_by_stray_characters_get_ids_of_their_own() {
+    let mut fixture = Fixture::sample(NAME, 10);
+    // the same purchase twice, drawn with a doubled space the second time.
+    let mut purchase = fixture.transactions[2].clone();
+    purchase.tx = purchase.tx.replacen(' ', "  ", 1);
+    fixture.transactions.insert(3, purchase);
+    let (_, rows) = parse_pdf(fixture.pdf(PASSWORD).unwrap(), &[PASSWORD]).unwrap();
+
+    assert_eq!(rows[2].tx, rows[3].tx);
+    assert_eq!((rows[2].occurrence, rows[3].occurrence), (0, 1));
+    assert_ne!(rows[2].id(), rows[3].id());
+}
+
+#[test]
+fn rows_know_where_they_came_from() {
+    let fixture = Fixture::sample(NAME, 40);
+    let options = ParseOptions {
+        provenance: true,
+        ..Default::default()
+    };
+    let (_, rows) = parse_pdf_with(fixture.pdf(PASSWORD).unwrap(), &[PASSWORD], options).unwrap();
+
+    for (i, row) in rows.iter().enumerate() {
+        let provenance = row.provenance.as_ref().unwrap();
+        // 25 rows to a page.
+        assert_eq!(provenance.page, i / 25 + 1);
+        assert_eq!(provenance.tokens.len(), provenance.texts.len());
+        assert!(provenance.texts.contains(&row.tx), "{:?}", provenance);
+    }
+
+    // and only when asked to.
+    let (_, rows) = parse_pdf(fixture.pdf(PASSWORD).unwrap(), &[PASSWORD]).unwrap();
+    assert!(rows.iter().all(|row| row.provenance.is_none()));
+}
+
+#[test]
+fn layout_files_replace_the_built_in_rules() {
+    let fixture = Fixture::sample(NAME, 5);
+    let path = std::env::temp_dir().join(format!("layouts-{}.toml", std::process::id()));
+    fs::write(
+        &path,
+        r#"
+[[layout]]
+name = "hdfc-patched"
+
+[[layout]]
+name = "hdfc-renamed-sections"
+markers = ["Nowhere in the fixture"]
+sections = ["Card Transactions"]
+"#,
+    )
+    .unwrap();
+    let layouts = layout::load(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // the first is hdfc's rules under a new name, and without markers, the
+    // fallback of statements no layout is detected as.
+    let options = ParseOptions {
+        layouts: &layouts,
+        ..Default::default()
+    };
+    let (statement, rows) =
+        parse_pdf_with(fixture.pdf(PASSWORD).unwrap(), &[PASSWORD], options).unwrap();
+    assert_eq!(statement.layout, "hdfc-patched");
+    assert_eq!(rows.len(), 5);
+
+    // the second isn't detected, and with its own section the table wouldn't
+    // be found.
+    let options = ParseOptions {
+        layouts: &layouts[1..],
+        ..Default::default()
+    };
+    let (statement, _) =
+        parse_pdf_with(fixture.pdf(PASSWORD).unwrap(), &[PASSWORD], options).unwrap();
+    assert_eq!(statement.layout, "hdfc");
+    assert_eq!(layouts[1].layout.sections, ["Card Transactions"]);
+    assert_eq!(layouts[1].layout.rows, layout::Rows::Columns);
+}
+
+#[test]
+fn rows_can_be_read_a_page_at_a_time() {
+    // a table of one text per column, and one of rows that can go on over
+    // the next page.
+    for layout in [&layout::HDFC, &layout::TATA_NEU] {
+        let fixture = Fixture::sample(NAME, 40).in_layout(layout);
+        let pdf = fixture.pdf(PASSWORD).unwrap();
+        let (_, all) = parse_pdf(pdf.clone(), &[PASSWORD]).unwrap();
+
+        let passwords = [PASSWORD.to_owned()];
+        let mut rows = StatementIter::new(
+            "fixture.pdf".to_owned(),
+            pdf,
+            NAME.to_owned(),
+            &passwords,
+            ParseOptions::default(),
+        )
+        .unwrap();
+        let first: Vec<Transaction> = rows.by_ref().take(3).collect::<Result<_, _>>().unwrap();
+        let ids = |rows: &[Transaction]| rows.iter().map(Transaction::id).collect::<Vec<_>>();
+        assert_eq!(ids(&first), ids(&all[..3]), "{}", layout.name);
+        // the totals are those of the rows so far.
+        assert_eq!(rows.statement().count, 3);
+
+        // and the rest follow.
+        let rest: Vec<Transaction> = rows.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(ids(&rest), ids(&all[3..]), "{}", layout.name);
+        assert_eq!(rows.statement().count, 40);
+    }
+}
+
+#[derive(Default)]
+struct Events {
+    events: Vec<String>,
+    statement: Option<Statement>,
+}
+
+impl Visitor for Events {
+    fn on_section_start(&mut self, heading: &str, page: usize) {
+        self.events.push(format!("{} on page {}", heading, page));
+    }
+
+    fn on_transaction(&mut self, transaction: Transaction) {
+        self.events.push(transaction.id());
+    }
+
+    fn on_metadata(&mut self, statement: &Statement) {
+        self.statement = Some(statement.clone());
+    }
+}
+
+#[test]
+fn visitors_hear_of_sections_rows_and_details() {
+    let passwords = [PASSWORD.to_owned()];
+    for layout in [&layout::HDFC, &layout::TATA_NEU] {
+        let fixture = Fixture::sample(NAME, 30).in_layout(layout);
+        let pdf = fixture.pdf(PASSWORD).unwrap();
+        let (_, rows) = parse_pdf(pdf.clone(), &[PASSWORD]).unwrap();
+
+        let mut visitor = Events::default();
+        let statement = parse_visit(
+            "fixture.pdf".to_owned(),
+            pdf,
+            NAME.to_owned(),
+            &passwords,
+            ParseOptions::default(),
+            &mut visitor,
+        )
+        .unwrap();
+        // each page's table, then its rows.
+        let mut expected = vec!["Domestic Transactions on page 1".to_owned()];
+        expected.extend(rows[..25].iter().map(Transaction::id));
+        expected.push("Domestic Transactions on page 2".to_owned());
+        expected.extend(rows[25..].iter().map(Transaction::id));
+        assert_eq!(visitor.events, expected, "{}", layout.name);
+        assert_eq!(visitor.statement.unwrap().count, statement.count);
+    }
+
+    let fixture = Fixture::sample(NAME, 30);
+    let pdf = fixture.pdf(PASSWORD).unwrap();
+    let (_, rows) = parse_pdf(pdf.clone(), &[PASSWORD]).unwrap();
+
+    let mut ids = Vec::new();
+    parse_with(
+        "fixture.pdf".to_owned(),
+        pdf,
+        NAME.to_owned(),
+        &passwords,
+        ParseOptions::default(),
+        |transaction| ids.push(transaction.id()),
+    )
+    .unwrap();
+    assert_eq!(ids, rows.iter().map(Transaction::id).collect::<Vec<_>>());
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn statements_parse_from_async_code() {
+    use hdfc_cc_parser_rs::parse_file_async;
+    use tokio::sync::mpsc as tokio_mpsc;
+
+    let fixture = Fixture::sample(NAME, 30);
+    let path = std::env::temp_dir().join(format!("fixture-{}.pdf", std::process::id()));
+    fs::write(&path, fixture.pdf(PASSWORD).unwrap()).unwrap();
+    let path = path.to_str().unwrap().to_owned();
+    let parse = |path: String| async move {
+        // smaller than the statement, so the parser waits on the reader.
+        let (tx, mut rx) = tokio_mpsc::channel(4);
+        let read = tokio::spawn(async move {
+            let mut rows = Vec::new();
+            while let Some(row) = rx.recv().await {
+                rows.push(row);
+            }
+            rows
+        });
+        let passwords = vec![PASSWORD.to_owned()];
+        let statement = parse_file_async(
+            path,
+            NAME.to_owned(),
+            passwords,
+            ParseOptions::default(),
+            tx,
+        )
+        .await;
+        (statement, read.await.unwrap())
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let (statement, rows) = runtime.block_on(parse(path.clone()));
+    fs::remove_file(&path).unwrap();
+    assert_eq!(statement.unwrap().count, 30);
+    assert_eq!(rows.len(), 30);
+
+    let (statement, rows) = runtime.block_on(parse(path));
+    assert!(matches!(statement, Err(ParseError::Io { .. })));
+    assert!(rows.is_empty());
+}
@@ -0,0 +1,38 @@
+// Foreign amounts read off international descriptions, and reference rates.
+use chrono::NaiveDate;
+use hdfc_cc_parser_rs::fx::{foreign_amount, Rates};
+
+#[test]
+fn foreign_amounts_end_descriptions() {
+    assert_eq!(
+        foreign_amount("AMAZON WEB SERVICES SEATTLE USD 23.60"),
+        Some(("USD", 23.6))
+    );
+    assert_eq!(
+        foreign_amount("BOOKING.COM AMSTERDAM EUR1,204.50"),
+        Some(("EUR", 1204.5))
+    );
+    assert_eq!(
+        foreign_amount("7-ELEVEN TOKYO JPY 1500"),
+        Some(("JPY", 1500.0))
+    );
+    assert_eq!(foreign_amount("SWIGGY LIMITED BANGALORE"), None);
+    // a code in the merchant's name isn't a currency
+    assert_eq!(foreign_amount("USD STORE MUMBAI"), None);
+}
+
+#[test]
+fn rates_fall_back_to_the_last_published() {
+    let rates = Rates::read(
+        "date,currency,rate\n2025-01-03,USD,85.75\n2025-01-06,usd,85.90\n2025-01-06,EUR,88.70\n"
+            .as_bytes(),
+    )
+    .unwrap();
+    let day = |d| NaiveDate::from_ymd_opt(2025, 1, d).unwrap();
+    assert_eq!(rates.rate("USD", day(3)), Some(85.75));
+    // the weekend
+    assert_eq!(rates.rate("USD", day(5)), Some(85.75));
+    assert_eq!(rates.rate("USD", day(9)), Some(85.9));
+    assert_eq!(rates.rate("USD", day(2)), None);
+    assert_eq!(rates.rate("GBP", day(9)), None);
+}
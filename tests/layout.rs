@@ -0,0 +1,175 @@
+// Layouts: candidates scored by their markers, the best one winning, and
+// the rows they read.
+use hdfc_cc_parser_rs::bank;
+use hdfc_cc_parser_rs::layout::{self, Custom, Layout};
+
+fn texts(texts: &[&str]) -> Vec<String> {
+    texts.iter().map(|t| t.to_string()).collect()
+}
+
+const PATCHED: Layout = Layout {
+    name: "hdfc-patched",
+    sections: &["Card Transactions"],
+    ..layout::HDFC
+};
+
+const INFINIA: Layout = Layout {
+    name: "hdfc-infinia",
+    markers: &["Infinia Credit Card Statement"],
+    ..layout::HDFC
+};
+
+#[test]
+fn markers_on_the_first_page_score_higher() {
+    // Diners' marker only on the second page, CashBack's on the first.
+    let texts = texts(&["CashBack", "Domestic Transactions", "Diners Club"]);
+    let detection = bank::detect(&texts, 2, None, &[]);
+    assert_eq!(detection.layout.name, "hdfc-cashback");
+    assert_eq!(detection.score, Some(2));
+
+    // both on the first page: a tie, to the more specific.
+    let detection = bank::detect(&texts, 3, None, &[]);
+    assert_eq!(detection.layout.name, "diners");
+}
+
+#[test]
+fn ties_go_to_the_more_specific_layout() {
+    let texts = texts(&["Swiggy HDFC Bank", "CashBack Summary"]);
+    let detection = bank::detect(&texts, texts.len(), None, &[]);
+    assert_eq!(detection.layout.name, "swiggy");
+    assert_eq!(detection.parser.name(), "hdfc");
+}
+
+#[test]
+fn more_matched_markers_win() {
+    // every HDFC account marker, and Amex's.
+    let texts = texts(&[
+        "American Express",
+        "Withdrawal Amt.",
+        "Deposit Amt.",
+        "Closing Balance",
+    ]);
+    let detection = bank::detect(&texts, texts.len(), None, &[]);
+    assert_eq!(detection.layout.name, "hdfc-account");
+    assert_eq!(detection.score, Some(6));
+    assert_eq!(detection.layout.max_score(), 6);
+
+    // unless --bank says otherwise.
+    let detection = bank::detect(&texts, texts.len(), Some("amex"), &[]);
+    assert_eq!(detection.layout.name, "amex");
+}
+
+#[test]
+fn undetected_statements_fall_back() {
+    let texts = texts(&["Domestic Transactions"]);
+    let detection = bank::detect(&texts, texts.len(), None, &[]);
+    assert_eq!(detection.layout.name, "hdfc");
+    assert_eq!(detection.score, None);
+
+    // to a --layout layout without markers, when there's one.
+    let custom = [Custom {
+        bank: "hdfc",
+        layout: &PATCHED,
+    }];
+    let detection = bank::detect(&texts, texts.len(), None, &custom);
+    assert_eq!(detection.layout.name, "hdfc-patched");
+    assert_eq!(detection.score, None);
+}
+
+#[test]
+fn layout_files_win_ties() {
+    let drawn = texts(&["Infinia Credit Card Statement", "Diners Club"]);
+    let custom = [Custom {
+        bank: "hdfc",
+        layout: &INFINIA,
+    }];
+    let detection = bank::detect(&drawn, drawn.len(), None, &custom);
+    assert_eq!(detection.layout.name, "hdfc-infinia");
+    assert_eq!(detection.score, Some(2));
+
+    // and lose to better matches.
+    let reordered = texts(&["Diners Club", "Infinia Credit Card Statement"]);
+    let detection = bank::detect(&reordered, 1, None, &custom);
+    assert_eq!(detection.layout.name, "diners");
+}
+
+#[test]
+fn account_rows_are_signed_by_the_balance_they_leave() {
+    // the summary on the last page, its labels before their values.
+    let texts = texts(&[
+        "Date",
+        "Narration",
+        "Chq./Ref.No.",
+        "Value Dt",
+        "Withdrawal Amt.",
+        "Deposit Amt.",
+        "Closing Balance",
+        "01/04/24",
+        "SALARY APRIL",
+        "0000123",
+        "01/04/24",
+        "50,000.00",
+        "60,000.00",
+        "02/04/24",
+        "UPI-SWIGGY",
+        "0000456",
+        "02/04/24",
+        "500.00",
+        "59,500.00",
+        "STATEMENT SUMMARY",
+        "Opening Balance",
+        "Dr Count",
+        "Cr Count",
+        "Debits",
+        "Credits",
+        "Closing Bal",
+        "10,000.00",
+        "1",
+        "1",
+        "500.00",
+        "50,000.00",
+        "59,500.00",
+    ]);
+    let rows = layout::HDFC_ACCOUNT.rows(&texts, None);
+    let amounts: Vec<(f32, Option<f32>)> =
+        rows.iter().map(|row| (row.amount, row.balance)).collect();
+    // the first row a deposit, as the opening balance was less.
+    assert_eq!(amounts, [(50000.0, Some(60000.0)), (-500.0, Some(59500.0))]);
+}
+
+#[test]
+fn rows_carry_over_to_the_next_page() {
+    let texts = texts(&[
+        "Amount (in Rs.)",
+        "05/01",
+        "SWIGGY",
+        "BANGALORE",
+        "450.00",
+        "07/01",
+        "AMAZON",
+        // the second page.
+        "Amount (in Rs.)",
+        "1,200.00",
+        "08/01",
+        "PAYMENT RECEIVED",
+        "2,000.00 Cr",
+        "Reward Points Summary",
+    ]);
+    let end = chrono::NaiveDate::from_ymd_opt(2024, 1, 12);
+    let mut reader = layout::RowReader::new(&layout::HDFC_LEGACY, end);
+
+    // the row the first page ends on is held back.
+    let first = reader.read(&texts[..7]);
+    assert_eq!(first.len(), 1);
+    assert_eq!(first[0].tx, "SWIGGY BANGALORE");
+    assert_eq!(reader.held(), Some(5));
+
+    let mut rest = reader.read(&texts[7..]);
+    rest.extend(reader.finish());
+    let rows: Vec<(&str, f32)> = rest
+        .iter()
+        .map(|row| (row.tx.as_str(), row.amount))
+        .collect();
+    assert_eq!(rows, [("AMAZON", -1200.0), ("PAYMENT RECEIVED", 2000.0)]);
+    assert_eq!(reader.held(), None);
+}
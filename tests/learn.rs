@@ -0,0 +1,70 @@
+// Category rules learned from a labeled CSV, through `categorize learn`.
+use hdfc_cc_parser_rs::categories::Categories;
+use std::fs;
+use std::process::Command;
+
+const LABELED: &str = "\
+Description,Category
+SWIGGY LIMITED BANGALORE,Food
+SWIGGY INSTAMART BANGALORE,Food
+ZOMATO GURGAON,Food
+AMAZON PAY INDIA,Shopping
+AMAZON RETAIL,Shopping
+";
+
+// The category -> patterns learned from LABELED, with `args` added.
+fn learn(name: &str, args: &[&str]) -> Vec<(String, Vec<String>)> {
+    let dir = std::env::temp_dir();
+    let labeled = dir.join(format!("learn-{}-{}.csv", std::process::id(), name));
+    let rules = dir.join(format!("learn-{}-{}.json", std::process::id(), name));
+    fs::write(&labeled, LABELED).unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_hdfc-cc-parser-rs"))
+        .args([
+            "categorize",
+            "learn",
+            "--labeled",
+            labeled.to_str().unwrap(),
+        ])
+        .args(["--output", rules.to_str().unwrap()])
+        .args(args)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let categories = Categories::load(rules.to_str().unwrap()).unwrap();
+
+    fs::remove_file(labeled).unwrap();
+    fs::remove_file(rules).unwrap();
+    categories
+        .rules
+        .into_iter()
+        .map(|rule| (rule.category, rule.patterns))
+        .collect()
+}
+
+fn rule(category: &str, patterns: &[&str]) -> (String, Vec<String>) {
+    (
+        category.to_owned(),
+        patterns.iter().map(|p| p.to_string()).collect(),
+    )
+}
+
+#[test]
+fn tokens_of_a_single_row_are_not_rules() {
+    // ZOMATO and GURGAON are seen once, and the ZOMATO row goes without.
+    assert_eq!(
+        learn("default", &[]),
+        [rule("Food", &["SWIGGY"]), rule("Shopping", &["AMAZON"])]
+    );
+}
+
+#[test]
+fn the_least_support_can_be_lowered() {
+    assert_eq!(
+        learn("lowered", &["--min-support", "1"]),
+        [
+            rule("Food", &["SWIGGY", "ZOMATO"]),
+            rule("Shopping", &["AMAZON"])
+        ]
+    );
+}
@@ -0,0 +1,40 @@
+// --locale: amounts and points grouped the Indian or the US way.
+use hdfc_cc_parser_rs::locale::{self, Locale};
+
+#[test]
+fn amounts_are_grouped_for_the_locale() {
+    assert_eq!(Locale::Plain.amount(1234567.5), "1234567.50");
+    assert_eq!(Locale::EnIn.amount(1234567.5), "12,34,567.50");
+    assert_eq!(Locale::EnUs.amount(1234567.5), "1,234,567.50");
+    // the sign stays out of the groups.
+    assert_eq!(Locale::EnIn.amount(-123456.25), "-1,23,456.25");
+    assert_eq!(Locale::EnUs.amount(-123456.25), "-123,456.25");
+    assert_eq!(Locale::EnIn.amount(-1000.0), "-1,000.00");
+    assert_eq!(Locale::EnIn.amount(999.0), "999.00");
+    assert_eq!(Locale::EnIn.amount(0.0), "0.00");
+}
+
+#[test]
+fn amounts_that_are_not_numbers_are_left_alone() {
+    for locale in [Locale::Plain, Locale::EnIn, Locale::EnUs] {
+        assert_eq!(locale.amount(f32::NAN), "NaN");
+        assert_eq!(locale.amount(f32::INFINITY), "inf");
+        assert_eq!(locale.amount(f32::NEG_INFINITY), "-inf");
+    }
+}
+
+#[test]
+fn points_are_grouped_for_the_locale() {
+    assert_eq!(Locale::Plain.number(123456789), "123456789");
+    assert_eq!(Locale::EnIn.number(123456789), "12,34,56,789");
+    assert_eq!(Locale::EnUs.number(123456789), "123,456,789");
+    assert_eq!(Locale::EnIn.number(-100000), "-1,00,000");
+}
+
+#[test]
+fn locales_parse_with_either_separator() {
+    assert_eq!(locale::parse("en-IN"), Ok(Locale::EnIn));
+    assert_eq!(locale::parse("en_US"), Ok(Locale::EnUs));
+    assert_eq!(locale::parse("plain"), Ok(Locale::Plain));
+    assert!(locale::parse("fr-FR").is_err());
+}
@@ -0,0 +1,82 @@
+// Linking rows across a run: refunds to their purchases, EMI conversions.
+use chrono::{NaiveDate, NaiveDateTime};
+use hdfc_cc_parser_rs::{matching, Transaction};
+
+fn row(day: u32, tx: &str, amount: f32) -> Transaction {
+    Transaction {
+        date: date(day),
+        tx: tx.to_owned(),
+        amount,
+        ..Default::default()
+    }
+}
+
+fn date(day: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2024, 1, day)
+        .unwrap()
+        .and_hms_opt(12, 0, 0)
+        .unwrap()
+}
+
+#[test]
+fn refunds_link_to_their_purchase() {
+    let mut rows = vec![
+        row(2, "AMAZON PAY INDIA PRIVATE BANGALORE", -1200.0),
+        row(3, "AMAZON PAY INDIA PRIVATE BANGALORE", -499.0),
+        row(5, "SWIGGY LIMITED BANGALORE", -350.0),
+        row(9, "REFUND-AMAZON PAY INDIA PRIVATE", 499.0),
+        // partial
+        row(12, "REVERSAL SWIGGY LIMITED", 100.0),
+        // a payment isn't a refund
+        row(15, "NETBANKING TRANSFER (Ref# 00000000000009)", 2000.0),
+    ];
+    matching::refunds(&mut rows, 60);
+
+    let amazon = rows[1].id();
+    assert_eq!(rows[0].match_id, None);
+    assert_eq!(rows[1].match_id.as_ref(), Some(&amazon));
+    assert_eq!(rows[3].match_id.as_ref(), Some(&amazon));
+    let swiggy = rows[2].id();
+    assert_eq!(rows[4].match_id.as_ref(), Some(&swiggy));
+    assert_eq!(rows[5].match_id, None);
+}
+
+#[test]
+fn refunds_outside_the_window_stay_unlinked() {
+    let mut rows = vec![
+        row(1, "ZOMATO LTD GURGAON", -640.0),
+        row(20, "REFUND ZOMATO LTD", 640.0),
+        // for more than was spent
+        row(2, "IRCTC NEW DELHI", -100.0),
+        row(4, "REFUND IRCTC", 900.0),
+    ];
+    matching::refunds(&mut rows, 7);
+    assert!(rows.iter().all(|row| row.match_id.is_none()));
+}
+
+#[test]
+fn emi_conversions_group_purchase_reversal_and_installments() {
+    let mut rows = vec![
+        row(2, "AMAZON PAY INDIA PRIVATE BANGALORE", -24000.0),
+        row(3, "SWIGGY LIMITED BANGALORE", -350.0),
+        row(6, "EMI CONV AMAZON PAY INDIA PRIVATE", 24000.0),
+        row(10, "EMI PRINCIPAL 1/12 AMAZON PAY INDIA PRIVATE", -2000.0),
+        row(10, "EMI INTEREST 1/12 AMAZON PAY INDIA PRIVATE", -310.5),
+        // of a purchase not converted in this run
+        row(10, "EMI PRINCIPAL 4/6 CROMA MUMBAI", -1500.0),
+    ];
+    matching::emi(&mut rows);
+
+    let group = rows[0].id();
+    for i in [0, 2, 3, 4] {
+        assert_eq!(rows[i].emi_group.as_ref(), Some(&group));
+    }
+    assert_eq!(rows[1].emi_group, None);
+    assert_eq!(rows[5].emi_group, None);
+    assert!(matching::emi_installment(&rows[3]));
+    assert!(!matching::emi_installment(&rows[0]));
+
+    // and the reversal isn't a refund
+    matching::refunds(&mut rows, 60);
+    assert!(rows.iter().all(|row| row.match_id.is_none()));
+}
@@ -0,0 +1,322 @@
+// The output formats: a purchase and a payment written out, checking which
+// way round each format signs them and where the columns go.
+use chrono::{NaiveDate, NaiveDateTime};
+use hdfc_cc_parser_rs::categories::merchant_key;
+use hdfc_cc_parser_rs::output::{self, Options, SignConvention};
+use hdfc_cc_parser_rs::Transaction;
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+const PURCHASE: &str = "SWIGGY LIMITED BANGALORE";
+const PAYMENT: &str = "NETBANKING TRANSFER (Ref# 00000000000009)";
+
+fn date(day: u32, hour: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2025, 1, day)
+        .unwrap()
+        .and_hms_opt(hour, 30, 0)
+        .unwrap()
+}
+
+fn rows() -> [Transaction; 2] {
+    [
+        Transaction {
+            date: date(5, 10),
+            tx: PURCHASE.to_owned(),
+            points: 12,
+            amount: -1250.5,
+            category: Some("Food".to_owned()),
+            tags: vec!["work".to_owned()],
+            ..Default::default()
+        },
+        Transaction {
+            date: date(10, 9),
+            tx: PAYMENT.to_owned(),
+            amount: 5000.0,
+            ..Default::default()
+        },
+    ]
+}
+
+// output::new wants the writer for good, this one keeps the bytes reachable.
+#[derive(Clone, Default)]
+struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Buffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn written(format: &str, options: Options) -> String {
+    let buffer = Buffer::default();
+    let mut out = output::new(format, buffer.clone(), options).unwrap();
+    for row in rows() {
+        out.write(&row).unwrap();
+    }
+    out.finish().unwrap();
+    drop(out);
+    String::from_utf8(buffer.0.take()).unwrap()
+}
+
+fn lines(format: &str) -> Vec<String> {
+    written(format, Options::default())
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}
+
+fn ids() -> [String; 2] {
+    rows().map(|row| row.id())
+}
+
+#[test]
+fn csv_amounts_follow_the_sign_convention() {
+    let csv = |sign_convention| {
+        written(
+            "csv",
+            Options {
+                add_headers: true,
+                sign_convention,
+                ..Default::default()
+            },
+        )
+    };
+    assert_eq!(
+        csv(SignConvention::DebitNegative),
+        format!(
+            "Date,Description,Points,Amount\n\
+             2025-01-05T10:30:00+05:30,{PURCHASE},12,-1250.5\n\
+             2025-01-10T09:30:00+05:30,{PAYMENT},0,5000\n"
+        )
+    );
+    assert_eq!(
+        csv(SignConvention::DebitPositive),
+        format!(
+            "Date,Description,Points,Amount\n\
+             2025-01-05T10:30:00+05:30,{PURCHASE},12,1250.5\n\
+             2025-01-10T09:30:00+05:30,{PAYMENT},0,-5000\n"
+        )
+    );
+    assert_eq!(
+        csv(SignConvention::SeparateColumns),
+        format!(
+            "Date,Description,Points,Debit,Credit\n\
+             2025-01-05T10:30:00+05:30,{PURCHASE},12,1250.50,\n\
+             2025-01-10T09:30:00+05:30,{PAYMENT},0,,5000.00\n"
+        )
+    );
+}
+
+#[test]
+fn qif_records_are_signed_as_parsed() {
+    assert_eq!(
+        written("qif", Options::default()),
+        format!(
+            "!Type:CCard\n\
+             D01/05/2025\nT-1250.50\nP{PURCHASE}\nMpoints 12; tags work\nLFood\n^\n\
+             D01/10/2025\nT5000.00\nP{PAYMENT}\n^\n"
+        )
+    );
+}
+
+#[test]
+fn ofx_transactions_are_debits_and_credits() {
+    let ofx = written("ofx", Options::default());
+    let [purchase, payment] = ids();
+    assert!(ofx.contains(&format!(
+        "<STMTTRN>\n<TRNTYPE>DEBIT</TRNTYPE>\n\
+         <DTPOSTED>20250105103000[+5.30:IST]</DTPOSTED>\n\
+         <TRNAMT>-1250.50</TRNAMT>\n<FITID>{purchase}</FITID>\n\
+         <NAME>{PURCHASE}</NAME>\n<MEMO>{PURCHASE}</MEMO>\n</STMTTRN>\n"
+    )));
+    // the name is cut to 32 characters, the memo has it all.
+    assert!(ofx.contains(&format!(
+        "<STMTTRN>\n<TRNTYPE>CREDIT</TRNTYPE>\n\
+         <DTPOSTED>20250110093000[+5.30:IST]</DTPOSTED>\n\
+         <TRNAMT>5000.00</TRNAMT>\n<FITID>{payment}</FITID>\n\
+         <NAME>NETBANKING TRANSFER (Ref# 000000</NAME>\n<MEMO>{PAYMENT}</MEMO>\n</STMTTRN>\n"
+    )));
+    assert!(ofx.contains(
+        "<DTSTART>20250105103000[+5.30:IST]</DTSTART>\n\
+         <DTEND>20250110093000[+5.30:IST]</DTEND>\n"
+    ));
+}
+
+#[test]
+fn ynab_splits_outflows_from_inflows() {
+    assert_eq!(
+        lines("ynab"),
+        [
+            "Date,Payee,Memo,Outflow,Inflow".to_owned(),
+            format!("01/05/2025,{PURCHASE},\"Food, work\",1250.50,"),
+            format!("01/10/2025,{PAYMENT},,,5000.00"),
+        ]
+    );
+}
+
+#[test]
+fn actual_outflows_are_negative() {
+    let [purchase, payment] = ids();
+    assert_eq!(
+        lines("actual"),
+        [
+            "date,payee,notes,category,amount,imported_id".to_owned(),
+            format!("2025-01-05,{PURCHASE},work,Food,-1250.50,{purchase}"),
+            format!("2025-01-10,{PAYMENT},,,5000.00,{payment}"),
+        ]
+    );
+}
+
+#[test]
+fn camt053_entries_carry_their_direction() {
+    let camt = written("camt053", Options::default());
+    let [purchase, payment] = ids();
+    assert!(camt.contains(&format!(
+        "<Ntry>\n<NtryRef>{purchase}</NtryRef>\n\
+         <Amt Ccy=\"INR\">1250.50</Amt><CdtDbtInd>DBIT</CdtDbtInd>\n\
+         <Sts>BOOK</Sts>\n<BookgDt><Dt>2025-01-05</Dt></BookgDt>\n"
+    )));
+    assert!(camt.contains(&format!(
+        "<Ntry>\n<NtryRef>{payment}</NtryRef>\n\
+         <Amt Ccy=\"INR\">5000.00</Amt><CdtDbtInd>CRDT</CdtDbtInd>\n\
+         <Sts>BOOK</Sts>\n<BookgDt><Dt>2025-01-10</Dt></BookgDt>\n"
+    )));
+    // without running balances the statement opens at zero.
+    assert!(camt.contains(
+        "<Cd>OPBD</Cd></CdOrPrtry></Tp><Amt Ccy=\"INR\">0.00</Amt>\
+         <CdtDbtInd>CRDT</CdtDbtInd><Dt><Dt>2025-01-05</Dt></Dt>"
+    ));
+    assert!(camt.contains(
+        "<Cd>CLBD</Cd></CdOrPrtry></Tp><Amt Ccy=\"INR\">3749.50</Amt>\
+         <CdtDbtInd>CRDT</CdtDbtInd><Dt><Dt>2025-01-10</Dt></Dt>"
+    ));
+}
+
+#[test]
+fn mt940_lines_mark_debits_and_credits() {
+    let message = written("mt940", Options::default());
+    let [purchase, payment] = ids();
+    assert!(message.ends_with("\r\n"));
+    // past the reference, account and statement number.
+    let lines: Vec<&str> = message.split("\r\n").skip(3).collect();
+    assert_eq!(
+        lines,
+        [
+            ":60F:C250105INR0,00".to_owned(),
+            format!(":61:2501050105D1250,50NMSCNONREF//{purchase}"),
+            format!(":86:{PURCHASE}"),
+            format!(":61:2501100110C5000,00NMSCNONREF//{payment}"),
+            // '#' isn't in the SWIFT character set.
+            ":86:NETBANKING TRANSFER (Ref  00000000000009)".to_owned(),
+            ":62F:C250110INR3749,50".to_owned(),
+            "-".to_owned(),
+            String::new(),
+        ]
+    );
+}
+
+#[test]
+fn mt940_continuation_lines_do_not_start_with_a_dash() {
+    // the 65 characters of the first :86: line end right before the '-'.
+    let description = format!("{}-REF 1234", "A".repeat(61));
+    let buffer = Buffer::default();
+    let mut out = output::new("mt940", buffer.clone(), Options::default()).unwrap();
+    let row = Transaction {
+        tx: description.clone(),
+        ..rows()[0].clone()
+    };
+    out.write(&row).unwrap();
+    out.finish().unwrap();
+    drop(out);
+    let message = String::from_utf8(buffer.0.take()).unwrap();
+    let lines: Vec<&str> = message.split("\r\n").collect();
+    let info = lines
+        .iter()
+        .position(|line| line.starts_with(":86:"))
+        .unwrap();
+    assert_eq!(lines[info], format!(":86:{}", &description[..61]));
+    assert_eq!(lines[info + 1], " -REF 1234");
+    assert!(lines[info + 2].starts_with(":62F:"));
+}
+
+#[test]
+fn tally_vouchers_debit_the_right_ledger() {
+    let xml = written("tally-xml", Options::default());
+    let entry = |ledger, positive, amount| {
+        format!(
+            "<ALLLEDGERENTRIES.LIST><LEDGERNAME>{ledger}</LEDGERNAME>\
+             <ISDEEMEDPOSITIVE>{positive}</ISDEEMEDPOSITIVE><AMOUNT>{amount}</AMOUNT>\
+             </ALLLEDGERENTRIES.LIST>\n"
+        )
+    };
+    let [purchase, payment] = ids();
+    assert!(xml.starts_with("<ENVELOPE>\n"));
+    assert!(xml.ends_with("</REQUESTDATA>\n</IMPORTDATA></BODY>\n</ENVELOPE>\n"));
+    assert!(xml.contains(&format!(
+        "<VOUCHER VCHTYPE=\"Payment\" ACTION=\"Create\">\n<DATE>20250105</DATE>\n\
+         <VOUCHERTYPENAME>Payment</VOUCHERTYPENAME>\n<REFERENCE>{purchase}</REFERENCE>\n\
+         <NARRATION>{PURCHASE}</NARRATION>\n{}{}</VOUCHER>\n",
+        entry("Suspense", "Yes", "-1250.50"),
+        entry("HDFC Credit Card", "No", "1250.50"),
+    )));
+    assert!(xml.contains(&format!(
+        "<VOUCHER VCHTYPE=\"Receipt\" ACTION=\"Create\">\n<DATE>20250110</DATE>\n\
+         <VOUCHERTYPENAME>Receipt</VOUCHERTYPENAME>\n<REFERENCE>{payment}</REFERENCE>\n\
+         <NARRATION>{PAYMENT}</NARRATION>\n{}{}</VOUCHER>\n",
+        entry("HDFC Credit Card", "Yes", "-5000.00"),
+        entry("Suspense", "No", "5000.00"),
+    )));
+}
+
+#[test]
+fn zohobooks_splits_withdrawals_from_deposits() {
+    let [purchase, payment] = ids();
+    assert_eq!(
+        lines("zohobooks"),
+        [
+            "Date,Withdrawals,Deposits,Payee,Description,Reference Number".to_owned(),
+            format!(
+                "05/01/2025,1250.50,,{},{PURCHASE},{purchase}",
+                merchant_key(PURCHASE)
+            ),
+            format!(
+                "10/01/2025,,5000.00,{},{PAYMENT},{payment}",
+                merchant_key(PAYMENT)
+            ),
+        ]
+    );
+}
+
+#[test]
+fn quickbooks_credits_come_before_debits() {
+    assert_eq!(
+        lines("quickbooks"),
+        [
+            "Date,Description,Credit,Debit".to_owned(),
+            format!("05/01/2025,{PURCHASE},,1250.50"),
+            format!("10/01/2025,{PAYMENT},5000.00,"),
+        ]
+    );
+}
+
+#[test]
+fn gnucash_charges_are_withdrawals_from_the_card() {
+    let [purchase, payment] = ids();
+    assert_eq!(
+        lines("gnucash"),
+        [
+            "Date,Number,Description,Notes,Account,Deposit,Withdrawal,Transfer Account".to_owned(),
+            format!(
+                "2025-01-05,{purchase},{PURCHASE},work,Liabilities:Credit Card:HDFC,,1250.50,Imbalance-INR"
+            ),
+            format!(
+                "2025-01-10,{payment},{PAYMENT},,Liabilities:Credit Card:HDFC,5000.00,,Imbalance-INR"
+            ),
+        ]
+    );
+}
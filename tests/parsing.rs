@@ -0,0 +1,144 @@
+// Property tests of the amount, points and row date parsing every layout
+// relies on, over the ways statements write them.
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use hdfc_cc_parser_rs::layout::{self, Layout};
+use hdfc_cc_parser_rs::locale::Locale;
+use hdfc_cc_parser_rs::statement::{parse_amount, parse_points};
+use proptest::prelude::*;
+
+const LAYOUTS: [&Layout; 10] = [
+    &layout::HDFC,
+    &layout::HDFC_LEGACY,
+    &layout::DINERS,
+    &layout::CASHBACK,
+    &layout::TATA_NEU,
+    &layout::SWIGGY,
+    &layout::SBI,
+    &layout::AMEX,
+    &layout::HDFC_ACCOUNT,
+    &layout::ICICI,
+];
+
+// `digits` grouped in thousands, or Indian style: the last three digits,
+// then twos ("1,23,456").
+fn group(digits: &str, separator: char, indian: bool) -> String {
+    let mut groups = Vec::new();
+    let mut rest = digits;
+    let mut size = 3;
+    while rest.len() > size {
+        let (head, tail) = rest.split_at(rest.len() - size);
+        groups.push(tail);
+        rest = head;
+        if indian {
+            size = 2;
+        }
+    }
+    groups.push(rest);
+    groups.reverse();
+    groups.join(&separator.to_string())
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() <= 0.01 + b.abs() * 1e-6
+}
+
+fn date() -> impl Strategy<Value = NaiveDate> {
+    // years %y reads back the same.
+    (0..60 * 365i64)
+        .prop_map(|days| NaiveDate::from_ymd_opt(2000, 1, 1).unwrap() + Duration::days(days))
+}
+
+proptest! {
+    #[test]
+    fn amounts_round_trip(
+        rupees in 0u64..100_000_000,
+        paise in 0u32..100,
+        separator in prop::sample::select(vec![',', '\u{a0}', '\u{202f}']),
+        indian: bool,
+        sign in prop::sample::select(vec!["", "₹", "₹ ", "Rs.", "Rs. ", "INR ", "`", "+"]),
+        mark in prop::sample::select(vec!["", " Cr", "Cr", " CR", " (Cr)", " Dr", " DR"]),
+    ) {
+        let text = format!(
+            "{}{}.{:02}{}",
+            sign,
+            group(&rupees.to_string(), separator, indian),
+            paise,
+            mark
+        );
+        let expected = rupees as f32 + paise as f32 / 100.0;
+        let expected = if mark.contains('C') { -expected } else { expected };
+        let amount = parse_amount(&text);
+        prop_assert!(amount.is_some_and(|amount| close(amount, expected)), "{:?} -> {:?}", text, amount);
+        // statements pad texts with spaces.
+        prop_assert_eq!(parse_amount(&format!("  {} ", text)), amount);
+    }
+
+    #[test]
+    fn formatted_amounts_round_trip(amount in -1e7f32..1e7) {
+        let parsed = parse_amount(&format!("{:.2}", amount));
+        prop_assert!(parsed.is_some_and(|parsed| close(parsed, amount)), "{} -> {:?}", amount, parsed);
+    }
+
+    // what --locale writes reads back.
+    #[test]
+    fn localized_amounts_round_trip(
+        amount in -1e7f32..1e7,
+        locale in prop::sample::select(vec![Locale::Plain, Locale::EnIn, Locale::EnUs]),
+    ) {
+        let text = locale.amount(amount);
+        let parsed = parse_amount(&text);
+        prop_assert!(parsed.is_some_and(|parsed| close(parsed, amount)), "{:?} -> {:?}", text, parsed);
+    }
+
+    // without a decimal point a number is points, or a reference number.
+    #[test]
+    fn whole_numbers_are_not_amounts(n in any::<u32>()) {
+        prop_assert_eq!(parse_amount(&n.to_string()), None);
+        prop_assert_eq!(parse_amount(&format!("{} Cr", n)), None);
+    }
+
+    #[test]
+    fn amounts_never_panic(text in "\\PC*") {
+        parse_amount(&text);
+        parse_points(&text);
+    }
+
+    #[test]
+    fn points_round_trip(points in -1_000_000i32..1_000_000, indian: bool) {
+        let digits = group(&points.unsigned_abs().to_string(), ',', indian);
+        for sign in if points < 0 { ["-", "- "] } else { ["", "+"] } {
+            let text = format!("{}{}", sign, digits);
+            prop_assert_eq!(parse_points(&text), Some(points), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn dates_round_trip(date in date(), seconds in 0..86_400u32) {
+        let time = NaiveDateTime::new(date, chrono::NaiveTime::from_num_seconds_from_midnight_opt(seconds, 0).unwrap());
+        for layout in LAYOUTS {
+            for format in layout.date_formats.iter().filter(|f| f.contains("%Y") || f.contains("%y")) {
+                let expected = if format.contains("%H") { time } else { date.and_hms_opt(0, 0, 0).unwrap() };
+                let text = expected.format(format).to_string();
+                prop_assert_eq!(layout.parse_date(&text, None), Some(expected), "{} {:?}", layout.name, text);
+                prop_assert_eq!(layout.parse_date(&format!(" {} ", text), None), Some(expected));
+            }
+        }
+    }
+
+    // Dates without a year are the latest one on or before the period's end.
+    #[test]
+    fn dates_without_year_take_the_period(end in date(), before in 0..365i64) {
+        let date = end - Duration::days(before);
+        for layout in LAYOUTS {
+            for format in layout.date_formats.iter().filter(|f| !f.contains("%Y") && !f.contains("%y")) {
+                let text = date.format(format).to_string();
+                prop_assert_eq!(
+                    layout.parse_date(&text, Some(end)),
+                    Some(date.and_hms_opt(0, 0, 0).unwrap()),
+                    "{} {:?} {}", layout.name, text, end
+                );
+                prop_assert_eq!(layout.parse_date(&text, None), None);
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+// Statement passwords derived from --password-pattern and --dob.
+use chrono::NaiveDate;
+use hdfc_cc_parser_rs::password::{derive, KNOWN_PATTERNS};
+
+const NAME: &str = "John O'Neil";
+
+fn dob() -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(1990, 1, 5)
+}
+
+#[test]
+fn placeholders_fill_in_the_name_and_date_of_birth() {
+    let passwords: Vec<String> = KNOWN_PATTERNS
+        .iter()
+        .map(|pattern| derive(pattern, NAME, dob()).unwrap())
+        .collect();
+    assert_eq!(
+        passwords,
+        ["JOHN0501", "john0501", "JOHN050190", "05011990"]
+    );
+
+    // letters only, literal text kept, and dob:%d%m without a format.
+    assert_eq!(
+        derive("{NAME:6}-{dob}", NAME, dob()).unwrap(),
+        "JOHNON-0501"
+    );
+    assert_eq!(derive("{name:20}", NAME, None).unwrap(), "johnoneil");
+}
+
+#[test]
+fn malformed_patterns_are_errors() {
+    for pattern in [
+        "{NAME:4",
+        "{name:four}",
+        "{pan:4}",
+        // neither a chrono specifier, nor a field a date has.
+        "{dob:%Q}",
+        "{dob:%H%M}",
+    ] {
+        assert!(derive(pattern, NAME, dob()).is_err(), "{}", pattern);
+    }
+    // the date of birth, when the pattern has it.
+    assert!(derive("{NAME:4}{dob:%d%m}", NAME, None).is_err());
+}
@@ -0,0 +1,56 @@
+// The points ledger across statements' points summaries.
+use chrono::NaiveDate;
+use hdfc_cc_parser_rs::points::{Batch, Ledger};
+use hdfc_cc_parser_rs::statement::Statement;
+
+fn date(year: i32, month: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, 15).unwrap()
+}
+
+fn statement(month: u32, points: [i32; 4]) -> Statement {
+    Statement {
+        statement_date: Some(date(2024, month)),
+        points_opening: Some(points[0]),
+        points_earned: Some(points[1]),
+        points_redeemed: Some(points[2]),
+        points_closing: Some(points[3]),
+        ..Statement::new("statement.pdf")
+    }
+}
+
+#[test]
+fn points_are_spent_oldest_first() {
+    let ledger = Ledger::new(&[
+        // out of order
+        statement(3, [1700, 300, 1000, 1000]),
+        statement(1, [500, 1200, 0, 1700]),
+        // 200 expired
+        statement(5, [1000, 400, 0, 1200]),
+    ]);
+    assert_eq!(ledger.earned, 1900);
+    assert_eq!(ledger.redeemed, 1000);
+    assert_eq!(ledger.expired, 200);
+    assert_eq!(ledger.balance, 1200);
+    assert_eq!(ledger.as_of, Some(date(2024, 5)));
+    assert_eq!(
+        ledger.batches,
+        vec![
+            Batch {
+                earned_on: date(2024, 1),
+                points: 500
+            },
+            Batch {
+                earned_on: date(2024, 3),
+                points: 300
+            },
+            Batch {
+                earned_on: date(2024, 5),
+                points: 400
+            },
+        ]
+    );
+
+    let expiring = ledger.expiring(24, date(2026, 4));
+    assert_eq!(expiring.len(), 2);
+    assert_eq!(expiring[0].0, date(2026, 1));
+}
@@ -0,0 +1,70 @@
+// --redact: card and account numbers, the cardholder's name and, with
+// --redact-merchants, the merchants masked.
+use hdfc_cc_parser_rs::redact::Redactor;
+use hdfc_cc_parser_rs::statement::Statement;
+use hdfc_cc_parser_rs::Transaction;
+
+fn row(tx: &str) -> Transaction {
+    Transaction {
+        tx: tx.to_owned(),
+        statement_file: "statements/John Doe 2025-01.pdf".to_owned(),
+        card: Some("4321XXXXXXXX9876".to_owned()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn numbers_keep_their_last_four_digits() {
+    let redactor = Redactor::new("JOHN DOE", false);
+    let mut transaction = row("NEFT CR-HDFC0000001-JOHN DOE-ACC 5012 3456 7890 1234");
+    redactor.transaction(&mut transaction);
+    // the IFSC is too short to be an account number.
+    assert_eq!(
+        transaction.tx,
+        "NEFT CR-HDFC0000001-XXXX XXXX-ACC XXXX XXXX XXXX 1234"
+    );
+    assert_eq!(transaction.card.as_deref(), Some("XXXX XXXX XXXX 9876"));
+    // the name in any case.
+    assert_eq!(
+        transaction.statement_file,
+        "statements/XXXX XXXX 2025-01.pdf"
+    );
+
+    let mut statement = Statement::new("statements/John Doe 2025-01.pdf");
+    statement.card_number = Some("4321 56XX XXXX 9876".to_owned());
+    statement.account_number = Some("50100123456789".to_owned());
+    redactor.statement(&mut statement);
+    assert_eq!(statement.file, "statements/XXXX XXXX 2025-01.pdf");
+    assert_eq!(
+        statement.card_number.as_deref(),
+        Some("XXXX XXXX XXXX 9876")
+    );
+    assert_eq!(
+        statement.account_number.as_deref(),
+        Some("XXXX XXXX XXXX 6789")
+    );
+}
+
+#[test]
+fn masked_runs_and_short_words_are_left_alone() {
+    // words of two letters or less would mask half the descriptions.
+    let redactor = Redactor::new("JO DOE", false);
+    let mut transaction = row("UPI-JO DOE-XXXXXXXXXXXXXXXX-PAYMENT");
+    redactor.transaction(&mut transaction);
+    assert_eq!(transaction.tx, "UPI-JO XXXX-XXXXXXXXXXXXXXXX-PAYMENT");
+}
+
+#[test]
+fn merchants_become_a_hash_of_their_merchant() {
+    let redactor = Redactor::new("JOHN DOE", true);
+    let redacted = |tx| {
+        let mut transaction = row(tx);
+        redactor.transaction(&mut transaction);
+        transaction.tx
+    };
+    let swiggy = redacted("SWIGGY LIMITED BANGALORE");
+    assert!(swiggy.starts_with("MERCHANT "));
+    assert_eq!(swiggy.len(), "MERCHANT ".len() + 8);
+    assert_eq!(redacted("SWIGGY LIMITED BANGALORE"), swiggy);
+    assert_ne!(redacted("AMAZON PAY INDIA PRIVATE BANGALORE"), swiggy);
+}
@@ -0,0 +1,33 @@
+// Statement details read out of the text drawn on a statement's pages.
+use hdfc_cc_parser_rs::statement::Statement;
+
+fn texts(texts: &[&str]) -> Vec<String> {
+    texts.iter().map(|t| t.to_string()).collect()
+}
+
+#[test]
+fn past_dues_land_in_the_bucket_their_label_goes_up_to() {
+    let mut statement = Statement::new("statement.pdf");
+    statement.read_details(&texts(&[
+        "Past Dues (If any)",
+        "Current Dues",
+        "0-30 Days",
+        "31-60 Days",
+        "61-90 Days",
+        "90+ Days",
+        "1,000.00",
+        "200.00",
+        "30.00",
+        "4.00",
+        "0.50",
+    ]));
+    let dues = statement.past_dues.unwrap();
+    assert_eq!(dues.current, 1000.0);
+    assert_eq!(dues.days_30, 200.0);
+    assert_eq!(dues.days_60, 30.0);
+    assert_eq!(dues.days_90_plus, 4.5);
+    // besides the card number and so on, which aren't drawn here.
+    assert!(statement
+        .warnings
+        .contains(&"234.50 past due beyond the current cycle".to_owned()));
+}